@@ -0,0 +1,70 @@
+//! Commit benchmark: `DatabaseCommit::commit` used to re-enter the tokio
+//! runtime once per changed storage slot; it now takes the storage lock once
+//! per account. A block with many storage writes is where that difference
+//! shows. Note that commit no longer needs a runtime at all.
+//!
+//! Run with `cargo bench --bench commit --features replay`.
+
+use std::sync::Arc;
+
+use alloy_primitives::{Address, U256};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use revm::state::{Account, AccountInfo, AccountStatus, EvmStorageSlot};
+use revm::DatabaseCommit;
+
+use megaviz_api::replay::SmartCacheDB;
+use megaviz_api::rpc::MegaEthClient;
+
+const ACCOUNTS: usize = 50;
+const SLOTS_PER_ACCOUNT: usize = 40;
+
+fn cache_db() -> SmartCacheDB {
+    // Point RocksDB at a scratch directory so runs don't touch real caches
+    let dir = std::env::temp_dir().join(format!("megaviz-commit-bench-{}", std::process::id()));
+    std::env::set_var("CACHE_DB_PATH", &dir);
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let client = rt
+        .block_on(MegaEthClient::new("http://localhost:1"))
+        .unwrap();
+    SmartCacheDB::new(Arc::new(client)).unwrap()
+}
+
+/// A change set shaped like a storage-heavy block: every slot changed
+fn changes(accounts: usize, slots: usize) -> revm::primitives::HashMap<Address, Account> {
+    let mut map = revm::primitives::HashMap::default();
+    for a in 0..accounts {
+        let mut storage = revm::primitives::HashMap::default();
+        for s in 0..slots {
+            storage.insert(
+                U256::from(s),
+                EvmStorageSlot::new_changed(U256::ZERO, U256::from(s + 1), 0),
+            );
+        }
+        map.insert(
+            Address::with_last_byte(a as u8 + 1),
+            Account {
+                info: AccountInfo::default(),
+                storage,
+                status: AccountStatus::Touched,
+            },
+        );
+    }
+    map
+}
+
+fn bench_commit(c: &mut Criterion) {
+    let mut db = cache_db();
+    let change_set = changes(ACCOUNTS, SLOTS_PER_ACCOUNT);
+
+    c.bench_function("commit_storage_heavy_block", |b| {
+        b.iter_batched(
+            || change_set.clone(),
+            |changes| db.commit(changes),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_commit);
+criterion_main!(benches);
@@ -0,0 +1,108 @@
+//! DA-size benchmark: FastLZ compression is the dominant per-block CPU cost,
+//! and the calculator memoizes compressed lengths by input hash. Repetitive
+//! calldata (the common case: many transactions hitting the same contract
+//! entry points) should process far faster than unique calldata.
+//!
+//! Run with `cargo bench --bench da_size`.
+
+use alloy_primitives::{Address, Bytes, B256, U256};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use megaviz_api::processor::MetricsCalculator;
+use megaviz_api::rpc::{RawBlock, RawReceipt, RawTransaction};
+
+const TXS_PER_BLOCK: u64 = 200;
+const CALLDATA_BYTES: usize = 4096;
+
+/// A transaction whose calldata is derived from `seed`; equal seeds produce
+/// identical inputs, so the DA cache can hit
+fn tx(index: u64, seed: u64) -> RawTransaction {
+    let mut input = vec![0u8; CALLDATA_BYTES];
+    for (i, byte) in input.iter_mut().enumerate() {
+        *byte = (seed as usize).wrapping_mul(31).wrapping_add(i / 32) as u8;
+    }
+
+    RawTransaction {
+        hash: B256::with_last_byte(index as u8),
+        from: Address::with_last_byte(1),
+        to: Some(Address::with_last_byte(2)),
+        input: Bytes::from(input),
+        gas: 100_000,
+        tx_type: 2,
+        nonce: index,
+        value: U256::ZERO,
+        gas_price: None,
+        max_fee_per_gas: Some(1_000_000_000),
+        max_priority_fee_per_gas: Some(1_000_000),
+        chain_id: Some(6342),
+        v: 0,
+        r: U256::from(1),
+        s: U256::from(1),
+        access_list: Vec::new(),
+        blob_versioned_hashes: Vec::new(),
+        max_fee_per_blob_gas: None,
+    }
+}
+
+fn receipts(block: &RawBlock) -> Vec<RawReceipt> {
+    block
+        .transactions
+        .iter()
+        .map(|tx| RawReceipt {
+            transaction_hash: tx.hash,
+            gas_used: 60_000,
+            status: true,
+            contract_address: None,
+            from: tx.from,
+            effective_gas_price: Some(1_000_000_000),
+        })
+        .collect()
+}
+
+/// A block whose transactions cycle through `shapes` distinct calldata shapes
+fn block(shapes: u64) -> RawBlock {
+    RawBlock {
+        number: 1,
+        hash: B256::with_last_byte(1),
+        parent_hash: B256::ZERO,
+        base_fee_per_gas: None,
+        gas_used: 0,
+        gas_limit: 30_000_000,
+        timestamp: 1_700_000_000,
+        extra_data: Bytes::new(),
+        mini_block_count: 0,
+        mini_block_gas: Vec::new(),
+        transactions: (0..TXS_PER_BLOCK).map(|i| tx(i, i % shapes)).collect(),
+    }
+}
+
+fn bench_process_block(c: &mut Criterion) {
+    let mut group = c.benchmark_group("process_block");
+
+    // Every transaction unique: the cache can't help, this is the baseline
+    let unique = block(TXS_PER_BLOCK);
+    let unique_receipts = receipts(&unique);
+    group.bench_function("unique_calldata", |b| {
+        b.iter_batched(
+            MetricsCalculator::new,
+            |calc| calc.process_block(&unique, &unique_receipts).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+
+    // Four calldata shapes across the block: most lookups hit the cache
+    let repetitive = block(4);
+    let repetitive_receipts = receipts(&repetitive);
+    group.bench_function("repetitive_calldata", |b| {
+        b.iter_batched(
+            MetricsCalculator::new,
+            |calc| calc.process_block(&repetitive, &repetitive_receipts).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_process_block);
+criterion_main!(benches);
@@ -0,0 +1,90 @@
+//! Minimal ABI decoding helpers shared by the identification tooling
+
+use anyhow::{bail, Result};
+
+/// Decode a string from `eth_call` return data
+///
+/// Handles the standard dynamic-string encoding (offset word, length word,
+/// UTF-8 data) as well as the non-standard right-padded `bytes32` returned
+/// by older tokens (MKR, SAI, etc.)
+pub fn decode_string(data: &[u8]) -> Result<String> {
+    if data.is_empty() {
+        bail!("Empty return data");
+    }
+
+    // Non-standard bytes32 symbol/name: a single word, right-padded with zeros
+    if data.len() == 32 {
+        let end = data.iter().position(|&b| b == 0).unwrap_or(32);
+        return Ok(String::from_utf8_lossy(&data[..end]).to_string());
+    }
+
+    if data.len() < 64 {
+        bail!("Return data too short for a dynamic string: {} bytes", data.len());
+    }
+
+    // Dynamic string: the first word points at the length word
+    let offset = read_usize_word(&data[..32])?;
+    if offset.checked_add(32).is_none_or(|end| end > data.len()) {
+        bail!("String offset {} out of bounds", offset);
+    }
+
+    let length = read_usize_word(&data[offset..offset + 32])?;
+    let start = offset + 32;
+    if start.checked_add(length).is_none_or(|end| end > data.len()) {
+        bail!("String length {} out of bounds", length);
+    }
+
+    Ok(String::from_utf8_lossy(&data[start..start + length]).to_string())
+}
+
+/// Read a 32-byte big-endian ABI word as usize, rejecting oversized values
+fn read_usize_word(word: &[u8]) -> Result<usize> {
+    if word[..24].iter().any(|&b| b != 0) {
+        bail!("ABI word does not fit in usize");
+    }
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&word[24..32]);
+    Ok(u64::from_be_bytes(buf) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build standard dynamic-string return data: offset, length, padded bytes
+    fn encode_dynamic_string(s: &str) -> Vec<u8> {
+        let mut data = vec![0u8; 64];
+        data[31] = 0x20; // offset = 32
+        data[63] = s.len() as u8;
+        data.extend_from_slice(s.as_bytes());
+        data.resize(64 + s.len().div_ceil(32).max(1) * 32, 0);
+        data
+    }
+
+    #[test]
+    fn test_decode_dynamic_string() {
+        let data = encode_dynamic_string("Wrapped Ether");
+        assert_eq!(decode_string(&data).unwrap(), "Wrapped Ether");
+    }
+
+    #[test]
+    fn test_decode_empty_string() {
+        let data = encode_dynamic_string("");
+        assert_eq!(decode_string(&data).unwrap(), "");
+    }
+
+    #[test]
+    fn test_decode_bytes32_symbol() {
+        // MKR-style: "MKR" right-padded to a single 32-byte word
+        let mut data = vec![0u8; 32];
+        data[..3].copy_from_slice(b"MKR");
+        assert_eq!(decode_string(&data).unwrap(), "MKR");
+    }
+
+    #[test]
+    fn test_decode_rejects_truncated_data() {
+        let mut data = encode_dynamic_string("Wrapped Ether");
+        data.truncate(70);
+        assert!(decode_string(&data).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! Backfill QuestDB holes left by downtime
+//!
+//! Scans block_production for missing block numbers, refetches those blocks
+//! from the chain, and writes them back through the ILP writer. Safe to run
+//! while the poller is up and safe to run twice: each missing block is
+//! re-checked against QuestDB right before it is written, so overlapping
+//! backfills dedup on block_number.
+//!
+//! Run with:
+//!   cargo run --bin backfill_gaps -- --hours 12
+
+use anyhow::Result;
+use clap::Parser;
+use megaviz_api::processor::MetricsCalculator;
+use megaviz_api::questdb::{QuestDBReader, QuestDBWriter};
+use megaviz_api::rpc::MegaEthClient;
+
+const DEFAULT_RPC_URL: &str = "https://carrot.megaeth.com/rpc";
+const DEFAULT_LOOKBACK_HOURS: i64 = 2;
+
+#[derive(Parser, Debug)]
+#[command(about = "Detect and backfill missing blocks in QuestDB")]
+struct Cli {
+    /// How many hours back to scan for gaps
+    #[arg(long, default_value_t = DEFAULT_LOOKBACK_HOURS)]
+    hours: i64,
+
+    /// MegaETH JSON-RPC endpoint
+    #[arg(long, default_value = DEFAULT_RPC_URL)]
+    rpc_url: String,
+
+    /// Report gaps without fetching or writing anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let args = Cli::parse();
+
+    let reader = QuestDBReader::connect().await?;
+    let gaps = reader.detect_gaps(args.hours).await?;
+
+    if gaps.is_empty() {
+        println!("No gaps in the last {} hours.", args.hours);
+        return Ok(());
+    }
+
+    let missing: u64 = gaps.iter().map(|r| r.len()).sum();
+    println!(
+        "Found {} gap(s) covering {} missing block(s) in the last {} hours:",
+        gaps.len(),
+        missing,
+        args.hours
+    );
+    for range in &gaps {
+        println!("  {} - {} ({} blocks)", range.start, range.end, range.len());
+    }
+
+    if args.dry_run {
+        return Ok(());
+    }
+
+    let client = MegaEthClient::new(&args.rpc_url).await?;
+    let calculator = MetricsCalculator::new();
+    let writer = QuestDBWriter::connect().await?;
+
+    let mut written = 0u64;
+    let mut skipped = 0u64;
+
+    for range in &gaps {
+        // Re-check just before writing so a concurrent poller or another
+        // backfill run doesn't produce duplicate rows
+        let existing = reader.existing_block_numbers(range.start, range.end).await?;
+
+        for block_number in range.start..=range.end {
+            if existing.contains(&block_number) {
+                skipped += 1;
+                continue;
+            }
+
+            let Some((block, receipts)) = client.get_block_with_receipts(block_number).await?
+            else {
+                eprintln!("Block {} not available from RPC, skipping", block_number);
+                skipped += 1;
+                continue;
+            };
+
+            let (block_metrics, tx_metrics) = calculator.process_block(&block, &receipts)?;
+            writer.write_block(&block_metrics, &tx_metrics).await?;
+            written += 1;
+        }
+    }
+
+    writer.flush().await?;
+    println!("Backfill done: {} block(s) written, {} skipped.", written, skipped);
+
+    Ok(())
+}
@@ -13,11 +13,25 @@ use megaviz_api::rpc::MegaEthClient;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::future::Future;
 use std::path::Path;
 use std::time::Duration;
 use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
 
+/// ERC-165 supportsInterface(bytes4) selector
+const SUPPORTS_INTERFACE_SELECTOR: &str = "0x01ffc9a7";
+/// ERC-165 interface ID (supportsInterface itself)
+const ERC165_INTERFACE_ID: [u8; 4] = [0x01, 0xff, 0xc9, 0xa7];
+/// Interface ID that a conforming ERC-165 contract must reject
+const INVALID_INTERFACE_ID: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+/// ERC-721 interface ID
+const ERC721_INTERFACE_ID: [u8; 4] = [0x80, 0xac, 0x58, 0xcd];
+/// ERC-721 Metadata extension interface ID (name/symbol/tokenURI)
+const ERC721_METADATA_INTERFACE_ID: [u8; 4] = [0x5b, 0x5e, 0x13, 0x9f];
+/// ERC-1155 interface ID
+const ERC1155_INTERFACE_ID: [u8; 4] = [0xd9, 0xb6, 0x7a, 0x26];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct IdentifiedContract {
     address: String,
@@ -38,40 +52,188 @@ struct IdentifiedContract {
     tx_hash: Option<String>,
 }
 
-struct ContractMonitor {
+/// Destination for identified contracts, selected via CONTRACT_SINK
+///
+/// `persist` follows the `impl Future + Send` convention from `EthRpc` so
+/// the monitor stays generic without boxing futures.
+trait ContractSink {
+    /// Previously persisted contracts, so a restart doesn't re-identify
+    /// everything; sinks without readable storage return an empty map
+    fn load(&self) -> Result<HashMap<Address, IdentifiedContract>> {
+        Ok(HashMap::new())
+    }
+
+    /// Persist the current set of identified contracts
+    ///
+    /// Called periodically with the full map; append-only sinks track what
+    /// they already shipped and only write the difference.
+    fn persist(
+        &mut self,
+        contracts: &HashMap<Address, IdentifiedContract>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Where this sink writes, for the startup banner
+    fn describe(&self) -> String;
+}
+
+/// Snapshot sink writing the full map to a pretty-printed JSON file
+struct JsonFileSink {
+    path: String,
+}
+
+impl JsonFileSink {
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+        }
+    }
+}
+
+impl ContractSink for JsonFileSink {
+    fn load(&self) -> Result<HashMap<Address, IdentifiedContract>> {
+        if Path::new(&self.path).exists() {
+            let content = fs::read_to_string(&self.path)?;
+            Ok(serde_json::from_str(&content).unwrap_or_default())
+        } else {
+            Ok(HashMap::new())
+        }
+    }
+
+    async fn persist(&mut self, contracts: &HashMap<Address, IdentifiedContract>) -> Result<()> {
+        let json = serde_json::to_string_pretty(contracts)?;
+        fs::write(&self.path, json)?;
+        info!("💾 Saved {} identified contracts to {}", contracts.len(), self.path);
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        self.path.clone()
+    }
+}
+
+/// Append-only sink printing each new contract as one JSON line, for
+/// piping into other tooling
+#[derive(Default)]
+struct StdoutSink {
+    seen: HashSet<Address>,
+}
+
+impl ContractSink for StdoutSink {
+    async fn persist(&mut self, contracts: &HashMap<Address, IdentifiedContract>) -> Result<()> {
+        for (address, contract) in contracts {
+            if self.seen.insert(*address) {
+                println!("{}", serde_json::to_string(contract)?);
+            }
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        "stdout (JSON lines)".to_string()
+    }
+}
+
+/// Append-only sink writing new contracts to QuestDB's
+/// contract_deployments table over ILP, including the identified category
+/// as contract_type
+struct QuestDbSink {
+    addr: String,
+    stream: Option<tokio::net::TcpStream>,
+    seen: HashSet<Address>,
+}
+
+impl QuestDbSink {
+    fn from_env() -> Self {
+        Self {
+            addr: std::env::var("QUESTDB_ILP_ADDR")
+                .unwrap_or_else(|_| "localhost:9009".to_string()),
+            stream: None,
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl ContractSink for QuestDbSink {
+    async fn persist(&mut self, contracts: &HashMap<Address, IdentifiedContract>) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let mut lines = String::new();
+        let mut new_addresses = Vec::new();
+        for (address, c) in contracts {
+            if self.seen.contains(address) {
+                continue;
+            }
+            lines.push_str(&format!(
+                "contract_deployments contract_address=\"{}\",deployer_address=\"{}\",\
+                 contract_type=\"{}\",block_number={}i {}\n",
+                c.address,
+                c.deployer,
+                c.category,
+                c.block_number,
+                (c.timestamp as i64) * 1_000_000_000,
+            ));
+            new_addresses.push(*address);
+        }
+        if new_addresses.is_empty() {
+            return Ok(());
+        }
+
+        // Reconnect lazily, like QuestDBWriter
+        if self.stream.is_none() {
+            self.stream = Some(
+                tokio::net::TcpStream::connect(&self.addr)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("QuestDB ILP connect failed: {}", e))?,
+            );
+        }
+        if let Some(stream) = self.stream.as_mut() {
+            if let Err(e) = stream.write_all(lines.as_bytes()).await {
+                // Drop the connection; the next persist reconnects and
+                // retries these contracts since they're not marked seen
+                self.stream = None;
+                anyhow::bail!("QuestDB ILP write failed: {}", e);
+            }
+        }
+
+        self.seen.extend(new_addresses.iter().copied());
+        info!("💾 Wrote {} new contracts to QuestDB at {}", new_addresses.len(), self.addr);
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("QuestDB ILP at {}", self.addr)
+    }
+}
+
+struct ContractMonitor<S: ContractSink> {
     rpc: MegaEthClient,
     blockscout: BlockscoutClient,
     processed_blocks: HashSet<u64>,
     identified_contracts: HashMap<Address, IdentifiedContract>,
-    output_file: String,
+    sink: S,
 }
 
-impl ContractMonitor {
-    async fn new(rpc_url: &str, output_file: &str) -> Result<Self> {
+impl<S: ContractSink> ContractMonitor<S> {
+    async fn new(rpc_url: &str, sink: S) -> Result<Self> {
         let rpc = MegaEthClient::new(rpc_url).await?;
         let blockscout = BlockscoutClient::new();
 
-        // Load existing identified contracts
-        let identified_contracts = if Path::new(output_file).exists() {
-            let content = fs::read_to_string(output_file)?;
-            serde_json::from_str(&content).unwrap_or_default()
-        } else {
-            HashMap::new()
-        };
+        // Resume from whatever the sink already holds
+        let identified_contracts = sink.load()?;
 
         Ok(Self {
             rpc,
             blockscout,
             processed_blocks: HashSet::new(),
             identified_contracts,
-            output_file: output_file.to_string(),
+            sink,
         })
     }
 
     /// Main monitoring loop
     async fn monitor(&mut self) -> Result<()> {
         info!("🚀 Starting MegaETH Contract Monitor");
-        info!("📊 Output: {}", self.output_file);
+        info!("📊 Output: {}", self.sink.describe());
         info!("🔍 Monitoring for new contract deployments...");
 
         let mut last_save = std::time::Instant::now();
@@ -90,7 +252,7 @@ impl ContractMonitor {
 
             // Save every 5 minutes or if we have new contracts
             if last_save.elapsed() > Duration::from_secs(300) {
-                self.save_results()?;
+                self.save_results().await?;
                 last_save = std::time::Instant::now();
             }
 
@@ -237,13 +399,19 @@ impl ContractMonitor {
                 // Check if it's MegaETH-specific
                 let is_megaeth_native = self.is_megaeth_native_pattern(&source.source_code);
 
+                // Prefer the real token symbol over a contract-name slice
+                let symbol = match self.blockscout.get_token_info(address).await {
+                    Ok(Some(token)) if !token.symbol.is_empty() => token.symbol,
+                    _ => source.contract_name[..4.min(source.contract_name.len())].to_uppercase(),
+                };
+
                 return Ok(IdentifiedContract {
                     address: addr_str,
                     deployer: deployer_str,
                     block_number,
                     timestamp,
                     name,
-                    symbol: source.contract_name[..4.min(source.contract_name.len())].to_uppercase(),
+                    symbol,
                     category,
                     confidence: 0.95,
                     detection_method: "Blockscout Verification".to_string(),
@@ -275,7 +443,7 @@ impl ContractMonitor {
 
         // Strategy 4: Analyze initial transactions (if contract has activity)
         debug!("Analyzing transaction patterns...");
-        if let Ok(txs) = self.blockscout.get_transactions(address, 10).await {
+        if let Ok(txs) = self.blockscout.get_all_transactions(address, 100).await {
             if !txs.is_empty() {
                 if let Some(pattern) = self.analyze_tx_patterns(&txs) {
                     return Ok(IdentifiedContract {
@@ -315,32 +483,61 @@ impl ContractMonitor {
 
     /// Check if address is a known MegaETH system contract
     fn check_system_contract(&self, address: Address) -> Option<(String, String, String)> {
-        let addr_str = format!("{:?}", address).to_lowercase();
-
-        const KNOWN_SYSTEM: &[(&str, &str, &str, &str)] = &[
-            ("0x6342000000000000000000000000000000000001", "Oracle", "ORA", "infrastructure"),
-            ("0x6342000000000000000000000000000000000002", "Timestamp Oracle", "TSO", "infrastructure"),
-            ("0x4200000000000000000000000000000000000015", "L1 Block", "L1B", "infrastructure"),
-            ("0x4200000000000000000000000000000000000007", "L2 Cross Domain Messenger", "CDM", "bridge"),
-            ("0x4200000000000000000000000000000000000010", "L2 Standard Bridge", "BRG", "bridge"),
-            ("0x4200000000000000000000000000000000000006", "WETH", "WETH", "defi"),
-        ];
+        megaviz_api::system_contracts::lookup(address)
+            .map(|c| (c.name.to_string(), c.symbol.to_string(), c.category.to_string()))
+    }
 
-        for (addr, name, symbol, category) in KNOWN_SYSTEM {
-            if addr_str == addr.to_lowercase() {
-                return Some((name.to_string(), symbol.to_string(), category.to_string()));
+    /// Detect standard interfaces
+    async fn detect_standard_interface(&self, address: Address) -> Result<Option<(String, String, String)>> {
+        // Prefer ERC-165 when the contract implements it: a declared interface
+        // beats guessing from selector probes
+        if self.implements_erc165(address).await {
+            if self.supports_interface(address, ERC721_INTERFACE_ID).await? {
+                let has_metadata = self
+                    .supports_interface(address, ERC721_METADATA_INTERFACE_ID)
+                    .await
+                    .unwrap_or(false);
+
+                let (name, symbol) = if has_metadata {
+                    (
+                        self.try_call_name(address).await
+                            .unwrap_or_else(|_| "Unknown NFT".to_string()),
+                        self.try_call_symbol(address).await
+                            .unwrap_or_else(|_| "NFT".to_string()),
+                    )
+                } else {
+                    ("Unknown NFT".to_string(), "NFT".to_string())
+                };
+
+                return Ok(Some((name, symbol, "nft".to_string())));
+            }
+
+            if self.supports_interface(address, ERC1155_INTERFACE_ID).await? {
+                return Ok(Some((
+                    "Unknown Multi Token".to_string(),
+                    "NFT".to_string(),
+                    "nft".to_string(),
+                )));
             }
         }
 
-        None
-    }
+        // Fall back to selector probing (ERC-20 and DEX pools predate
+        // ERC-165); all five probes go out in one JSON-RPC batch, and a
+        // revert in one slot just means that contract lacks the function
+        let calls = vec![
+            (address, "0x18160ddd".to_string()), // totalSupply()
+            (address, "0x70a08231".to_string()), // balanceOf(address)
+            (address, "0x6352211e".to_string()), // ownerOf(uint256)
+            (address, "0x0dfe1681".to_string()), // token0()
+            (address, "0xd21220a7".to_string()), // token1()
+        ];
+        let probes = self.rpc.eth_call_batch(&calls).await?;
+        let [total_supply, balance_of, owner_of, token0, token1] = probes.as_slice() else {
+            anyhow::bail!("eth_call batch returned {} entries, expected 5", probes.len());
+        };
 
-    /// Detect standard interfaces
-    async fn detect_standard_interface(&self, address: Address) -> Result<Option<(String, String, String)>> {
         // Try ERC-20
-        if self.has_function(address, "0x18160ddd").await? &&  // totalSupply()
-           self.has_function(address, "0x70a08231").await? {   // balanceOf(address)
-
+        if total_supply.is_ok() && balance_of.is_ok() {
             let name = self.try_call_name(address).await
                 .unwrap_or_else(|_| "Unknown Token".to_string());
             let symbol = self.try_call_symbol(address).await
@@ -350,7 +547,7 @@ impl ContractMonitor {
         }
 
         // Try ERC-721
-        if self.has_function(address, "0x6352211e").await? {  // ownerOf(uint256)
+        if owner_of.is_ok() {
             let name = self.try_call_name(address).await
                 .unwrap_or_else(|_| "Unknown NFT".to_string());
             let symbol = self.try_call_symbol(address).await
@@ -360,8 +557,7 @@ impl ContractMonitor {
         }
 
         // Try DEX Pool
-        if self.has_function(address, "0x0dfe1681").await? &&  // token0()
-           self.has_function(address, "0xd21220a7").await? {   // token1()
+        if token0.is_ok() && token1.is_ok() {
             return Ok(Some((
                 "DEX Pool".to_string(),
                 "POOL".to_string(),
@@ -372,27 +568,45 @@ impl ContractMonitor {
         Ok(None)
     }
 
-    /// Check if contract has a specific function
-    async fn has_function(&self, address: Address, selector: &str) -> Result<bool> {
-        match self.rpc.eth_call(address, selector).await {
-            Ok(_) => Ok(true),
-            Err(_) => Ok(false),
-        }
+    /// Call ERC-165 supportsInterface(bytes4) for a given interface ID
+    async fn supports_interface(&self, address: Address, interface_id: [u8; 4]) -> Result<bool> {
+        // bytes4 argument is left-aligned in its 32-byte word
+        let data = format!(
+            "{}{}{}",
+            SUPPORTS_INTERFACE_SELECTOR,
+            hex::encode(interface_id),
+            "00".repeat(28)
+        );
+        let result = self.rpc.eth_call(address, &data).await?;
+        Ok(result.len() >= 32 && result[31] == 1)
+    }
+
+    /// Check whether the contract implements ERC-165 at all
+    ///
+    /// Per the spec: supportsInterface(0x01ffc9a7) must return true and
+    /// supportsInterface(0xffffffff) must return false
+    async fn implements_erc165(&self, address: Address) -> bool {
+        matches!(
+            self.supports_interface(address, ERC165_INTERFACE_ID).await,
+            Ok(true)
+        ) && matches!(
+            self.supports_interface(address, INVALID_INTERFACE_ID).await,
+            Ok(false)
+        )
     }
 
     /// Try to call name() function
     async fn try_call_name(&self, address: Address) -> Result<String> {
         const NAME_SELECTOR: &str = "0x06fdde03";
         let result = self.rpc.eth_call(address, NAME_SELECTOR).await?;
-        // Simplified decoder - in production use proper ABI decoder
-        Ok("Token".to_string())
+        megaviz_api::abi::decode_string(&result)
     }
 
     /// Try to call symbol() function
     async fn try_call_symbol(&self, address: Address) -> Result<String> {
         const SYMBOL_SELECTOR: &str = "0x95d89b41";
         let result = self.rpc.eth_call(address, SYMBOL_SELECTOR).await?;
-        Ok("TKN".to_string())
+        megaviz_api::abi::decode_string(&result)
     }
 
     /// Check if contract uses MegaETH-specific patterns
@@ -440,15 +654,9 @@ impl ContractMonitor {
         None
     }
 
-    /// Save results to JSON file
-    fn save_results(&self) -> Result<()> {
-        let json = serde_json::to_string_pretty(&self.identified_contracts)?;
-        fs::write(&self.output_file, json)?;
-        info!("💾 Saved {} identified contracts to {}",
-            self.identified_contracts.len(),
-            self.output_file
-        );
-        Ok(())
+    /// Push the current results into the configured sink
+    async fn save_results(&mut self) -> Result<()> {
+        self.sink.persist(&self.identified_contracts).await
     }
 }
 
@@ -461,10 +669,26 @@ async fn main() -> Result<()> {
     let rpc_url = std::env::var("RPC_URL")
         .unwrap_or_else(|_| "https://mainnet.megaeth.com/rpc".to_string());
 
-    let output_file = std::env::var("OUTPUT_FILE")
-        .unwrap_or_else(|_| "identified_contracts.json".to_string());
+    // CONTRACT_SINK picks where identified contracts go: "json" (default,
+    // honors OUTPUT_FILE), "questdb" (honors QUESTDB_ILP_ADDR) or "stdout"
+    let sink_kind = std::env::var("CONTRACT_SINK").unwrap_or_else(|_| "json".to_string());
+    match sink_kind.as_str() {
+        "json" => {
+            let output_file = std::env::var("OUTPUT_FILE")
+                .unwrap_or_else(|_| "identified_contracts.json".to_string());
+            run(&rpc_url, JsonFileSink::new(&output_file)).await
+        }
+        "questdb" => run(&rpc_url, QuestDbSink::from_env()).await,
+        "stdout" => run(&rpc_url, StdoutSink::default()).await,
+        other => anyhow::bail!(
+            "Unknown CONTRACT_SINK '{}'; expected json, questdb or stdout",
+            other
+        ),
+    }
+}
 
-    let mut monitor = ContractMonitor::new(&rpc_url, &output_file).await?;
+async fn run<S: ContractSink>(rpc_url: &str, sink: S) -> Result<()> {
+    let mut monitor = ContractMonitor::new(rpc_url, sink).await?;
 
     // Graceful shutdown
     tokio::select! {
@@ -473,7 +697,7 @@ async fn main() -> Result<()> {
         }
         _ = tokio::signal::ctrl_c() => {
             info!("🛑 Shutting down gracefully...");
-            monitor.save_results()?;
+            monitor.save_results().await?;
         }
     }
 
@@ -0,0 +1,183 @@
+//! Validate estimated da_size against actual L1 batch submissions
+//!
+//! Correlates our FastLZ-based block-level DA estimates with the bytes the
+//! MegaETH batcher actually posted to L1, and reports how accurate the DA
+//! model is over a range. Requires an L1 RPC endpoint; when none is
+//! configured the validation is skipped.
+//!
+//! Run with:
+//!   L1_RPC_URL=https://eth.example.com/rpc cargo run --bin da_validator
+//!
+//! Environment:
+//!   L1_RPC_URL        - L1 JSON-RPC endpoint (optional; skip validation if unset)
+//!   L1_BATCH_INBOX    - Batch inbox address the batcher posts to
+//!   MEGAETH_RPC_URL   - L2 JSON-RPC endpoint
+//!   VALIDATE_BLOCKS   - Number of recent L2 blocks to estimate (default 1000)
+//!   L1_SCAN_BLOCKS    - Number of recent L1 blocks to scan for batches (default 300)
+
+use anyhow::{Context, Result};
+use alloy_primitives::Address;
+use megaviz_api::processor::MetricsCalculator;
+use megaviz_api::rpc::MegaEthClient;
+
+const DEFAULT_L2_RPC: &str = "https://carrot.megaeth.com/rpc";
+const DEFAULT_VALIDATE_BLOCKS: u64 = 1000;
+const DEFAULT_L1_SCAN_BLOCKS: u64 = 300;
+
+/// One batcher submission observed on L1
+#[derive(Debug)]
+struct BatchSubmission {
+    l1_block: u64,
+    l1_timestamp: u64,
+    calldata_bytes: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    println!("===========================================");
+    println!("  DA Size Model Validator");
+    println!("===========================================\n");
+
+    let l1_rpc_url = match std::env::var("L1_RPC_URL") {
+        Ok(url) => url,
+        Err(_) => {
+            println!("L1_RPC_URL not configured - skipping DA validation.");
+            println!("Set L1_RPC_URL (and L1_BATCH_INBOX) to enable the ground-truth check.");
+            return Ok(());
+        }
+    };
+
+    let batch_inbox: Address = std::env::var("L1_BATCH_INBOX")
+        .context("L1_BATCH_INBOX must be set when L1_RPC_URL is configured")?
+        .parse()
+        .context("Invalid L1_BATCH_INBOX address")?;
+
+    let l2_rpc_url = std::env::var("MEGAETH_RPC_URL")
+        .unwrap_or_else(|_| DEFAULT_L2_RPC.to_string());
+    // Clamp to at least one block: the range arithmetic below subtracts
+    // `blocks - 1`, which would underflow on an explicit 0
+    let validate_blocks: u64 = std::env::var("VALIDATE_BLOCKS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_VALIDATE_BLOCKS)
+        .max(1);
+    let l1_scan_blocks: u64 = std::env::var("L1_SCAN_BLOCKS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_L1_SCAN_BLOCKS)
+        .max(1);
+
+    // Both clients speak plain JSON-RPC, so the same client type works for L1
+    let l2 = MegaEthClient::new(&l2_rpc_url).await?;
+    let l1 = MegaEthClient::new(&l1_rpc_url).await?;
+
+    // --- Step 1: estimate DA over the recent L2 range ---
+    let l2_latest = l2.get_latest_block_number().await?;
+    let l2_start = l2_latest.saturating_sub(validate_blocks - 1);
+    println!("Estimating DA for L2 blocks {} to {}...", l2_start, l2_latest);
+
+    let calculator = MetricsCalculator::new();
+    let mut estimated_da: u64 = 0;
+    let mut estimated_blocks: u64 = 0;
+    let mut l2_start_ts: u64 = u64::MAX;
+    let mut l2_end_ts: u64 = 0;
+
+    for block_num in l2_start..=l2_latest {
+        let Some(block) = l2.get_block(block_num).await? else {
+            continue;
+        };
+        l2_start_ts = l2_start_ts.min(block.timestamp);
+        l2_end_ts = l2_end_ts.max(block.timestamp);
+
+        // DA size only depends on the transaction bytes, not the receipts
+        let (metrics, _) = calculator.process_block(&block, &[])?;
+        estimated_da += metrics.da_size;
+        estimated_blocks += 1;
+
+        if (block_num - l2_start) % 200 == 0 {
+            println!("  Progress: {} / {} blocks...", block_num - l2_start, validate_blocks);
+        }
+    }
+
+    println!("\nEstimated DA: {} bytes over {} blocks", estimated_da, estimated_blocks);
+
+    // --- Step 2: collect actual batcher submissions from L1 ---
+    let l1_latest = l1.get_latest_block_number().await?;
+    let l1_start = l1_latest.saturating_sub(l1_scan_blocks - 1);
+    println!("\nScanning L1 blocks {} to {} for batches to {:?}...", l1_start, l1_latest, batch_inbox);
+
+    let mut batches: Vec<BatchSubmission> = Vec::new();
+
+    for block_num in l1_start..=l1_latest {
+        let Some(block) = l1.get_block(block_num).await? else {
+            continue;
+        };
+
+        // Only count L1 blocks overlapping the L2 window we estimated
+        if block.timestamp < l2_start_ts || block.timestamp > l2_end_ts {
+            continue;
+        }
+
+        for tx in &block.transactions {
+            if tx.to == Some(batch_inbox) {
+                batches.push(BatchSubmission {
+                    l1_block: block_num,
+                    l1_timestamp: block.timestamp,
+                    calldata_bytes: tx.input.len() as u64,
+                });
+            }
+        }
+    }
+
+    if batches.is_empty() {
+        println!("\nNo batch submissions found in the overlapping window.");
+        println!("Widen L1_SCAN_BLOCKS or check the L1_BATCH_INBOX address.");
+        return Ok(());
+    }
+
+    // --- Step 3: report per-batch and aggregate error statistics ---
+    let actual_da: u64 = batches.iter().map(|b| b.calldata_bytes).sum();
+    let batch_count = batches.len() as u64;
+    let mean_batch = actual_da as f64 / batch_count as f64;
+    let min_batch = batches.iter().map(|b| b.calldata_bytes).min().unwrap_or(0);
+    let max_batch = batches.iter().map(|b| b.calldata_bytes).max().unwrap_or(0);
+
+    println!("\n===========================================");
+    println!("  BATCH SUBMISSIONS ({} found)", batch_count);
+    println!("===========================================\n");
+    println!("  {:>10} {:>12} {:>12}", "L1 Block", "Timestamp", "Bytes");
+    for batch in &batches {
+        println!("  {:>10} {:>12} {:>12}", batch.l1_block, batch.l1_timestamp, batch.calldata_bytes);
+    }
+
+    println!("\n  Mean batch size: {:.0} bytes", mean_batch);
+    println!("  Min batch size:  {} bytes", min_batch);
+    println!("  Max batch size:  {} bytes", max_batch);
+
+    println!("\n===========================================");
+    println!("  MODEL ACCURACY");
+    println!("===========================================\n");
+
+    let error = estimated_da as f64 - actual_da as f64;
+    let error_pct = if actual_da > 0 {
+        (error / actual_da as f64) * 100.0
+    } else {
+        0.0
+    };
+
+    println!("  Estimated DA: {:>14} bytes", estimated_da);
+    println!("  Actual DA:    {:>14} bytes", actual_da);
+    println!("  Error:        {:>14.0} bytes ({:+.2}%)", error, error_pct);
+    println!(
+        "  Ratio:        {:>14.4}",
+        estimated_da as f64 / actual_da.max(1) as f64
+    );
+
+    if error_pct.abs() < 10.0 {
+        println!("\n  ✅ DA model within 10% of actual L1 submissions");
+    } else {
+        println!("\n  ⚠️  DA model deviates more than 10% from actual L1 submissions");
+    }
+
+    Ok(())
+}
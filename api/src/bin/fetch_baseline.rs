@@ -3,7 +3,8 @@
 //! Run with: cargo run --bin fetch_baseline
 
 use anyhow::{Context, Result};
-use megaviz_api::metrics::{limits, PercentileStats, RollingStats};
+use clap::Parser;
+use megaviz_api::metrics::{limits, PercentileReport, PercentileStats, RollingStats};
 use reqwest::Client;
 use serde_json::{json, Value};
 use std::time::Instant;
@@ -11,9 +12,16 @@ use std::time::Instant;
 const MEGAETH_RPC: &str = "https://carrot.megaeth.com/rpc";
 const BLOCKS_TO_FETCH: u64 = 500;
 
+/// Fetch real blocks from MegaETH and compute baseline percentiles
+#[derive(Debug, Parser)]
+struct Cli {
+    /// Write the structured report here (JSON, or CSV for a .csv path)
+    #[arg(long)]
+    out: Option<std::path::PathBuf>,
+}
+
 #[derive(Debug, Clone)]
 struct BlockMetrics {
-    block_number: u64,
     tx_count: u64,
     total_gas: u64,
     tx_size: u64,
@@ -26,6 +34,8 @@ struct BlockMetrics {
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = Cli::parse();
+
     println!("===========================================");
     println!("  MegaViz Baseline Metrics Calculator");
     println!("===========================================\n");
@@ -91,6 +101,20 @@ async fn main() -> Result<()> {
     print_metric_stats("Data Size (est)", &stats.data_size, limits::BLOCK_DATA_LIMIT);
     print_metric_stats("State Growth (est)", &stats.state_growth, limits::BLOCK_STATE_GROWTH_LIMIT);
 
+    // Structured export: the machine-readable artifact is the source of
+    // truth, the tables above are just for eyeballs
+    if let Some(path) = &args.out {
+        let mut report = PercentileReport::new(successful);
+        report.push("total_gas", &report_row(&stats.gas));
+        report.push("kv_updates", &report_row(&stats.kv_updates));
+        report.push("tx_size", &report_row(&stats.tx_size));
+        report.push("da_size", &report_row(&stats.da_size));
+        report.push("data_size", &report_row(&stats.data_size));
+        report.push("state_growth", &report_row(&stats.state_growth));
+        report.write(path)?;
+        println!("Wrote {}", path.display());
+    }
+
     // Show example normalized block
     println!("\n===========================================");
     println!("  EXAMPLE: Normalizing Block #{}", latest);
@@ -171,7 +195,6 @@ async fn fetch_block_metrics(client: &Client, block_num: u64) -> Result<BlockMet
 
     if tx_count == 0 {
         return Ok(BlockMetrics {
-            block_number: block_num,
             tx_count: 0,
             total_gas: 0,
             tx_size: 0,
@@ -207,7 +230,6 @@ async fn fetch_block_metrics(client: &Client, block_num: u64) -> Result<BlockMet
     let state_growth = kv_updates / 5;
 
     Ok(BlockMetrics {
-        block_number: block_num,
         tx_count,
         total_gas,
         tx_size,
@@ -218,24 +240,38 @@ async fn fetch_block_metrics(client: &Client, block_num: u64) -> Result<BlockMet
     })
 }
 
+/// Percentile ladder as ordered (label, value) pairs, for the report
+fn report_row(stats: &PercentileStats) -> Vec<(&'static str, f64)> {
+    vec![
+        ("min", stats.min as f64),
+        ("p10", stats.p10),
+        ("p25", stats.p25),
+        ("p50", stats.median),
+        ("p75", stats.p75),
+        ("p90", stats.p90),
+        ("max", stats.max as f64),
+        ("iqr", stats.iqr),
+    ]
+}
+
 fn print_metric_stats(name: &str, stats: &PercentileStats, limit: u64) {
     if stats.count == 0 {
         println!("{}: No data\n", name);
         return;
     }
 
-    let median_pct = (stats.median as f64 / limit as f64) * 100.0;
-    let p90_pct = (stats.p90 as f64 / limit as f64) * 100.0;
+    let median_pct = (stats.median / limit as f64) * 100.0;
+    let p90_pct = (stats.p90 / limit as f64) * 100.0;
 
     println!("{}:", name);
     println!("  Min:    {:>12}", format_number(stats.min));
-    println!("  P10:    {:>12}", format_number(stats.p10));
-    println!("  P25:    {:>12}", format_number(stats.p25));
-    println!("  Median: {:>12}  ({:.4}% of limit)", format_number(stats.median), median_pct);
-    println!("  P75:    {:>12}", format_number(stats.p75));
-    println!("  P90:    {:>12}  ({:.4}% of limit)", format_number(stats.p90), p90_pct);
+    println!("  P10:    {:>12}", format_number(stats.p10 as u64));
+    println!("  P25:    {:>12}", format_number(stats.p25 as u64));
+    println!("  Median: {:>12}  ({:.4}% of limit)", format_number(stats.median as u64), median_pct);
+    println!("  P75:    {:>12}", format_number(stats.p75 as u64));
+    println!("  P90:    {:>12}  ({:.4}% of limit)", format_number(stats.p90 as u64), p90_pct);
     println!("  Max:    {:>12}", format_number(stats.max));
-    println!("  IQR:    {:>12}", format_number(stats.iqr));
+    println!("  IQR:    {:>12}", format_number(stats.iqr as u64));
     println!("  Limit:  {:>12}", format_number(limit));
     println!();
 }
@@ -1,14 +1,55 @@
 // Fast parallel block fetcher for percentile calculation
 // Fetches 100K blocks to calculate gas/tx_size/da_size percentiles
 
+use clap::Parser;
+use megaviz_api::metrics::PercentileReport;
 use megaviz_api::rpc::MegaEthClient;
 use futures::stream::{self, StreamExt};
 use serde::{Serialize, Deserialize};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use tokio::sync::Semaphore;
 
-const TARGET_BLOCKS: u64 = 100_000;  // 100K blocks
-const CONCURRENT_REQUESTS: usize = 50;  // Parallel requests
+const DEFAULT_TARGET_BLOCKS: u64 = 100_000;  // 100K blocks
+const DEFAULT_CONCURRENT_REQUESTS: usize = 50;  // Parallel requests
+const DEFAULT_RPC_URL: &str = "https://carrot.megaeth.com/rpc";
+
+/// Fetch recent blocks and calculate resource-metric percentiles
+#[derive(Debug, Parser)]
+struct Cli {
+    /// How many recent blocks to analyze
+    #[arg(long, default_value_t = DEFAULT_TARGET_BLOCKS)]
+    blocks: u64,
+
+    /// Maximum concurrent RPC requests
+    #[arg(long, default_value_t = DEFAULT_CONCURRENT_REQUESTS)]
+    concurrency: usize,
+
+    /// RPC endpoint to fetch from
+    #[arg(long, default_value = DEFAULT_RPC_URL)]
+    rpc_url: String,
+
+    /// Write the structured report here (JSON, or CSV for a .csv path)
+    #[arg(long)]
+    out: Option<std::path::PathBuf>,
+}
+
+/// Percentile ladder as ordered (label, value) pairs, for the report
+fn report_row(p: &Percentiles) -> Vec<(&'static str, f64)> {
+    vec![
+        ("p0", p.p0),
+        ("p10", p.p10),
+        ("p20", p.p20),
+        ("p30", p.p30),
+        ("p40", p.p40),
+        ("p50", p.p50),
+        ("p60", p.p60),
+        ("p70", p.p70),
+        ("p80", p.p80),
+        ("p90", p.p90),
+        ("p100", p.p100),
+    ]
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Percentiles {
@@ -25,7 +66,7 @@ pub struct Percentiles {
     pub p100: f64,
 }
 
-fn calculate_percentiles(values: &mut Vec<f64>) -> Percentiles {
+fn calculate_percentiles(values: &mut [f64]) -> Percentiles {
     if values.is_empty() {
         return Percentiles {
             p0: 0.0, p10: 0.0, p20: 0.0, p30: 0.0, p40: 0.0,
@@ -58,21 +99,26 @@ fn calculate_percentiles(values: &mut Vec<f64>) -> Percentiles {
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    println!("=== FAST PERCENTILE CALCULATOR (100K blocks) ===\n");
+    let args = Cli::parse();
+
+    println!("=== FAST PERCENTILE CALCULATOR ({} blocks) ===\n", args.blocks);
 
-    let client = Arc::new(MegaEthClient::new("https://carrot.megaeth.com/rpc").await?);
+    let client = Arc::new(MegaEthClient::new(&args.rpc_url).await?);
     let latest = client.get_block_number().await?;
 
-    let start_block = latest.saturating_sub(TARGET_BLOCKS);
+    let start_block = latest.saturating_sub(args.blocks);
     let total_blocks = latest - start_block;
 
     println!("Fetching {} blocks from {} to {}", total_blocks, start_block, latest);
-    println!("Using {} concurrent requests\n", CONCURRENT_REQUESTS);
+    println!("Using {} concurrent requests\n", args.concurrency);
 
     // Atomic counters for progress
     let fetched = Arc::new(AtomicU64::new(0));
     let failed = Arc::new(AtomicU64::new(0));
 
+    // Semaphore bounds in-flight RPC requests to --concurrency
+    let semaphore = Arc::new(Semaphore::new(args.concurrency.max(1)));
+
     let start_time = std::time::Instant::now();
 
     // Create block number stream
@@ -84,11 +130,13 @@ async fn main() -> anyhow::Result<()> {
             let client = client.clone();
             let fetched = fetched.clone();
             let failed = failed.clone();
+            let semaphore = semaphore.clone();
             async move {
+                let _permit = semaphore.acquire().await.expect("semaphore closed");
                 match client.get_block(block_num).await {
                     Ok(Some(block)) => {
                         let count = fetched.fetch_add(1, Ordering::Relaxed);
-                        if count % 5000 == 0 {
+                        if count.is_multiple_of(5000) {
                             eprintln!("  Progress: {} blocks fetched...", count);
                         }
                         Some((
@@ -104,7 +152,7 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
         })
-        .buffer_unordered(CONCURRENT_REQUESTS)
+        .buffer_unordered(args.concurrency.max(1))
         .collect()
         .await;
 
@@ -122,12 +170,10 @@ async fn main() -> anyhow::Result<()> {
     let mut tx_count_values: Vec<f64> = Vec::with_capacity(total_fetched as usize);
     let mut tx_size_values: Vec<f64> = Vec::with_capacity(total_fetched as usize);
 
-    for result in results {
-        if let Some((gas, tx_count, tx_size)) = result {
-            gas_values.push(gas);
-            tx_count_values.push(tx_count);
-            tx_size_values.push(tx_size);
-        }
+    for (gas, tx_count, tx_size) in results.into_iter().flatten() {
+        gas_values.push(gas);
+        tx_count_values.push(tx_count);
+        tx_size_values.push(tx_size);
     }
 
     println!("\nCalculating percentiles from {} samples...\n", gas_values.len());
@@ -231,5 +277,28 @@ async fn main() -> anyhow::Result<()> {
     println!("  }}");
     println!("}};");
 
+    // Structured export: this artifact, not the console output, is what
+    // downstream consumers should read
+    if let Some(path) = &args.out {
+        let mut report = PercentileReport::new(gas_values.len() as u64);
+        report.push("gas", &report_row(&gas_percentiles));
+        report.push("tx_count", &report_row(&tx_count_percentiles));
+        report.push("tx_size", &report_row(&tx_size_percentiles));
+        report.push("da_size", &report_row(&da_percentiles));
+        report.write(path)?;
+        println!("\nWrote {}", path.display());
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_usage_is_valid() {
+        Cli::command().debug_assert();
+    }
+}
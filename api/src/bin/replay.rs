@@ -0,0 +1,157 @@
+//! Replay a block through revm and print measured per-transaction metrics
+//!
+//! This is the ground-truth tool for the estimator: the calculator guesses
+//! KV updates, state growth and the compute/storage gas split from gas
+//! totals and calldata, while replay measures them from the actual change
+//! set. `--compare` prints both side by side.
+//!
+//! Run with:
+//!   cargo run --features replay --bin replay -- --block 12345
+//!   cargo run --features replay --bin replay -- --block 12345 --compare
+//!   cargo run --features replay --bin replay -- --block 12345 --tx 0xabc...
+
+use std::sync::Arc;
+
+use alloy_primitives::B256;
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use megaviz_api::processor::MetricsCalculator;
+use megaviz_api::replay::{replay_stats, BlockReplayer, MetricProvenance, SmartCacheDB};
+use megaviz_api::rpc::MegaEthClient;
+
+const DEFAULT_RPC: &str = "https://carrot.megaeth.com/rpc";
+
+/// Replay a block through revm and print measured per-transaction metrics
+#[derive(Debug, Parser)]
+struct Cli {
+    /// Block number to replay
+    #[arg(long)]
+    block: u64,
+
+    /// MegaETH JSON-RPC endpoint
+    #[arg(long, default_value = DEFAULT_RPC)]
+    rpc_url: String,
+
+    /// Only print this transaction (the whole block is still replayed so
+    /// earlier transactions' state changes are visible)
+    #[arg(long)]
+    tx: Option<B256>,
+
+    /// Also run the heuristic estimator and print measured vs estimated
+    #[arg(long)]
+    compare: bool,
+
+    /// Print the target transaction's state diff as JSON (requires --tx)
+    #[arg(long, requires = "tx")]
+    diff: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Cli::parse();
+
+    let client = MegaEthClient::new(&args.rpc_url)
+        .await
+        .context("Failed to create MegaETH client")?;
+    let (block, receipts) = client
+        .get_block_with_receipts(args.block)
+        .await?
+        .with_context(|| format!("Block {} not found", args.block))?;
+
+    println!(
+        "Replaying block {} ({} transactions)...\n",
+        args.block,
+        block.transactions.len()
+    );
+
+    let db = SmartCacheDB::new(Arc::new(client)).context("Failed to open replay cache")?;
+    let replayer = BlockReplayer::new(db);
+
+    if args.diff {
+        let wanted = args.tx.expect("clap enforces --tx with --diff");
+        let tx_index = block
+            .transactions
+            .iter()
+            .position(|tx| tx.hash == wanted)
+            .with_context(|| format!("Transaction {:?} not in block {}", wanted, args.block))?;
+        let diff = replayer.replay_tx_with_diff(&block, tx_index)?;
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+
+    let (block_metrics, per_tx) = replayer.replay_block(&block, &receipts)?;
+
+    // Estimates come from a fresh calculator so --compare shows exactly what
+    // the poller would have stored
+    let estimates = if args.compare {
+        let calculator = MetricsCalculator::new();
+        let (_, estimated) = calculator.process_block(&block, &receipts)?;
+        Some(estimated)
+    } else {
+        None
+    };
+
+    if args.compare {
+        println!(
+            "{:<14} {:<4} {:>12} {:>12} {:>10} {:>10} {:>8} {:>8} {:>8} {:>8}",
+            "tx", "src", "gas(m)", "gas(e)", "kv(m)", "kv(e)", "sg(m)", "sg(e)", "cg(m)", "cg(e)"
+        );
+    } else {
+        println!(
+            "{:<14} {:<4} {:>12} {:>12} {:>12} {:>10} {:>10}",
+            "tx", "src", "total_gas", "compute_gas", "storage_gas", "kv", "state_growth"
+        );
+    }
+
+    for (i, replayed) in per_tx.iter().enumerate() {
+        let m = &replayed.metrics;
+        if let Some(wanted) = args.tx {
+            if m.tx_hash != wanted {
+                continue;
+            }
+        }
+
+        let short_hash = &format!("{:?}", m.tx_hash)[..14];
+        let src = match replayed.provenance {
+            MetricProvenance::Replayed => "rpl",
+            MetricProvenance::Estimated => "est",
+        };
+
+        if let Some(estimates) = &estimates {
+            let e = &estimates[i];
+            println!(
+                "{:<14} {:<4} {:>12} {:>12} {:>10} {:>10} {:>8} {:>8} {:>8} {:>8}",
+                short_hash,
+                src,
+                m.total_gas,
+                e.total_gas,
+                m.kv_updates,
+                e.kv_updates,
+                m.state_growth,
+                e.state_growth,
+                m.compute_gas,
+                e.compute_gas,
+            );
+        } else {
+            println!(
+                "{:<14} {:<4} {:>12} {:>12} {:>12} {:>10} {:>10}",
+                short_hash, src, m.total_gas, m.compute_gas, m.storage_gas, m.kv_updates, m.state_growth,
+            );
+        }
+    }
+
+    let stats = replay_stats();
+    println!(
+        "\nBlock totals: gas={} kv_updates={} state_growth={}",
+        block_metrics.total_gas, block_metrics.kv_updates, block_metrics.state_growth
+    );
+    println!(
+        "Replay: {} attempted, {} fell back to estimates ({:.1}% failure)",
+        stats.attempted(),
+        stats.failed(),
+        stats.failure_rate() * 100.0
+    );
+
+    Ok(())
+}
@@ -4,16 +4,90 @@
 //! https://megaeth.blockscout.com/api-docs
 
 use anyhow::{Context, Result};
+use alloy_json_abi::JsonAbi;
 use alloy_primitives::Address;
+use lru::LruCache;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
-use tracing::{debug, info, warn};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// How many parsed ABIs to keep cached
+const ABI_CACHE_CAPACITY: usize = 256;
+/// How long a cached ABI stays fresh before re-fetching
+const ABI_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+/// Default number of attempts for ABI fetches
+const DEFAULT_MAX_RETRIES: u32 = 3;
+/// Largest page the txlist endpoint will serve in one request
+const TXLIST_PAGE_SIZE: usize = 100;
+/// Pause between txlist pages to stay under Blockscout's rate limits
+const TXLIST_PAGE_DELAY: Duration = Duration::from_millis(250);
+/// Default request budget against the shared Blockscout instance
+const DEFAULT_BLOCKSCOUT_RPS: f64 = 5.0;
+
+/// Errors callers need to branch on, beyond generic fetch failures
+#[derive(Debug, thiserror::Error)]
+pub enum BlockscoutError {
+    /// Blockscout returned HTTP 429; back off instead of treating the
+    /// contract as unverified
+    #[error("Blockscout rate limited (HTTP 429)")]
+    RateLimited,
+}
+
+/// Minimal token bucket: one token per request, refilled at a fixed rate
+///
+/// Hand-rolled because the whole client needs exactly one knob
+/// (requests per second), not a limiter framework.
+struct RateLimiter {
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    fn new(refill_per_sec: f64) -> Self {
+        Self {
+            tokens: 1.0,
+            refill_per_sec,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// How long to wait before a request may proceed, consuming a token
+    fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        self.tokens = (self.tokens
+            + now.duration_since(self.last_refill).as_secs_f64() * self.refill_per_sec)
+            .min(1.0);
+        self.last_refill = now;
+
+        // The balance may go negative: that debt is the wait for this
+        // request, and keeps refills during the wait from double-counting
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.refill_per_sec)
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct BlockscoutClient {
     client: Client,
     base_url: String,
+    max_retries: u32,
+    abi_cache: Arc<Mutex<LruCache<Address, CachedAbi>>>,
+    limiter: Arc<Mutex<RateLimiter>>,
+}
+
+/// A parsed ABI plus the time it was fetched, for TTL eviction
+#[derive(Clone)]
+struct CachedAbi {
+    abi: Arc<JsonAbi>,
+    fetched_at: Instant,
 }
 
 #[derive(Debug, Deserialize)]
@@ -66,6 +140,29 @@ pub struct Transaction {
     pub contract_address: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct TokenInfoResponse {
+    pub status: String,
+    pub message: String,
+    pub result: Option<TokenInfo>,
+}
+
+/// Token metadata from the Blockscout token endpoint
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    pub name: String,
+    pub symbol: String,
+    /// Empty for NFTs, which have no decimals
+    #[serde(default)]
+    pub decimals: String,
+    /// Token standard: "ERC-20", "ERC-721", or "ERC-1155"
+    #[serde(rename = "type")]
+    pub token_type: String,
+    #[serde(default)]
+    pub total_supply: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ContractCreationResponse {
     pub status: String,
@@ -81,6 +178,12 @@ pub struct ContractCreation {
     pub tx_hash: String,
 }
 
+impl Default for BlockscoutClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BlockscoutClient {
     pub fn new() -> Self {
         Self {
@@ -89,9 +192,111 @@ impl BlockscoutClient {
                 .build()
                 .expect("Failed to create HTTP client"),
             base_url: "https://megaeth.blockscout.com/api".to_string(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            abi_cache: Arc::new(Mutex::new(LruCache::new(
+                NonZeroUsize::new(ABI_CACHE_CAPACITY).unwrap(),
+            ))),
+            limiter: Arc::new(Mutex::new(RateLimiter::new(DEFAULT_BLOCKSCOUT_RPS))),
+        }
+    }
+
+    /// Set the request budget in requests per second
+    pub fn with_rate_limit(self, requests_per_second: f64) -> Self {
+        *self.limiter.lock().unwrap() = RateLimiter::new(requests_per_second.max(0.1));
+        self
+    }
+
+    /// Wait until the shared request budget allows another call
+    async fn throttle(&self) {
+        let wait = self.limiter.lock().unwrap().reserve();
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
         }
     }
 
+    /// Issue a throttled GET, surfacing 429s as BlockscoutError::RateLimited
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        self.throttle().await;
+
+        let response = self.client
+            .get(url)
+            .send()
+            .await
+            .context("Failed to fetch from Blockscout")?;
+
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(BlockscoutError::RateLimited.into());
+        }
+
+        response
+            .json()
+            .await
+            .context("Failed to parse Blockscout response")
+    }
+
+    /// Set the number of attempts for ABI fetches
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries.max(1);
+        self
+    }
+
+    /// Get a contract's parsed ABI, cached by address
+    ///
+    /// Hits the source-code endpoint at most once per address per TTL; repeat
+    /// lookups for contracts we decode events/calls for come from the LRU.
+    pub async fn get_abi(&self, address: Address) -> Result<Arc<JsonAbi>> {
+        if let Some(cached) = self.cached_abi(address) {
+            return Ok(cached);
+        }
+
+        let mut last_err = None;
+        for attempt in 0..self.max_retries {
+            if attempt > 0 {
+                // Exponential backoff between attempts, also spacing out
+                // requests so we stay under Blockscout's rate limits
+                tokio::time::sleep(Duration::from_millis(500 * (1 << attempt))).await;
+            }
+
+            match self.fetch_abi(address).await {
+                Ok(abi) => {
+                    let abi = Arc::new(abi);
+                    self.abi_cache.lock().unwrap().put(
+                        address,
+                        CachedAbi {
+                            abi: abi.clone(),
+                            fetched_at: Instant::now(),
+                        },
+                    );
+                    return Ok(abi);
+                }
+                Err(e) => last_err = Some(e),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("ABI fetch failed")))
+    }
+
+    /// Look up a still-fresh cached ABI
+    fn cached_abi(&self, address: Address) -> Option<Arc<JsonAbi>> {
+        let mut cache = self.abi_cache.lock().unwrap();
+        match cache.get(&address) {
+            Some(cached) if cached.fetched_at.elapsed() < ABI_CACHE_TTL => {
+                Some(cached.abi.clone())
+            }
+            Some(_) => {
+                cache.pop(&address);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Fetch and parse the ABI from the verified source-code endpoint
+    async fn fetch_abi(&self, address: Address) -> Result<JsonAbi> {
+        let source = self.get_source_code(address).await?;
+        parse_abi(&source.abi)
+    }
+
     /// Check if a contract is verified on Blockscout
     pub async fn is_verified(&self, address: Address) -> Result<bool> {
         let source = self.get_source_code(address).await?;
@@ -107,14 +312,7 @@ impl BlockscoutClient {
 
         debug!("Fetching source code from Blockscout: {}", url);
 
-        let response: ContractSourceResponse = self.client
-            .get(&url)
-            .send()
-            .await
-            .context("Failed to fetch from Blockscout")?
-            .json()
-            .await
-            .context("Failed to parse Blockscout response")?;
+        let response: ContractSourceResponse = self.get_json(&url).await?;
 
         if response.status != "1" {
             anyhow::bail!("Blockscout API error: {}", response.message);
@@ -135,14 +333,7 @@ impl BlockscoutClient {
 
         debug!("Fetching contract creation from Blockscout: {}", url);
 
-        let response: ContractCreationResponse = self.client
-            .get(&url)
-            .send()
-            .await
-            .context("Failed to fetch from Blockscout")?
-            .json()
-            .await
-            .context("Failed to parse Blockscout response")?;
+        let response: ContractCreationResponse = self.get_json(&url).await?;
 
         if response.status != "1" {
             anyhow::bail!("Blockscout API error: {}", response.message);
@@ -154,27 +345,50 @@ impl BlockscoutClient {
             .context("No contract creation data")
     }
 
-    /// Get recent transactions for a contract
+    /// Get token metadata (name, symbol, decimals, type, total supply)
+    ///
+    /// Returns Ok(None) when the address isn't a token Blockscout knows
+    /// about, so callers can fall back without treating it as an error.
+    pub async fn get_token_info(&self, address: Address) -> Result<Option<TokenInfo>> {
+        let url = format!(
+            "{}?module=token&action=getToken&contractaddress={:?}",
+            self.base_url, address
+        );
+
+        debug!("Fetching token info from Blockscout: {}", url);
+
+        let response: TokenInfoResponse = self.get_json(&url).await?;
+
+        if response.status != "1" {
+            // Non-token addresses come back as an error status, not a 404
+            let message = response.message.to_lowercase();
+            if message.contains("not found") || message.contains("no token") {
+                return Ok(None);
+            }
+            anyhow::bail!("Blockscout API error: {}", response.message);
+        }
+
+        Ok(response.result)
+    }
+
+    /// Get one page of recent transactions for a contract
+    ///
+    /// `page` is 1-based; `offset` is the page size. Use
+    /// [`get_all_transactions`](Self::get_all_transactions) to walk pages.
     pub async fn get_transactions(
         &self,
         address: Address,
-        limit: usize,
+        page: usize,
+        offset: usize,
     ) -> Result<Vec<Transaction>> {
         let url = format!(
-            "{}?module=account&action=txlist&address={:?}&startblock=0&endblock=99999999&page=1&offset={}&sort=desc",
-            self.base_url, address, limit
+            "{}?module=account&action=txlist&address={:?}&startblock=0&endblock=99999999&page={}&offset={}&sort=desc",
+            self.base_url, address, page, offset
         );
 
         debug!("Fetching transactions from Blockscout: {}", url);
 
-        let response: TransactionListResponse = self.client
-            .get(&url)
-            .send()
-            .await
-            .context("Failed to fetch from Blockscout")?
-            .json()
-            .await
-            .context("Failed to parse Blockscout response")?;
+        let response: TransactionListResponse = self.get_json(&url).await?;
 
         if response.status != "1" {
             // Empty result is OK
@@ -187,6 +401,39 @@ impl BlockscoutClient {
         Ok(response.result)
     }
 
+    /// Get up to `max` recent transactions, walking txlist pages
+    ///
+    /// Stops early on an empty or short page (including the "No transactions
+    /// found" terminal response, which `get_transactions` maps to an empty
+    /// vec), and pauses briefly between pages to respect rate limits.
+    pub async fn get_all_transactions(
+        &self,
+        address: Address,
+        max: usize,
+    ) -> Result<Vec<Transaction>> {
+        let mut all = Vec::new();
+        let mut page = 1;
+
+        while all.len() < max {
+            if page > 1 {
+                tokio::time::sleep(TXLIST_PAGE_DELAY).await;
+            }
+
+            let page_size = TXLIST_PAGE_SIZE.min(max - all.len());
+            let txs = self.get_transactions(address, page, page_size).await?;
+            let short_page = txs.len() < page_size;
+            all.extend(txs);
+
+            if short_page {
+                break;
+            }
+            page += 1;
+        }
+
+        all.truncate(max);
+        Ok(all)
+    }
+
     /// Extract project name from verified source code
     pub fn extract_project_name(&self, source: &ContractSource) -> Option<String> {
         // Strategy 1: Parse from import statements
@@ -271,13 +518,18 @@ impl BlockscoutClient {
             .trim()
             .to_string();
 
-        // Insert spaces before capital letters
+        // Insert spaces at word boundaries, keeping acronyms like "ERC20" intact
         let mut result = String::new();
-        for (i, c) in cleaned.chars().enumerate() {
-            if i > 0 && c.is_uppercase() {
-                result.push(' ');
+        let mut prev: Option<char> = None;
+        for c in cleaned.chars() {
+            if let Some(p) = prev {
+                // Boundary: lowercase/digit followed by uppercase (e.g. "pV3" or "20T")
+                if c.is_uppercase() && (p.is_lowercase() || p.is_ascii_digit()) {
+                    result.push(' ');
+                }
             }
             result.push(c);
+            prev = Some(c);
         }
 
         result.trim().to_string()
@@ -318,6 +570,17 @@ impl BlockscoutClient {
     }
 }
 
+/// Parse a Blockscout ABI string into a typed ABI
+///
+/// Blockscout returns a sentinel string instead of JSON for unverified
+/// contracts, which surfaces here as a parse error with context.
+pub fn parse_abi(abi: &str) -> Result<JsonAbi> {
+    if abi.is_empty() || abi.starts_with("Contract source code not verified") {
+        anyhow::bail!("Contract has no verified ABI");
+    }
+    serde_json::from_str(abi).context("Failed to parse contract ABI")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -346,4 +609,75 @@ mod tests {
         assert_eq!(client.clean_contract_name("ChainlinkAggregator"), "Chainlink Aggregator");
         assert_eq!(client.clean_contract_name("ERC20Token"), "ERC20 Token");
     }
+
+    #[tokio::test]
+    async fn test_rapid_calls_are_spaced_by_the_limiter() {
+        // 50 rps with a 1-token bucket: 6 calls need at least 5 refills
+        let client = BlockscoutClient::new().with_rate_limit(50.0);
+
+        let start = Instant::now();
+        for _ in 0..6 {
+            client.throttle().await;
+        }
+
+        assert!(
+            start.elapsed() >= Duration::from_millis(95),
+            "6 calls at 50 rps finished in {:?}",
+            start.elapsed()
+        );
+    }
+
+    #[test]
+    fn test_token_info_deserializes_getoken_result() {
+        let json = r#"{
+            "status": "1",
+            "message": "OK",
+            "result": {
+                "name": "Wrapped Ether",
+                "symbol": "WETH",
+                "decimals": "18",
+                "type": "ERC-20",
+                "totalSupply": "1000000000000000000000"
+            }
+        }"#;
+
+        let response: TokenInfoResponse = serde_json::from_str(json).unwrap();
+        let token = response.result.unwrap();
+        assert_eq!(token.symbol, "WETH");
+        assert_eq!(token.decimals, "18");
+        assert_eq!(token.token_type, "ERC-20");
+    }
+
+    #[test]
+    fn test_parse_sample_abi() {
+        let abi = r#"[
+            {
+                "type": "function",
+                "name": "balanceOf",
+                "inputs": [{"name": "owner", "type": "address"}],
+                "outputs": [{"name": "", "type": "uint256"}],
+                "stateMutability": "view"
+            },
+            {
+                "type": "event",
+                "name": "Transfer",
+                "inputs": [
+                    {"name": "from", "type": "address", "indexed": true},
+                    {"name": "to", "type": "address", "indexed": true},
+                    {"name": "value", "type": "uint256", "indexed": false}
+                ],
+                "anonymous": false
+            }
+        ]"#;
+
+        let parsed = parse_abi(abi).unwrap();
+        assert!(parsed.function("balanceOf").is_some());
+        assert!(parsed.event("Transfer").is_some());
+    }
+
+    #[test]
+    fn test_parse_abi_rejects_unverified_sentinel() {
+        assert!(parse_abi("Contract source code not verified").is_err());
+        assert!(parse_abi("").is_err());
+    }
 }
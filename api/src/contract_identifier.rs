@@ -3,13 +3,43 @@
 //! This module automatically identifies which protocol/project a contract belongs to
 //! by using multiple detection methods.
 
-use alloy_primitives::{Address, Bytes};
+use alloy_primitives::{keccak256, Address, Bytes, B256};
 use anyhow::{Context, Result};
+use dashmap::DashMap;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::OnceLock;
-use tracing::{debug, info, warn};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::rpc::{MegaEthClient, RawLog};
+
+/// EIP-1967 implementation slot: keccak256("eip1967.proxy.implementation") - 1
+const EIP1967_IMPLEMENTATION_SLOT: &str =
+    "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+/// EIP-1967 beacon slot: keccak256("eip1967.proxy.beacon") - 1
+const EIP1967_BEACON_SLOT: &str =
+    "0xa3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50";
+/// Legacy OpenZeppelin slot: keccak256("org.zeppelinos.proxy.implementation")
+const OZ_LEGACY_IMPLEMENTATION_SLOT: &str =
+    "0x7050c9e0f4ca769c69bd3a8ef740bc37934f8e2c036e5a723fd8ee048ed3f8c3";
+/// implementation() selector, for beacon proxies
+const IMPLEMENTATION_SELECTOR: &str = "0x5c60da1b";
+/// How many recent blocks to scan for event-signature analysis
+const EVENT_SCAN_BLOCKS: u64 = 2000;
+
+/// How long an identified contract stays cached: identities are stable, so
+/// this mostly bounds memory, not staleness
+const IDENTIFIED_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// How long a fallback result stays cached: an unidentified contract may get
+/// verified on the explorer or start emitting classifiable events later
+const UNIDENTIFIED_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+/// How many proxy links to follow before identifying the contract as-is;
+/// bounds the recursion so a cycle of proxies pointing at each other
+/// (adversarial on-chain data) can't overflow the stack
+const MAX_PROXY_HOPS: u32 = 4;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContractInfo {
@@ -22,24 +52,118 @@ pub struct ContractInfo {
 
 /// Main contract identifier
 pub struct ContractIdentifier {
+    /// Shared chain client: clones share the circuit breaker and reqwest
+    /// connection pool, so identification sees (and feeds) the same
+    /// endpoint-health view as the rest of the process
+    chain: MegaEthClient,
     rpc_client: Client,
     rpc_url: String,
     block_explorer_api_key: Option<String>,
+    /// Identified contracts by address, with when each was fetched; the
+    /// pipeline is several RPC round-trips, so rerunning it per call for
+    /// the same address is wasteful
+    cache: DashMap<Address, (ContractInfo, Instant)>,
+    identified_ttl: Duration,
+    unidentified_ttl: Duration,
+    cache_lookups: AtomicU64,
+    cache_hits: AtomicU64,
 }
 
 impl ContractIdentifier {
-    pub fn new(rpc_url: String, block_explorer_api_key: Option<String>) -> Self {
+    pub fn new(chain: MegaEthClient, block_explorer_api_key: Option<String>) -> Self {
+        let rpc_url = chain.rpc_url().to_string();
         Self {
+            chain,
             rpc_client: Client::new(),
             rpc_url,
             block_explorer_api_key,
+            cache: DashMap::new(),
+            identified_ttl: IDENTIFIED_CACHE_TTL,
+            unidentified_ttl: UNIDENTIFIED_CACHE_TTL,
+            cache_lookups: AtomicU64::new(0),
+            cache_hits: AtomicU64::new(0),
         }
     }
 
-    /// Main identification pipeline - tries multiple methods
+    /// Override how long cached results stay fresh (identified contracts,
+    /// then fallback results)
+    pub fn with_cache_ttls(mut self, identified: Duration, unidentified: Duration) -> Self {
+        self.identified_ttl = identified;
+        self.unidentified_ttl = unidentified;
+        self
+    }
+
+    /// Drop every cached identity, forcing the next calls to re-run the
+    /// pipeline (e.g. after loading new bytecode fingerprints)
+    pub fn clear_cache(&self) {
+        self.cache.clear();
+    }
+
+    /// Fraction of identify calls served from cache (0.0 before any lookups)
+    pub fn cache_hit_rate(&self) -> f64 {
+        let lookups = self.cache_lookups.load(Ordering::Relaxed);
+        if lookups == 0 {
+            return 0.0;
+        }
+        self.cache_hits.load(Ordering::Relaxed) as f64 / lookups as f64
+    }
+
+    /// Identify a contract, served from cache while the entry is fresh
+    ///
+    /// Fallback results expire on a shorter TTL than identified ones (see
+    /// [`UNIDENTIFIED_CACHE_TTL`]).
     pub async fn identify(&self, address: Address) -> Result<ContractInfo> {
+        self.identify_with_hops(address, 0).await
+    }
+
+    /// Cache-checked identification, carrying how many proxy links were
+    /// already followed to reach `address`
+    async fn identify_with_hops(&self, address: Address, proxy_hops: u32) -> Result<ContractInfo> {
+        self.cache_lookups.fetch_add(1, Ordering::Relaxed);
+        if let Some(entry) = self.cache.get(&address) {
+            let (info, fetched_at) = entry.value();
+            let ttl = if info.source == "Fallback" {
+                self.unidentified_ttl
+            } else {
+                self.identified_ttl
+            };
+            if fetched_at.elapsed() < ttl {
+                self.cache_hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(info.clone());
+            }
+        }
+
+        let info = self.identify_uncached(address, proxy_hops).await?;
+        self.cache.insert(address, (info.clone(), Instant::now()));
+        Ok(info)
+    }
+
+    /// Main identification pipeline - tries multiple methods
+    async fn identify_uncached(&self, address: Address, proxy_hops: u32) -> Result<ContractInfo> {
         info!("Identifying contract: {:?}", address);
 
+        // Proxies first: identify the implementation, not the proxy stub,
+        // so bytecode fingerprinting and name/symbol calls hit real code.
+        // The hop cap keeps proxy cycles from recursing forever; nothing is
+        // cached until a pipeline run completes, so the cache alone can't
+        // break a cycle
+        if proxy_hops < MAX_PROXY_HOPS {
+            if let Ok(Some(implementation)) = self.resolve_implementation(address).await {
+                if implementation != address {
+                    info!("Proxy detected, following to implementation: {:?}", implementation);
+                    let mut info =
+                        Box::pin(self.identify_with_hops(implementation, proxy_hops + 1)).await?;
+                    info.source = format!("{} (via proxy)", info.source);
+                    return Ok(info);
+                }
+            }
+        } else {
+            warn!(
+                "Proxy chain from {:?} exceeds {} hops, identifying the contract as-is",
+                address, MAX_PROXY_HOPS
+            );
+        }
+
         // Method 1: Try RPC name/symbol functions (fastest, works for tokens)
         if let Ok(info) = self.try_rpc_name_symbol(address).await {
             info!("✅ Identified via RPC name/symbol: {}", info.name);
@@ -75,15 +199,23 @@ impl ContractIdentifier {
         const NAME_SELECTOR: &str = "0x06fdde03"; // name()
         const SYMBOL_SELECTOR: &str = "0x95d89b41"; // symbol()
 
-        // Try calling name()
-        let name = match self.eth_call(address, NAME_SELECTOR).await {
-            Ok(result) => self.decode_string(&result)?,
+        // Both probes go out in one JSON-RPC batch; either call may
+        // revert independently on contracts without metadata
+        let calls = vec![
+            (address, NAME_SELECTOR.to_string()),
+            (address, SYMBOL_SELECTOR.to_string()),
+        ];
+        let mut results = self.chain.eth_call_batch(&calls).await?.into_iter();
+        let name_result = results.next().context("Batch dropped the name() entry")?;
+        let symbol_result = results.next().context("Batch dropped the symbol() entry")?;
+
+        let name = match name_result {
+            Ok(bytes) => crate::abi::decode_string(&bytes)?,
             Err(_) => return Err(anyhow::anyhow!("name() call failed")),
         };
 
-        // Try calling symbol()
-        let symbol = match self.eth_call(address, SYMBOL_SELECTOR).await {
-            Ok(result) => self.decode_string(&result)?,
+        let symbol = match symbol_result {
+            Ok(bytes) => crate::abi::decode_string(&bytes)?,
             Err(_) => name[..4.min(name.len())].to_string(),
         };
 
@@ -145,10 +277,16 @@ impl ContractIdentifier {
     async fn try_bytecode_fingerprint(&self, address: Address) -> Result<ContractInfo> {
         // Get contract bytecode
         let bytecode = self.get_code(address).await?;
-        let bytecode_hash = keccak256(&bytecode);
 
-        // Look up in known bytecode database
-        if let Some(info) = KNOWN_BYTECODES.get().and_then(|db| db.get(&bytecode_hash)) {
+        // Look up the full hash, then the metadata-stripped hash: identical
+        // source compiled with different metadata (IPFS hash, compiler
+        // patch level) differs only in the CBOR trailer
+        let full_hash = keccak256(&bytecode);
+        let stripped_hash = keccak256(strip_metadata(&bytecode));
+        let known = KNOWN_BYTECODES
+            .get()
+            .and_then(|db| db.get(&full_hash).or_else(|| db.get(&stripped_hash)));
+        if let Some(info) = known {
             return Ok(ContractInfo {
                 name: info.name.clone(),
                 symbol: info.symbol.clone(),
@@ -166,11 +304,85 @@ impl ContractIdentifier {
         Err(anyhow::anyhow!("No bytecode match found"))
     }
 
-    /// Method 4: Analyze event signatures from recent transactions
+    /// Method 4: Analyze event signatures from recent logs
+    ///
+    /// Classifies by the events a contract actually emits: Swap/Sync mark a
+    /// DEX pool, Transfer with an indexed tokenId marks an NFT, Transfer
+    /// with amount in data marks a token.
     async fn try_event_signatures(&self, address: Address) -> Result<ContractInfo> {
-        // This would require fetching recent transactions and analyzing event logs
-        // Placeholder for now
-        Err(anyhow::anyhow!("Event signature analysis not implemented"))
+        let latest = self.chain.get_latest_block_number().await?;
+        let from = latest.saturating_sub(EVENT_SCAN_BLOCKS);
+
+        let logs = self.chain.get_logs(address, from, latest).await?;
+        if logs.is_empty() {
+            return Err(anyhow::anyhow!("No recent logs to analyze"));
+        }
+
+        let (name, symbol, category) =
+            classify_event_logs(&logs).context("No known event signatures in recent logs")?;
+
+        Ok(ContractInfo {
+            name,
+            symbol,
+            category,
+            confidence: 0.75,
+            source: "Event signatures".to_string(),
+        })
+    }
+
+    /// Resolve a proxy's implementation address, if the contract is a proxy
+    ///
+    /// Checks the EIP-1967 implementation slot, the EIP-1967 beacon slot
+    /// (following the beacon's implementation()), and the legacy
+    /// OpenZeppelin slot, in that order.
+    pub async fn resolve_implementation(&self, address: Address) -> Result<Option<Address>> {
+        // EIP-1967 implementation slot
+        let word = self.get_storage_at(address, EIP1967_IMPLEMENTATION_SLOT).await?;
+        if let Some(implementation) = address_from_slot_word(&word) {
+            return Ok(Some(implementation));
+        }
+
+        // EIP-1967 beacon slot: the slot holds the beacon, which exposes
+        // implementation()
+        let word = self.get_storage_at(address, EIP1967_BEACON_SLOT).await?;
+        if let Some(beacon) = address_from_slot_word(&word) {
+            if let Ok(result) = self.eth_call(beacon, IMPLEMENTATION_SELECTOR).await {
+                if let Some(implementation) = address_from_slot_word(&result) {
+                    return Ok(Some(implementation));
+                }
+            }
+        }
+
+        // Legacy OpenZeppelin proxies predate EIP-1967
+        let word = self.get_storage_at(address, OZ_LEGACY_IMPLEMENTATION_SLOT).await?;
+        if let Some(implementation) = address_from_slot_word(&word) {
+            return Ok(Some(implementation));
+        }
+
+        Ok(None)
+    }
+
+    /// Helper: Read a storage slot via eth_getStorageAt
+    async fn get_storage_at(&self, address: Address, slot: &str) -> Result<String> {
+        let payload = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "eth_getStorageAt",
+            "params": [format!("{:?}", address), slot, "latest"],
+            "id": 1
+        });
+
+        let response: serde_json::Value = self.rpc_client
+            .post(&self.rpc_url)
+            .json(&payload)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        Ok(response["result"]
+            .as_str()
+            .context("Invalid RPC response")?
+            .to_string())
     }
 
     /// Helper: Make eth_call RPC request
@@ -223,28 +435,6 @@ impl ContractIdentifier {
         Ok(Bytes::from(hex::decode(&hex_code[2..]).context("Invalid hex")?))
     }
 
-    /// Helper: Decode ABI-encoded string
-    fn decode_string(&self, hex_data: &str) -> Result<String> {
-        // Strip 0x prefix
-        let data = hex::decode(&hex_data[2..])?;
-
-        if data.len() < 64 {
-            return Err(anyhow::anyhow!("Data too short"));
-        }
-
-        // String offset is at position 0-32
-        // String length is at position 32-64
-        let length = u32::from_be_bytes([data[60], data[61], data[62], data[63]]) as usize;
-
-        if data.len() < 64 + length {
-            return Err(anyhow::anyhow!("Data length mismatch"));
-        }
-
-        // String content starts at position 64
-        let string_bytes = &data[64..64 + length];
-        Ok(String::from_utf8_lossy(string_bytes).to_string())
-    }
-
     /// Helper: Infer category from contract/token name
     fn infer_category_from_name(&self, name: &str) -> String {
         let name_lower = name.to_lowercase();
@@ -309,10 +499,31 @@ impl ContractIdentifier {
         "other".to_string()
     }
 
-    /// Helper: Try partial bytecode matching (for contracts with constructor params)
+    /// Helper: Try partial bytecode matching
+    ///
+    /// Contracts whose trailing bytes differ per deployment (immutables,
+    /// constructor-arg-derived values) never match on the full hash, so
+    /// compare against known runtime prefixes instead: a match means the
+    /// fetched bytecode starts with a known contract's runtime portion.
     async fn try_partial_bytecode_match(&self, bytecode: &Bytes) -> Result<Option<ContractInfo>> {
-        // This would compare bytecode prefixes/suffixes against known patterns
-        // Placeholder for now
+        let Some(prefixes) = KNOWN_BYTECODE_PREFIXES.get() else {
+            return Ok(None);
+        };
+
+        for (prefix, info) in prefixes {
+            if !prefix.is_empty() && bytecode.starts_with(prefix) {
+                return Ok(Some(ContractInfo {
+                    name: info.name.clone(),
+                    symbol: info.symbol.clone(),
+                    category: info.category.clone(),
+                    // Lower than an exact hash match: the trailing bytes
+                    // were not compared
+                    confidence: 0.9,
+                    source: "Bytecode prefix match".to_string(),
+                }));
+            }
+        }
+
         Ok(None)
     }
 
@@ -320,7 +531,7 @@ impl ContractIdentifier {
     fn generate_fallback_name(&self, address: Address) -> ContractInfo {
         ContractInfo {
             name: format!("Contract {}", &format!("{:?}", address)[2..8].to_uppercase()),
-            symbol: format!("{}", &format!("{:?}", address)[2..6].to_uppercase()),
+            symbol: format!("{:?}", address)[2..6].to_uppercase(),
             category: "other".to_string(),
             confidence: 0.1,
             source: "Fallback".to_string(),
@@ -328,14 +539,200 @@ impl ContractIdentifier {
     }
 }
 
-// Known bytecode database (in production, this would be loaded from a file/database)
-static KNOWN_BYTECODES: OnceLock<HashMap<[u8; 32], ContractInfo>> = OnceLock::new();
+// Known bytecode database, populated once at startup by load_fingerprints
+static KNOWN_BYTECODES: OnceLock<HashMap<B256, ContractInfo>> = OnceLock::new();
+
+// Runtime-portion prefixes for partial matching, from entries that carry
+// full bytecode in the fingerprint file
+static KNOWN_BYTECODE_PREFIXES: OnceLock<BytecodePrefixes> = OnceLock::new();
+
+/// One entry in the fingerprint file
+#[derive(Debug, Deserialize)]
+struct FingerprintEntry {
+    /// keccak256 of the deployed (runtime) bytecode, 0x-prefixed hex
+    bytecode_hash: String,
+    name: String,
+    symbol: String,
+    category: String,
+    /// Optional full runtime bytecode (0x-prefixed hex); entries carrying it
+    /// also participate in partial (prefix) matching
+    #[serde(default)]
+    bytecode: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FingerprintFile {
+    fingerprints: Vec<FingerprintEntry>,
+}
+
+/// Runtime-bytecode prefixes paired with the contract they identify
+type BytecodePrefixes = Vec<(Vec<u8>, ContractInfo)>;
+
+/// Parse a fingerprint file into the exact-hash map and the prefix list
+///
+/// Split out from [`load_fingerprints`] so parsing is testable without
+/// touching the process-wide OnceLocks.
+fn parse_fingerprints(json: &str) -> Result<(HashMap<B256, ContractInfo>, BytecodePrefixes)> {
+    let file: FingerprintFile =
+        serde_json::from_str(json).context("Failed to parse fingerprint file")?;
+
+    let mut by_hash = HashMap::new();
+    let mut prefixes = Vec::new();
+    for entry in file.fingerprints {
+        let info = ContractInfo {
+            name: entry.name,
+            symbol: entry.symbol,
+            category: entry.category,
+            confidence: 0.98,
+            source: "Bytecode fingerprint".to_string(),
+        };
+
+        let hash: B256 = entry
+            .bytecode_hash
+            .parse()
+            .with_context(|| format!("Invalid bytecode_hash for {}", info.name))?;
+        by_hash.insert(hash, info.clone());
+
+        if let Some(code_hex) = entry.bytecode {
+            let code = hex::decode(code_hex.trim_start_matches("0x"))
+                .with_context(|| format!("Invalid bytecode hex for {}", info.name))?;
+            // Entries carrying full bytecode also match on the
+            // metadata-stripped hash
+            by_hash.insert(keccak256(strip_metadata(&code)), info.clone());
+            prefixes.push((code, info));
+        }
+    }
+
+    Ok((by_hash, prefixes))
+}
+
+/// Load the bytecode fingerprint database from a JSON file at startup
+///
+/// File format:
+///
+/// ```json
+/// {
+///   "fingerprints": [
+///     {
+///       "bytecode_hash": "0x<keccak256 of runtime bytecode>",
+///       "name": "Uniswap V2 Pair",
+///       "symbol": "UNIV2",
+///       "category": "dex",
+///       "bytecode": "0x..."   // optional, enables prefix matching
+///     }
+///   ]
+/// }
+/// ```
+///
+/// Returns the number of fingerprints loaded. Errors if the database was
+/// already loaded in this process.
+pub fn load_fingerprints(path: &std::path::Path) -> Result<usize> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read fingerprint file {}", path.display()))?;
+    let (by_hash, prefixes) = parse_fingerprints(&json)?;
+
+    let count = by_hash.len();
+    KNOWN_BYTECODES
+        .set(by_hash)
+        .map_err(|_| anyhow::anyhow!("Fingerprint database already loaded"))?;
+    let _ = KNOWN_BYTECODE_PREFIXES.set(prefixes);
+    Ok(count)
+}
+
+/// Classify a contract by the events it emits
+///
+/// Returns (name, symbol, category), or None when no known signature shows
+/// up. DEX signals win over transfer signals: pools emit Transfer too (LP
+/// tokens), but Swap/Sync are unambiguous.
+fn classify_event_logs(logs: &[RawLog]) -> Option<(String, String, String)> {
+    let swap_v2 = keccak256(b"Swap(address,uint256,uint256,uint256,uint256,address)");
+    let swap_v3 = keccak256(b"Swap(address,address,int256,int256,uint160,uint128,int24)");
+    let sync = keccak256(b"Sync(uint112,uint112)");
+    let transfer = keccak256(b"Transfer(address,address,uint256)");
+    let transfer_single =
+        keccak256(b"TransferSingle(address,address,address,uint256,uint256)");
+    let transfer_batch =
+        keccak256(b"TransferBatch(address,address,address,uint256[],uint256[])");
+
+    let mut saw_token_transfer = false;
+    let mut saw_nft_transfer = false;
+
+    for log in logs {
+        let Some(topic0) = log.topics.first() else {
+            continue;
+        };
+
+        if *topic0 == swap_v2 || *topic0 == swap_v3 || *topic0 == sync {
+            return Some(("DEX Pool".to_string(), "POOL".to_string(), "dex".to_string()));
+        }
+        if *topic0 == transfer_single || *topic0 == transfer_batch {
+            saw_nft_transfer = true;
+        }
+        if *topic0 == transfer {
+            // ERC-20 and ERC-721 share the Transfer signature; ERC-721
+            // indexes the tokenId, giving 4 topics instead of 3
+            if log.topics.len() == 4 {
+                saw_nft_transfer = true;
+            } else {
+                saw_token_transfer = true;
+            }
+        }
+    }
+
+    if saw_nft_transfer {
+        return Some((
+            "NFT Collection".to_string(),
+            "NFT".to_string(),
+            "nft".to_string(),
+        ));
+    }
+    if saw_token_transfer {
+        return Some(("Token".to_string(), "TKN".to_string(), "token".to_string()));
+    }
+
+    None
+}
+
+/// Strip the trailing Solidity CBOR metadata section from deployed bytecode
+///
+/// solc appends `<CBOR-encoded metadata> <2-byte big-endian length>` to the
+/// runtime bytecode. The section carries the IPFS/swarm hash of the source
+/// metadata and the compiler version, so byte-identical contracts built in
+/// different environments diverge only here. Returns the input unchanged
+/// when no plausible trailer is present.
+fn strip_metadata(bytecode: &[u8]) -> &[u8] {
+    let len = bytecode.len();
+    if len < 2 {
+        return bytecode;
+    }
+
+    let meta_len = u16::from_be_bytes([bytecode[len - 2], bytecode[len - 1]]) as usize;
+    let Some(body_len) = len.checked_sub(meta_len + 2) else {
+        return bytecode;
+    };
+
+    // Every solc trailer starts with a small CBOR map (0xa0-0xb7); anything
+    // else is code that happens to end in a plausible length
+    match bytecode.get(body_len) {
+        Some(first) if (0xa0..=0xb7).contains(first) => &bytecode[..body_len],
+        _ => bytecode,
+    }
+}
 
-fn keccak256(data: &[u8]) -> [u8; 32] {
-    use sha3::{Digest, Keccak256};
-    let mut hasher = Keccak256::new();
-    hasher.update(data);
-    hasher.finalize().into()
+/// Extract the address packed into the low 20 bytes of a storage word
+///
+/// Returns None for a zero word (slot unset, i.e. not a proxy).
+fn address_from_slot_word(word: &str) -> Option<Address> {
+    let bytes = hex::decode(word.trim_start_matches("0x")).ok()?;
+    if bytes.len() < 20 || bytes.iter().all(|&b| b == 0) {
+        return None;
+    }
+    // Address is right-aligned; everything above it must be zero padding
+    let (padding, addr) = bytes.split_at(bytes.len() - 20);
+    if padding.iter().any(|&b| b != 0) {
+        return None;
+    }
+    Some(Address::from_slice(addr))
 }
 
 #[derive(Debug, Deserialize)]
@@ -354,16 +751,323 @@ struct ContractData {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
 
     #[tokio::test]
     async fn test_identify_erc20() {
-        let identifier = ContractIdentifier::new(
-            "https://mainnet.megaeth.com/rpc".to_string(),
-            None,
-        );
+        let chain = MegaEthClient::new("https://mainnet.megaeth.com/rpc")
+            .await
+            .unwrap();
+        let _identifier = ContractIdentifier::new(chain, None);
 
         // This would test with a known ERC-20 contract
         // let info = identifier.identify(address).await.unwrap();
         // assert_eq!(info.category, "other"); // or specific category
     }
+
+    /// Serve a canned JSON-RPC response to every POST, counting requests
+    async fn counting_rpc_stub() -> (String, Arc<std::sync::atomic::AtomicU64>) {
+        use axum::routing::post;
+
+        let hits = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let counter = Arc::clone(&hits);
+        let app = axum::Router::new().route(
+            "/",
+            post(move |axum::Json(req): axum::Json<serde_json::Value>| {
+                counter.fetch_add(1, Ordering::Relaxed);
+                async move {
+                    axum::Json(serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": req["id"],
+                        "result": "0x"
+                    }))
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (url, hits)
+    }
+
+    /// Stub whose eth_getStorageAt answers make `a` and `b` look like
+    /// EIP-1967 proxies pointing at each other; every other call (including
+    /// batches) answers "0x"
+    async fn proxy_cycle_stub(a: Address, b: Address) -> String {
+        use axum::routing::post;
+
+        fn slot_word(addr: Address) -> String {
+            format!("0x{}{}", "00".repeat(12), hex::encode(addr))
+        }
+
+        let app = axum::Router::new().route(
+            "/",
+            post(move |axum::Json(req): axum::Json<serde_json::Value>| async move {
+                if let Some(calls) = req.as_array() {
+                    let entries: Vec<serde_json::Value> = calls
+                        .iter()
+                        .map(|call| {
+                            serde_json::json!({
+                                "jsonrpc": "2.0",
+                                "id": call["id"],
+                                "result": "0x"
+                            })
+                        })
+                        .collect();
+                    return axum::Json(serde_json::Value::Array(entries));
+                }
+
+                let result = if req["method"] == "eth_getStorageAt" {
+                    let target = req["params"][0].as_str().unwrap_or("");
+                    if target.eq_ignore_ascii_case(&format!("{:?}", a)) {
+                        slot_word(b)
+                    } else {
+                        slot_word(a)
+                    }
+                } else {
+                    "0x".to_string()
+                };
+                axum::Json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": req["id"],
+                    "result": result
+                }))
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_proxy_cycle_stops_at_the_hop_cap() {
+        let a = Address::repeat_byte(0xaa);
+        let b = Address::repeat_byte(0xbb);
+        let url = proxy_cycle_stub(a, b).await;
+        let chain = MegaEthClient::new(&url).await.unwrap();
+        let identifier = ContractIdentifier::new(chain, None);
+
+        // A cycle can't be identified as anything; the point is that the
+        // pipeline terminates with the fallback instead of overflowing
+        let info = identifier.identify(a).await.unwrap();
+        assert!(info.source.starts_with("Fallback"), "{}", info.source);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_identify_is_served_from_cache() {
+        let (url, hits) = counting_rpc_stub().await;
+        let chain = MegaEthClient::new(&url).await.unwrap();
+        let identifier = ContractIdentifier::new(chain, None);
+        let address = Address::repeat_byte(0x42);
+
+        // Everything answers "0x", so the pipeline falls through to the
+        // fallback name, which still gets cached
+        let first = identifier.identify(address).await.unwrap();
+        assert_eq!(first.source, "Fallback");
+        let requests_after_first = hits.load(Ordering::Relaxed);
+        assert!(requests_after_first > 0, "first call must hit the RPC");
+
+        let second = identifier.identify(address).await.unwrap();
+        assert_eq!(second.name, first.name);
+        assert_eq!(
+            hits.load(Ordering::Relaxed),
+            requests_after_first,
+            "second call must not hit the RPC"
+        );
+        assert_eq!(identifier.cache_hit_rate(), 0.5);
+
+        // Clearing the cache forces the pipeline to run again
+        identifier.clear_cache();
+        identifier.identify(address).await.unwrap();
+        assert!(hits.load(Ordering::Relaxed) > requests_after_first);
+    }
+
+    const FINGERPRINT_FIXTURE: &str = r#"{
+        "fingerprints": [
+            {
+                "bytecode_hash": "0x29045a592007d0c246ef02c2223570da9522d0cf0f73282c79a1bc8f0bb2c238",
+                "name": "Wrapped Ether",
+                "symbol": "WETH",
+                "category": "token"
+            },
+            {
+                "bytecode_hash": "0x0000000000000000000000000000000000000000000000000000000000000001",
+                "name": "Uniswap V2 Pair",
+                "symbol": "UNIV2",
+                "category": "dex",
+                "bytecode": "0x60806040"
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_fingerprints_fixture() {
+        let (by_hash, prefixes) = parse_fingerprints(FINGERPRINT_FIXTURE).unwrap();
+        // Two declared hashes, plus the stripped-bytecode hash derived from
+        // the entry that carries full bytecode
+        assert_eq!(by_hash.len(), 3);
+        assert_eq!(prefixes.len(), 1, "only the entry with bytecode gets a prefix");
+
+        let weth_hash: B256 = "0x29045a592007d0c246ef02c2223570da9522d0cf0f73282c79a1bc8f0bb2c238"
+            .parse()
+            .unwrap();
+        let weth = &by_hash[&weth_hash];
+        assert_eq!(weth.name, "Wrapped Ether");
+        assert_eq!(weth.source, "Bytecode fingerprint");
+
+        let (prefix, pair) = &prefixes[0];
+        assert_eq!(prefix, &vec![0x60, 0x80, 0x60, 0x40]);
+        assert_eq!(pair.symbol, "UNIV2");
+    }
+
+    #[test]
+    fn test_load_fingerprints_populates_database() {
+        let path = std::env::temp_dir().join(format!(
+            "megaviz-fingerprints-{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&path, FINGERPRINT_FIXTURE).unwrap();
+
+        // The OnceLock is process-wide, so this is the only test that loads
+        let count = load_fingerprints(&path).unwrap();
+        assert_eq!(count, 3);
+        assert!(KNOWN_BYTECODES.get().is_some());
+        assert_eq!(KNOWN_BYTECODE_PREFIXES.get().unwrap().len(), 1);
+
+        // A second load must refuse rather than silently replace
+        assert!(load_fingerprints(&path).is_err());
+        std::fs::remove_file(&path).ok();
+    }
+
+    /// Runtime bytecode ending in a solc-style `a264...0033` metadata trailer
+    fn bytecode_with_metadata(body: &[u8], ipfs_byte: u8) -> Vec<u8> {
+        // 0xa2 map, "ipfs" key, then padding up to the declared 0x33 bytes
+        let mut metadata = vec![0xa2, 0x64, b'i', b'p', b'f', b's', ipfs_byte];
+        metadata.resize(0x33, 0x00);
+
+        let mut code = body.to_vec();
+        code.extend_from_slice(&metadata);
+        code.extend_from_slice(&[0x00, 0x33]);
+        code
+    }
+
+    #[test]
+    fn test_strip_metadata_removes_trailer() {
+        let body = [0x60, 0x80, 0x60, 0x40, 0x52];
+        let code = bytecode_with_metadata(&body, 0x01);
+        assert_eq!(strip_metadata(&code), body);
+    }
+
+    #[test]
+    fn test_differing_metadata_strips_to_same_hash() {
+        let body = [0x60, 0x80, 0x60, 0x40, 0x52];
+        let a = bytecode_with_metadata(&body, 0x01);
+        let b = bytecode_with_metadata(&body, 0x02);
+        assert_ne!(keccak256(&a), keccak256(&b));
+        assert_eq!(
+            keccak256(strip_metadata(&a)),
+            keccak256(strip_metadata(&b))
+        );
+    }
+
+    #[test]
+    fn test_strip_metadata_leaves_plain_code_alone() {
+        // Too short to carry a trailer
+        assert_eq!(strip_metadata(&[0x60]), &[0x60]);
+        // Declared length larger than the code
+        assert_eq!(strip_metadata(&[0x60, 0xff, 0xff]), &[0x60, 0xff, 0xff]);
+        // Plausible length but the section doesn't start with a CBOR map
+        let code = [0x60, 0x80, 0x52, 0x00, 0x02];
+        assert_eq!(strip_metadata(&code), code);
+    }
+
+    #[test]
+    fn test_eip1967_slot_constants() {
+        // EIP-1967 defines the slots as keccak256(label) - 1
+        let implementation =
+            alloy_primitives::U256::from_be_bytes(keccak256(b"eip1967.proxy.implementation").0)
+                - alloy_primitives::U256::from(1);
+        assert_eq!(
+            format!("0x{:064x}", implementation),
+            EIP1967_IMPLEMENTATION_SLOT
+        );
+
+        let beacon = alloy_primitives::U256::from_be_bytes(keccak256(b"eip1967.proxy.beacon").0)
+            - alloy_primitives::U256::from(1);
+        assert_eq!(format!("0x{:064x}", beacon), EIP1967_BEACON_SLOT);
+
+        // The legacy OpenZeppelin slot is the plain hash of its label
+        assert_eq!(
+            format!("{:?}", keccak256(b"org.zeppelinos.proxy.implementation")),
+            OZ_LEGACY_IMPLEMENTATION_SLOT
+        );
+    }
+
+    fn test_log(topics: Vec<B256>) -> RawLog {
+        RawLog {
+            address: Address::ZERO,
+            topics,
+            data: Bytes::new(),
+            block_number: 1,
+        }
+    }
+
+    #[test]
+    fn test_classify_swap_logs_as_dex() {
+        let logs = vec![
+            test_log(vec![
+                keccak256(b"Transfer(address,address,uint256)"),
+                B256::ZERO,
+                B256::ZERO,
+            ]),
+            test_log(vec![keccak256(b"Sync(uint112,uint112)")]),
+        ];
+        // Swap/Sync outrank the LP-token Transfer
+        let (_, _, category) = classify_event_logs(&logs).unwrap();
+        assert_eq!(category, "dex");
+    }
+
+    #[test]
+    fn test_classify_transfer_topic_counts() {
+        let transfer = keccak256(b"Transfer(address,address,uint256)");
+
+        // 3 topics: amount lives in data, so this is an ERC-20
+        let erc20 = vec![test_log(vec![transfer, B256::ZERO, B256::ZERO])];
+        assert_eq!(classify_event_logs(&erc20).unwrap().2, "token");
+
+        // 4 topics: indexed tokenId, so this is an ERC-721
+        let erc721 = vec![test_log(vec![transfer, B256::ZERO, B256::ZERO, B256::ZERO])];
+        assert_eq!(classify_event_logs(&erc721).unwrap().2, "nft");
+    }
+
+    #[test]
+    fn test_classify_unknown_signatures_returns_none() {
+        let logs = vec![test_log(vec![keccak256(b"SomethingElse(uint256)")])];
+        assert!(classify_event_logs(&logs).is_none());
+    }
+
+    #[test]
+    fn test_address_from_slot_word() {
+        // Implementation slot layout: address right-aligned in the 32-byte word
+        let word = "0x000000000000000000000000a2327a938febf5fec13bacfb16ae10ecbc4cbdcf";
+        assert_eq!(
+            address_from_slot_word(word),
+            Some("0xa2327a938febf5fec13bacfb16ae10ecbc4cbdcf".parse().unwrap())
+        );
+
+        // Unset slot (not a proxy)
+        let zero = "0x0000000000000000000000000000000000000000000000000000000000000000";
+        assert_eq!(address_from_slot_word(zero), None);
+
+        // Garbage in the padding bytes is not an address
+        let dirty = "0x010000000000000000000000a2327a938febf5fec13bacfb16ae10ecbc4cbdcf";
+        assert_eq!(address_from_slot_word(dirty), None);
+    }
 }
@@ -1,7 +1,12 @@
+pub mod abi;
 pub mod blockscout_client;
+pub mod contract_identifier;
 pub mod metrics;
 pub mod processor;
 pub mod questdb;
+#[cfg(feature = "replay")]
 pub mod replay;
 pub mod rpc;
 pub mod server;
+pub mod system_contracts;
+pub mod telemetry;
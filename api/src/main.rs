@@ -1,20 +1,28 @@
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 use anyhow::{Context, Result};
 use tokio::net::TcpListener;
 use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, Level};
 use tracing_subscriber::EnvFilter;
 
 use megaviz_api::metrics::MetricsStore;
-use megaviz_api::rpc::{BlockEvent, BlockPoller, MegaEthClient};
-use megaviz_api::server::create_router;
+use megaviz_api::questdb::{QuestDBReader, QuestDBWriter};
+use megaviz_api::rpc::{BlockEvent, BlockPoller, MegaEthClient, TentativeBlockEvent};
+use megaviz_api::contract_identifier::ContractIdentifier;
+use megaviz_api::server::{create_router_with_services, FeeOracle};
 
 /// Default configuration
 const DEFAULT_RPC_URL: &str = "https://carrot.megaeth.com/rpc";
 const DEFAULT_PORT: u16 = 3001;
 const DEFAULT_POLL_INTERVAL_MS: u64 = 1000;
 const DEFAULT_CONFIRMATION_BLOCKS: u64 = 5;
+/// At ~10ms blocks the chain can produce 100 blocks/s and the poller emits
+/// up to 100 per poll, so buffer ten seconds of events before slow
+/// subscribers start lagging
+const DEFAULT_WS_BUFFER_BLOCKS: usize = 1000;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -44,12 +52,24 @@ async fn main() -> Result<()> {
         .ok()
         .and_then(|p| p.parse().ok())
         .unwrap_or(DEFAULT_CONFIRMATION_BLOCKS);
+    let start_block: Option<u64> = std::env::var("START_BLOCK")
+        .ok()
+        .and_then(|p| p.parse().ok());
+    let end_block: Option<u64> = std::env::var("END_BLOCK")
+        .ok()
+        .and_then(|p| p.parse().ok());
 
     info!("MegaViz API starting...");
     info!("RPC URL: {}", rpc_url);
     info!("Port: {}", port);
     info!("Poll interval: {}ms", poll_interval_ms);
     info!("Confirmation blocks: {}", confirmation_blocks);
+    if let Some(start) = start_block {
+        info!("Start block: {}", start);
+    }
+    if let Some(end) = end_block {
+        info!("End block: {} (backfill mode, poller stops when reached)", end);
+    }
 
     // Initialize components
     let client = MegaEthClient::new(&rpc_url)
@@ -60,27 +80,136 @@ async fn main() -> Result<()> {
     let chain_id = client.get_chain_id().await?;
     info!("Connected to chain ID: {}", chain_id);
 
-    let store = MetricsStore::new();
+    // Load bytecode fingerprints when a database file is configured
+    if let Ok(path) = std::env::var("FINGERPRINT_DB_PATH") {
+        match megaviz_api::contract_identifier::load_fingerprints(std::path::Path::new(&path)) {
+            Ok(count) => info!("Loaded {} bytecode fingerprints from {}", count, path),
+            Err(e) => tracing::warn!("Bytecode fingerprints disabled: {}", e),
+        }
+    }
+
+    let store = MetricsStore::from_env();
+    // Roughly 10ms per block on MegaETH; memory is ~1 KB per block plus
+    // per-transaction overhead
+    info!(
+        "Metrics retention: {} blocks (~{:.1} minutes of chain time at 10ms blocks)",
+        store.max_blocks(),
+        store.max_blocks() as f64 * 0.01 / 60.0
+    );
 
     // Create broadcast channel for real-time block updates
-    let (block_tx, _) = broadcast::channel::<BlockEvent>(100);
+    let ws_buffer_blocks: usize = std::env::var("WS_BUFFER_BLOCKS")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(DEFAULT_WS_BUFFER_BLOCKS)
+        .max(1);
+    let (block_tx, _) = broadcast::channel::<BlockEvent>(ws_buffer_blocks);
+    let (tentative_tx, _) = broadcast::channel::<TentativeBlockEvent>(ws_buffer_blocks);
 
     // Create and start the block poller
-    let poller = BlockPoller::new(
-        MegaEthClient::new(&rpc_url).await?,
+    // Clones share the RPC circuit breaker, so /health reflects the same
+    // endpoint view the poller and fee oracle act on
+    let mut poller = BlockPoller::new(
+        client.clone(),
         store.clone(),
         confirmation_blocks,
         Duration::from_millis(poll_interval_ms),
         block_tx.clone(),
-    );
+    )
+    .with_block_range(start_block, end_block)
+    .with_process_concurrency(
+        std::env::var("PROCESS_CONCURRENCY")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(8),
+    )
+    .with_sampling(
+        std::env::var("SAMPLE_EVERY_N_BLOCKS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1),
+    )
+    .with_code_size_fetching(
+        std::env::var("FETCH_DEPLOYED_CODE_SIZE")
+            .map(|v| v != "0" && !v.eq_ignore_ascii_case("false"))
+            .unwrap_or(true),
+    )
+    .with_tentative_channel(tentative_tx.clone());
+
+    // Persist blocks to QuestDB when an ILP endpoint is configured
+    if std::env::var("QUESTDB_ILP_ADDR").is_ok() {
+        match QuestDBWriter::connect().await {
+            Ok(writer) => {
+                info!("QuestDB persistence enabled");
+                poller = poller.with_questdb_writer(writer);
+            }
+            Err(e) => {
+                tracing::warn!("QuestDB persistence disabled: {}", e);
+            }
+        }
+    }
+
+    // Poller stops on the same signal that stops the HTTP server, so a
+    // final QuestDB flush isn't cut off mid-write
+    let shutdown = CancellationToken::new();
+    poller = poller.with_shutdown(shutdown.clone());
 
     // Spawn the poller task
-    tokio::spawn(async move {
+    let poller_task = tokio::spawn(async move {
         poller.run().await;
     });
 
+    // Restrict CORS to an explicit origin allow-list when configured;
+    // unset means any origin (development default)
+    let allowed_origins: Option<Vec<String>> = std::env::var("CORS_ALLOWED_ORIGINS")
+        .ok()
+        .map(|list| {
+            list.split(',')
+                .map(|origin| origin.trim().to_string())
+                .filter(|origin| !origin.is_empty())
+                .collect()
+        });
+    if let Some(origins) = &allowed_origins {
+        info!("CORS restricted to {} origin(s)", origins.len());
+    }
+
+    // Reader serves /stats/compare baselines older than in-memory retention
+    let questdb_reader = if std::env::var("QUESTDB_ILP_ADDR").is_ok()
+        || std::env::var("QUESTDB_PG_CONN").is_ok()
+    {
+        match QuestDBReader::connect().await {
+            Ok(reader) => {
+                // Create any missing tables so a fresh QuestDB serves
+                // reads immediately instead of erroring until first write
+                if let Err(e) = reader.ensure_schema().await {
+                    tracing::warn!("QuestDB schema setup failed: {}", e);
+                }
+                Some(Arc::new(reader))
+            }
+            Err(e) => {
+                tracing::warn!("QuestDB reader unavailable: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
     // Create the HTTP server
-    let router = create_router(store, block_tx);
+    let fee_oracle = FeeOracle::new(client.clone());
+    let contracts = ContractIdentifier::new(
+        client.clone(),
+        std::env::var("BLOCK_EXPLORER_API_KEY").ok(),
+    );
+    let router = create_router_with_services(
+        store,
+        block_tx,
+        tentative_tx,
+        fee_oracle,
+        contracts,
+        questdb_reader,
+        allowed_origins,
+    );
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     let listener = TcpListener::bind(addr).await?;
 
@@ -89,13 +218,61 @@ async fn main() -> Result<()> {
     info!("Endpoints:");
     info!("  GET /health              - Health check");
     info!("  GET /stats/window        - Window statistics (query: seconds=60)");
+    info!("  GET /stats/mini-block-gas - Gas distribution across mini-blocks (query: seconds=60)");
+    info!("  GET /stats/system-activity - Known system contract activity (query: seconds=60)");
+    info!("  GET /stats/compare       - Recent window vs offset baseline (query: recent_seconds, baseline_seconds, baseline_offset)");
+    info!("  GET /stats/histogram     - Metric distribution (query: seconds, metric, buckets, log)");
+    info!("  GET /stats/percentile-audit - Exact vs QuestDB approx percentile (query: metric, seconds, percentile)");
+    info!("  GET /stats/tx-types      - Transaction-type mix (query: seconds=60)");
+    info!("  GET /stats/deployments   - Live contract deployments (query: seconds=60)");
+    info!("  GET /history/blocks      - Long-range block history from QuestDB (query: window=1m..7d, gaps)");
+    info!("  GET /deployments/heatmap - Deployment heatmap from QuestDB (query: view=daily|weekly|monthly)");
+    info!("  GET /deployments/details - Deployments in a time bucket (query: start, end)");
     info!("  GET /blocks/:number      - Get block metrics");
     info!("  GET /blocks/recent       - Get recent blocks (query: count=100)");
+    info!("  GET /blocks/range        - Get blocks by range (query: start, end, limit=100)");
+    info!("  GET /transactions/:hash  - Per-transaction resource breakdown");
     info!("  GET /viz/ring            - Ring visualization data");
     info!("  GET /viz/dials           - Dial visualization data");
-    info!("  WS  /ws/blocks           - Real-time block stream");
+    info!("  GET /viz/fees            - Base fee and suggested priority fee");
+    info!("  GET /contracts/:addr/identify - Identify a contract (cached)");
+    info!("  WS  /ws/blocks           - Real-time block stream (?mode=head for the unconfirmed tip)");
+    info!("  GET /sse/blocks          - Real-time block stream (SSE)");
+    info!("  GET /metrics             - Prometheus operational metrics");
+
+    axum::serve(listener, router)
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
 
-    axum::serve(listener, router).await?;
+    // Let in-flight block processing finish before exiting
+    info!("Shutting down, waiting for poller to stop...");
+    shutdown.cancel();
+    let _ = poller_task.await;
 
     Ok(())
 }
+
+/// Resolves when the process receives Ctrl-C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl-C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
@@ -1,10 +1,18 @@
+mod p2;
+mod percentile_report;
 mod rolling_stats;
 mod store;
 mod types;
 
+pub use p2::{P2Quantile, StreamingPercentiles};
+pub use percentile_report::{MetricRow, PercentileReport};
 pub use rolling_stats::{
-    limits, AllMetricStats, MetricSample, NormalizedBlockMetrics, NormalizedMetric,
-    PercentileStats, RollingStats,
+    baseline_window_from_env, limits, stats_window_from_env, AllMetricStats, MetricSample,
+    NormalizedBlockMetrics, NormalizedMetric, PercentileStats, RollingStats,
 };
 pub use store::MetricsStore;
-pub use types::{BlockMetrics, TransactionMetrics, WindowStats};
+pub use types::{
+    AddressActivity, BlockMetrics, DeploymentEvent, DeploymentStats, HistogramBucket,
+    MetricHistogram, MiniBlockGasStats, Sparkline, SystemActivityStats, SystemContractActivity,
+    TopAddressesStats, TransactionMetrics, WindowReference, WindowStats,
+};
@@ -0,0 +1,385 @@
+//! Streaming percentile estimation via the P² algorithm
+//!
+//! Jain & Chlamtac's P² algorithm maintains five markers per tracked
+//! quantile and adjusts them with a piecewise-parabolic fit on every
+//! observation, so an estimate is available in O(1) without storing or
+//! sorting the sample stream. Estimates converge to within a few percent
+//! of the exact quantile for well-behaved distributions.
+//!
+//! The classic algorithm summarizes the whole stream since startup. For
+//! long-running collectors that is the wrong semantic — load shifts and
+//! the estimate should follow — so [`StreamingPercentiles`] supports an
+//! optional decay horizon: once the marker positions reach the horizon
+//! they are halved, which down-weights older observations exponentially
+//! and makes the estimate behave like a moving window of roughly
+//! horizon-many samples.
+
+use super::rolling_stats::PercentileStats;
+
+/// Number of marker heights/positions the P² algorithm maintains
+const MARKERS: usize = 5;
+
+/// P² estimator for a single quantile
+///
+/// Feed observations with [`observe`](Self::observe) and read the current
+/// estimate with [`estimate`](Self::estimate). Until five observations
+/// have arrived the estimator buffers them and computes the quantile
+/// exactly, so early reads are never garbage.
+#[derive(Debug, Clone)]
+pub struct P2Quantile {
+    /// Target quantile in (0, 1)
+    p: f64,
+    /// Marker heights q_1..q_5 (estimates of min, lower mid, quantile,
+    /// upper mid, max)
+    q: [f64; MARKERS],
+    /// Actual marker positions n_1..n_5 (1-based ranks)
+    n: [f64; MARKERS],
+    /// Desired marker positions
+    np: [f64; MARKERS],
+    /// Per-observation increments of the desired positions
+    dnp: [f64; MARKERS],
+    /// Buffered observations during the initial phase
+    initial: Vec<f64>,
+    /// Total observations seen
+    count: u64,
+}
+
+impl P2Quantile {
+    /// Create an estimator for quantile `p` (e.g. 0.5 for the median)
+    pub fn new(p: f64) -> Self {
+        assert!(p > 0.0 && p < 1.0, "quantile must be in (0, 1), got {p}");
+        Self {
+            p,
+            q: [0.0; MARKERS],
+            n: [0.0; MARKERS],
+            np: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            dnp: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+            initial: Vec::with_capacity(MARKERS),
+            count: 0,
+        }
+    }
+
+    /// Total observations fed to this estimator
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Record one observation
+    pub fn observe(&mut self, x: f64) {
+        self.count += 1;
+
+        // Initial phase: buffer the first five observations, then seed the
+        // markers from them in sorted order
+        if self.count <= MARKERS as u64 {
+            self.initial.push(x);
+            if self.initial.len() == MARKERS {
+                self.initial.sort_by(f64::total_cmp);
+                for i in 0..MARKERS {
+                    self.q[i] = self.initial[i];
+                    self.n[i] = (i + 1) as f64;
+                }
+            }
+            return;
+        }
+
+        // Find the cell the observation falls into, extending the extreme
+        // markers when it lands outside the current range
+        let k = if x < self.q[0] {
+            self.q[0] = x;
+            0
+        } else if x >= self.q[MARKERS - 1] {
+            self.q[MARKERS - 1] = x;
+            MARKERS - 2
+        } else {
+            (1..MARKERS).find(|&i| x < self.q[i]).unwrap_or(MARKERS - 1) - 1
+        };
+
+        // Shift the positions of all markers above the cell, then advance
+        // every desired position
+        for i in (k + 1)..MARKERS {
+            self.n[i] += 1.0;
+        }
+        for i in 0..MARKERS {
+            self.np[i] += self.dnp[i];
+        }
+
+        // Adjust the interior markers towards their desired positions
+        for i in 1..(MARKERS - 1) {
+            let d = self.np[i] - self.n[i];
+            if (d >= 1.0 && self.n[i + 1] - self.n[i] > 1.0)
+                || (d <= -1.0 && self.n[i - 1] - self.n[i] < -1.0)
+            {
+                let d = d.signum();
+                let candidate = self.parabolic(i, d);
+                // The parabolic fit can overshoot a neighbour; fall back to
+                // linear interpolation when it would break monotonicity
+                if self.q[i - 1] < candidate && candidate < self.q[i + 1] {
+                    self.q[i] = candidate;
+                } else {
+                    self.q[i] = self.linear(i, d);
+                }
+                self.n[i] += d;
+            }
+        }
+    }
+
+    /// Current estimate of the tracked quantile
+    pub fn estimate(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        // Initial phase: compute exactly from the buffered observations
+        if self.count < MARKERS as u64 {
+            let mut sorted = self.initial.clone();
+            sorted.sort_by(f64::total_cmp);
+            let rank = self.p * (sorted.len() - 1) as f64;
+            let lower = rank.floor() as usize;
+            let upper = (lower + 1).min(sorted.len() - 1);
+            let frac = rank - lower as f64;
+            return sorted[lower] + frac * (sorted[upper] - sorted[lower]);
+        }
+        self.q[2]
+    }
+
+    /// Halve the marker positions so older observations decay away
+    ///
+    /// Scaling actual and desired positions around rank 1 preserves their
+    /// ordering and the relative spacing of the markers while making the
+    /// next horizon's worth of observations count as much as everything
+    /// seen so far.
+    pub(super) fn decay(&mut self) {
+        if self.count < MARKERS as u64 {
+            return;
+        }
+        for i in 0..MARKERS {
+            self.n[i] = 1.0 + (self.n[i] - 1.0) / 2.0;
+            self.np[i] = 1.0 + (self.np[i] - 1.0) / 2.0;
+        }
+    }
+
+    /// Piecewise-parabolic (P²) marker height update
+    fn parabolic(&self, i: usize, d: f64) -> f64 {
+        self.q[i]
+            + d / (self.n[i + 1] - self.n[i - 1])
+                * ((self.n[i] - self.n[i - 1] + d) * (self.q[i + 1] - self.q[i])
+                    / (self.n[i + 1] - self.n[i])
+                    + (self.n[i + 1] - self.n[i] - d) * (self.q[i] - self.q[i - 1])
+                        / (self.n[i] - self.n[i - 1]))
+    }
+
+    /// Linear fallback marker height update
+    fn linear(&self, i: usize, d: f64) -> f64 {
+        let j = if d > 0.0 { i + 1 } else { i - 1 };
+        self.q[i] + d * (self.q[j] - self.q[i]) / (self.n[j] - self.n[i])
+    }
+}
+
+/// Streaming estimates of the percentile set `RollingStats` reports
+///
+/// Bundles one [`P2Quantile`] per reported percentile plus exact min, max
+/// and count, so a full [`PercentileStats`] is available in O(1). With a
+/// decay horizon the estimators forget old observations exponentially;
+/// without one they summarize the stream since startup.
+#[derive(Debug, Clone)]
+pub struct StreamingPercentiles {
+    p10: P2Quantile,
+    p25: P2Quantile,
+    median: P2Quantile,
+    p75: P2Quantile,
+    p90: P2Quantile,
+    min: u64,
+    max: u64,
+    /// Effective sample count, halved on decay along with the markers
+    count: f64,
+    /// Effective count at which the estimators decay, if any
+    horizon: Option<u64>,
+}
+
+impl StreamingPercentiles {
+    /// Create estimators that summarize the whole stream
+    pub fn new() -> Self {
+        Self::with_horizon(None)
+    }
+
+    /// Create estimators that decay once `horizon` samples are reflected
+    pub fn with_horizon(horizon: Option<u64>) -> Self {
+        Self {
+            p10: P2Quantile::new(0.10),
+            p25: P2Quantile::new(0.25),
+            median: P2Quantile::new(0.50),
+            p75: P2Quantile::new(0.75),
+            p90: P2Quantile::new(0.90),
+            min: u64::MAX,
+            max: 0,
+            count: 0.0,
+            horizon,
+        }
+    }
+
+    /// Record one observation across all tracked percentiles
+    pub fn observe(&mut self, value: u64) {
+        let x = value as f64;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.count += 1.0;
+        self.p10.observe(x);
+        self.p25.observe(x);
+        self.median.observe(x);
+        self.p75.observe(x);
+        self.p90.observe(x);
+
+        if let Some(horizon) = self.horizon {
+            if self.count >= horizon as f64 {
+                self.count /= 2.0;
+                self.p10.decay();
+                self.p25.decay();
+                self.median.decay();
+                self.p75.decay();
+                self.p90.decay();
+            }
+        }
+    }
+
+    /// Current percentile estimates in the same shape the exact path uses
+    ///
+    /// `count` is the effective number of samples the estimate reflects;
+    /// `min` and `max` are exact over the whole stream since they cannot
+    /// be decayed.
+    pub fn stats(&self) -> PercentileStats {
+        if self.count == 0.0 {
+            return PercentileStats::default();
+        }
+        let p25 = self.p25.estimate();
+        let p75 = self.p75.estimate();
+        PercentileStats {
+            p10: self.p10.estimate(),
+            p25,
+            median: self.median.estimate(),
+            p75,
+            p90: self.p90.estimate(),
+            iqr: p75 - p25,
+            min: self.min,
+            max: self.max,
+            count: self.count.round() as usize,
+        }
+    }
+}
+
+impl Default for StreamingPercentiles {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Exact interpolated percentile, mirroring the rolling-stats exact path
+    fn exact_percentile(values: &mut [f64], p: f64) -> f64 {
+        values.sort_by(f64::total_cmp);
+        let rank = p * (values.len() - 1) as f64;
+        let lower = rank.floor() as usize;
+        let upper = (lower + 1).min(values.len() - 1);
+        let frac = rank - lower as f64;
+        values[lower] + frac * (values[upper] - values[lower])
+    }
+
+    fn assert_close(estimate: f64, exact: f64, spread: f64, label: &str) {
+        assert!(
+            (estimate - exact).abs() <= spread,
+            "{label}: estimate {estimate} vs exact {exact} (tolerance {spread})"
+        );
+    }
+
+    #[test]
+    fn test_initial_phase_is_exact() {
+        let mut est = P2Quantile::new(0.5);
+        est.observe(30.0);
+        est.observe(10.0);
+        est.observe(20.0);
+        assert!((est.estimate() - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_p2_tracks_uniform_distribution() {
+        let mut rng = StdRng::seed_from_u64(0x5032);
+        let mut streaming = StreamingPercentiles::new();
+        let mut values = Vec::with_capacity(10_000);
+        for _ in 0..10_000 {
+            let v = rng.gen_range(1..=100_000u64);
+            streaming.observe(v);
+            values.push(v as f64);
+        }
+
+        // Tolerate 2% of the value range: P² converges well inside that on
+        // a uniform stream of this length
+        let tolerance = 2_000.0;
+        let stats = streaming.stats();
+        assert_close(stats.p10, exact_percentile(&mut values, 0.10), tolerance, "p10");
+        assert_close(stats.p25, exact_percentile(&mut values, 0.25), tolerance, "p25");
+        assert_close(stats.median, exact_percentile(&mut values, 0.50), tolerance, "p50");
+        assert_close(stats.p75, exact_percentile(&mut values, 0.75), tolerance, "p75");
+        assert_close(stats.p90, exact_percentile(&mut values, 0.90), tolerance, "p90");
+        assert_eq!(stats.count, 10_000);
+    }
+
+    #[test]
+    fn test_p2_tracks_skewed_distribution() {
+        // Square a uniform draw for a right-skewed stream, the shape block
+        // gas actually has: mostly small values with a heavy upper tail
+        let mut rng = StdRng::seed_from_u64(0x5032);
+        let mut streaming = StreamingPercentiles::new();
+        let mut values = Vec::with_capacity(10_000);
+        for _ in 0..10_000 {
+            let u = rng.gen_range(1..=1_000u64);
+            let v = u * u;
+            streaming.observe(v);
+            values.push(v as f64);
+        }
+
+        let stats = streaming.stats();
+        for (estimate, p, label) in [
+            (stats.p10, 0.10, "p10"),
+            (stats.median, 0.50, "p50"),
+            (stats.p90, 0.90, "p90"),
+        ] {
+            let exact = exact_percentile(&mut values, p);
+            // 5% relative tolerance: the parabolic fit is less exact on a
+            // skewed stream but should stay in the right neighbourhood
+            assert_close(estimate, exact, exact * 0.05, label);
+        }
+    }
+
+    #[test]
+    fn test_decay_follows_distribution_shift() {
+        let mut decaying = StreamingPercentiles::with_horizon(Some(1_000));
+        let mut lifetime = StreamingPercentiles::new();
+        let mut rng = StdRng::seed_from_u64(0x5032);
+
+        // Load shifts an order of magnitude halfway through the stream
+        for _ in 0..5_000 {
+            let v = rng.gen_range(900..=1_100u64);
+            decaying.observe(v);
+            lifetime.observe(v);
+        }
+        for _ in 0..5_000 {
+            let v = rng.gen_range(9_000..=11_000u64);
+            decaying.observe(v);
+            lifetime.observe(v);
+        }
+
+        let decayed = decaying.stats().median;
+        let undecayed = lifetime.stats().median;
+        assert!(
+            decayed > 8_000.0,
+            "decaying median {decayed} should track the new regime"
+        );
+        assert!(
+            undecayed < 8_000.0,
+            "lifetime median {undecayed} should still reflect the old regime"
+        );
+    }
+}
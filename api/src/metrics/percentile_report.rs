@@ -0,0 +1,131 @@
+//! Machine-readable percentile artifacts
+//!
+//! The fetch_percentiles and fetch_baseline binaries print human-formatted
+//! tables; this report is the structured counterpart they write to disk so
+//! the frontend can load baselines at build time instead of copying JS
+//! constants by hand.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// One metric's percentile values
+///
+/// Percentiles are kept as ordered (label, value) pairs so CSV columns come
+/// out in the order the producer chose (p0, p10, ... rather than lexical).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricRow {
+    pub metric: String,
+    pub percentiles: Vec<(String, f64)>,
+}
+
+/// Structured result of one percentile run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PercentileReport {
+    /// When this report was generated
+    pub generated_at: DateTime<Utc>,
+    /// How many blocks the percentiles were computed from
+    pub blocks_analyzed: u64,
+    /// One row per metric
+    pub metrics: Vec<MetricRow>,
+}
+
+impl PercentileReport {
+    pub fn new(blocks_analyzed: u64) -> Self {
+        Self {
+            generated_at: Utc::now(),
+            blocks_analyzed,
+            metrics: Vec::new(),
+        }
+    }
+
+    /// Append one metric's percentile row
+    pub fn push(&mut self, metric: &str, percentiles: &[(&str, f64)]) {
+        self.metrics.push(MetricRow {
+            metric: metric.to_string(),
+            percentiles: percentiles
+                .iter()
+                .map(|(label, value)| (label.to_string(), *value))
+                .collect(),
+        });
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize percentile report")
+    }
+
+    /// Render as CSV: one row per metric, one column per percentile
+    ///
+    /// Column order follows the first row's labels; all rows from one
+    /// producer share the same label set.
+    pub fn to_csv(&self) -> String {
+        let Some(first) = self.metrics.first() else {
+            return "metric\n".to_string();
+        };
+
+        let mut out = String::from("metric");
+        for (label, _) in &first.percentiles {
+            out.push(',');
+            out.push_str(label);
+        }
+        out.push('\n');
+
+        for row in &self.metrics {
+            out.push_str(&row.metric);
+            for (_, value) in &row.percentiles {
+                out.push_str(&format!(",{:.0}", value));
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+
+    /// Write the report to `path`: CSV when the extension is .csv, JSON
+    /// otherwise
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let content = if path.extension().and_then(|e| e.to_str()) == Some("csv") {
+            self.to_csv()
+        } else {
+            self.to_json()?
+        };
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write percentile report to {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_report() -> PercentileReport {
+        let mut report = PercentileReport::new(500);
+        report.push("gas", &[("p10", 1000.0), ("p50", 2000.0), ("p90", 9000.0)]);
+        report.push("tx_size", &[("p10", 10.0), ("p50", 20.0), ("p90", 90.0)]);
+        report
+    }
+
+    #[test]
+    fn test_csv_has_one_row_per_metric_and_producer_column_order() {
+        let csv = sample_report().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0], "metric,p10,p50,p90");
+        assert_eq!(lines[1], "gas,1000,2000,9000");
+        assert_eq!(lines[2], "tx_size,10,20,90");
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let json = sample_report().to_json().unwrap();
+        let parsed: PercentileReport = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.blocks_analyzed, 500);
+        assert_eq!(parsed.metrics.len(), 2);
+        assert_eq!(parsed.metrics[0].metric, "gas");
+        assert_eq!(parsed.metrics[0].percentiles[1], ("p50".to_string(), 2000.0));
+    }
+}
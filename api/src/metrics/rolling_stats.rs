@@ -1,3 +1,6 @@
+use super::p2::StreamingPercentiles;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
@@ -30,14 +33,17 @@ pub struct MetricSample {
 }
 
 /// Percentile statistics for a single metric
+///
+/// Percentiles are linearly interpolated between ranks, so they can fall
+/// between observed sample values.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct PercentileStats {
-    pub p10: u64,
-    pub p25: u64,
-    pub median: u64,
-    pub p75: u64,
-    pub p90: u64,
-    pub iqr: u64,  // p75 - p25
+    pub p10: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub p90: f64,
+    pub iqr: f64,  // p75 - p25
     pub min: u64,
     pub max: u64,
     pub count: usize,
@@ -78,47 +84,248 @@ pub struct NormalizedBlockMetrics {
     pub state_growth: NormalizedMetric,
 }
 
+/// Normalized score above which a block counts as anomalous
+pub const ANOMALY_SCORE_THRESHOLD: f64 = 80.0;
+/// Protocol-limit utilization above which a block counts as anomalous
+pub const ANOMALY_UTILIZATION_PCT: f64 = 90.0;
+
+impl NormalizedBlockMetrics {
+    fn metrics(&self) -> [&NormalizedMetric; 6] {
+        [
+            &self.gas,
+            &self.kv_updates,
+            &self.tx_size,
+            &self.da_size,
+            &self.data_size,
+            &self.state_growth,
+        ]
+    }
+
+    /// Largest normalized score across all metrics
+    pub fn max_score(&self) -> f64 {
+        self.metrics()
+            .iter()
+            .map(|m| m.score)
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    /// True when any metric scores far above the baseline or runs close to
+    /// its protocol limit
+    pub fn is_anomalous(&self) -> bool {
+        self.metrics().iter().any(|m| {
+            m.score > ANOMALY_SCORE_THRESHOLD || m.utilization_pct > ANOMALY_UTILIZATION_PCT
+        })
+    }
+}
+
+/// Default short window used for responsive aggregation stats
+pub const DEFAULT_STATS_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Default long window used as the normalization baseline
+///
+/// Normalization scores are computed against this longer window so a brief
+/// burst of activity doesn't immediately re-center the percentiles, while
+/// the short stats window stays responsive for aggregation.
+pub const DEFAULT_BASELINE_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Default half-life for the EWMA throughput rates
+pub const DEFAULT_EWMA_HALF_LIFE: Duration = Duration::from_secs(10);
+
 /// Rolling statistics calculator using reservoir sampling
+///
+/// Keeps two sample buffers: a short stats window for responsive aggregates
+/// and a longer baseline window that normalization percentiles are computed
+/// against. The baseline window should be at least as long as the stats
+/// window.
 pub struct RollingStats {
-    /// Window duration (default 10 minutes)
+    /// Short window duration (default 10 minutes)
     window_duration: Duration,
-    /// Maximum samples to keep (for memory efficiency)
+    /// Long baseline window duration (default 1 hour)
+    baseline_duration: Duration,
+    /// Maximum samples to keep per buffer (for memory efficiency)
     max_samples: usize,
     /// Samples stored as a deque for efficient removal of old entries
     samples: VecDeque<MetricSample>,
+    /// Baseline samples, evicted on the longer window
+    baseline_samples: VecDeque<MetricSample>,
+    /// Half-life for the EWMA throughput rates
+    ewma_half_life: Duration,
+    /// Smoothed gas throughput (gas per second)
+    ewma_gas_per_sec: f64,
+    /// Smoothed block throughput (blocks per second)
+    ewma_blocks_per_sec: f64,
+    /// Timestamp of the last sample, for elapsed-time weighting
+    last_sample_at: Option<Instant>,
+    /// Seeded RNG driving reservoir replacement
+    rng: StdRng,
+    /// Stream position for Algorithm R on the stats buffer
+    seen: u64,
+    /// Stream position for Algorithm R on the baseline buffer
+    baseline_seen: u64,
+    /// P² estimators shadowing the stats buffer
+    streaming: StreamingSet,
+    /// P² estimators shadowing the baseline buffer
+    baseline_streaming: StreamingSet,
 }
 
-impl RollingStats {
-    /// Create a new RollingStats with 10 minute window and 2000 max samples
-    pub fn new() -> Self {
+/// One streaming percentile estimator per tracked metric
+///
+/// Shadows a sample buffer: every sample fed to the buffer is also fed
+/// here, so once the buffer saturates the percentiles can be read in O(1)
+/// instead of sorting the buffer per metric.
+struct StreamingSet {
+    gas: StreamingPercentiles,
+    kv_updates: StreamingPercentiles,
+    tx_size: StreamingPercentiles,
+    da_size: StreamingPercentiles,
+    data_size: StreamingPercentiles,
+    state_growth: StreamingPercentiles,
+}
+
+impl StreamingSet {
+    fn with_horizon(horizon: u64) -> Self {
         Self {
-            window_duration: Duration::from_secs(10 * 60), // 10 minutes
-            max_samples: 2000,
-            samples: VecDeque::with_capacity(2000),
+            gas: StreamingPercentiles::with_horizon(Some(horizon)),
+            kv_updates: StreamingPercentiles::with_horizon(Some(horizon)),
+            tx_size: StreamingPercentiles::with_horizon(Some(horizon)),
+            da_size: StreamingPercentiles::with_horizon(Some(horizon)),
+            data_size: StreamingPercentiles::with_horizon(Some(horizon)),
+            state_growth: StreamingPercentiles::with_horizon(Some(horizon)),
+        }
+    }
+
+    fn observe(&mut self, sample: &MetricSample) {
+        self.gas.observe(sample.total_gas);
+        self.kv_updates.observe(sample.kv_updates);
+        self.tx_size.observe(sample.tx_size);
+        self.da_size.observe(sample.da_size);
+        self.data_size.observe(sample.data_size);
+        self.state_growth.observe(sample.state_growth);
+    }
+
+    fn compute(&self) -> AllMetricStats {
+        AllMetricStats {
+            gas: self.gas.stats(),
+            kv_updates: self.kv_updates.stats(),
+            tx_size: self.tx_size.stats(),
+            da_size: self.da_size.stats(),
+            data_size: self.data_size.stats(),
+            state_growth: self.state_growth.stats(),
         }
     }
+}
 
-    /// Create with custom parameters
+impl RollingStats {
+    /// Create a new RollingStats with default windows and 2000 max samples
+    pub fn new() -> Self {
+        Self::with_windows(DEFAULT_STATS_WINDOW, DEFAULT_BASELINE_WINDOW, 2000)
+    }
+
+    /// Create with custom parameters (baseline window = stats window)
     pub fn with_params(window_duration: Duration, max_samples: usize) -> Self {
+        Self::with_windows(window_duration, window_duration, max_samples)
+    }
+
+    /// Create with independently configurable stats and baseline windows
+    pub fn with_windows(
+        window_duration: Duration,
+        baseline_duration: Duration,
+        max_samples: usize,
+    ) -> Self {
+        let baseline_duration = baseline_duration.max(window_duration);
+        // Decay horizons approximate how many samples each window would
+        // hold if the buffer never capped, so the streaming estimates
+        // forget old data at roughly the same rate as buffer eviction
+        let window_ratio =
+            (baseline_duration.as_secs() / window_duration.as_secs().max(1)).max(1);
         Self {
             window_duration,
+            baseline_duration,
             max_samples,
+            streaming: StreamingSet::with_horizon(max_samples as u64),
+            baseline_streaming: StreamingSet::with_horizon(max_samples as u64 * window_ratio),
             samples: VecDeque::with_capacity(max_samples),
+            baseline_samples: VecDeque::with_capacity(max_samples),
+            ewma_half_life: DEFAULT_EWMA_HALF_LIFE,
+            ewma_gas_per_sec: 0.0,
+            ewma_blocks_per_sec: 0.0,
+            last_sample_at: None,
+            // Fixed seed: sampling stays uniform but runs are reproducible
+            rng: StdRng::seed_from_u64(0x4d45_4741),
+            seen: 0,
+            baseline_seen: 0,
         }
     }
 
+    /// Set the half-life for the EWMA throughput rates
+    pub fn with_half_life(mut self, half_life: Duration) -> Self {
+        self.ewma_half_life = half_life;
+        self
+    }
+
+    /// Create from STATS_WINDOW_SECS / BASELINE_WINDOW_SECS env vars
+    pub fn from_env() -> Self {
+        Self::with_windows(stats_window_from_env(), baseline_window_from_env(), 2000)
+    }
+
+    /// The short aggregation window
+    pub fn stats_window(&self) -> Duration {
+        self.window_duration
+    }
+
+    /// The long normalization baseline window
+    pub fn baseline_window(&self) -> Duration {
+        self.baseline_duration
+    }
+
+    /// Smoothed gas throughput in gas per second
+    pub fn ewma_gas_per_second(&self) -> f64 {
+        self.ewma_gas_per_sec
+    }
+
+    /// Smoothed block throughput in blocks per second
+    pub fn ewma_blocks_per_second(&self) -> f64 {
+        self.ewma_blocks_per_sec
+    }
+
     /// Add a new block sample
     pub fn add_sample(&mut self, sample: MetricSample) {
         // Remove samples older than window
         self.evict_old();
 
-        // If at capacity, use reservoir sampling
-        if self.samples.len() >= self.max_samples {
-            // Replace a random sample (simplified: replace oldest)
-            self.samples.pop_front();
+        // Update EWMA rates, weighting by elapsed time since the last block
+        // so a single large block doesn't dominate the smoothed throughput
+        if let Some(last) = self.last_sample_at {
+            let dt = sample.timestamp.saturating_duration_since(last).as_secs_f64();
+            if dt > 0.0 {
+                let alpha = 1.0 - 0.5f64.powf(dt / self.ewma_half_life.as_secs_f64());
+                let gas_rate = sample.total_gas as f64 / dt;
+                let block_rate = 1.0 / dt;
+                self.ewma_gas_per_sec += alpha * (gas_rate - self.ewma_gas_per_sec);
+                self.ewma_blocks_per_sec += alpha * (block_rate - self.ewma_blocks_per_sec);
+            }
         }
-
-        self.samples.push_back(sample);
+        self.last_sample_at = Some(sample.timestamp);
+
+        // At capacity, Algorithm R keeps each buffer a uniform sample of the
+        // stream; below capacity (window eviction keeping up) just append
+        reservoir_push(
+            &mut self.rng,
+            &mut self.samples,
+            self.max_samples,
+            &mut self.seen,
+            sample,
+        );
+        reservoir_push(
+            &mut self.rng,
+            &mut self.baseline_samples,
+            self.max_samples,
+            &mut self.baseline_seen,
+            sample,
+        );
+
+        self.streaming.observe(&sample);
+        self.baseline_streaming.observe(&sample);
     }
 
     /// Add sample from raw values
@@ -142,16 +349,16 @@ impl RollingStats {
         });
     }
 
-    /// Remove samples older than window duration
+    /// Remove samples older than their window duration
+    ///
+    /// Reservoir replacement breaks insertion-time ordering, so this scans
+    /// the whole buffer instead of popping from the front.
     fn evict_old(&mut self) {
-        let cutoff = Instant::now() - self.window_duration;
-        while let Some(front) = self.samples.front() {
-            if front.timestamp < cutoff {
-                self.samples.pop_front();
-            } else {
-                break;
-            }
-        }
+        let now = Instant::now();
+        let cutoff = now - self.window_duration;
+        self.samples.retain(|s| s.timestamp >= cutoff);
+        let baseline_cutoff = now - self.baseline_duration;
+        self.baseline_samples.retain(|s| s.timestamp >= baseline_cutoff);
     }
 
     /// Get number of samples in the window
@@ -159,28 +366,67 @@ impl RollingStats {
         self.samples.len()
     }
 
-    /// Compute percentile stats for all metrics
+    /// Compute percentile stats over the short stats window
+    ///
+    /// While the buffer is below capacity the percentiles are computed
+    /// exactly; once it saturates (and the exact path would be sorting a
+    /// reservoir approximation anyway) the O(1) P² estimates are returned
+    /// instead of re-sorting every metric per call.
     pub fn compute_stats(&self) -> AllMetricStats {
-        if self.samples.is_empty() {
+        if self.samples.len() >= self.max_samples {
+            self.streaming.compute()
+        } else {
+            Self::compute_all(&self.samples)
+        }
+    }
+
+    /// Compute percentile stats over the long baseline window
+    ///
+    /// Switches from the exact sort to P² estimates on saturation, like
+    /// [`compute_stats`](Self::compute_stats).
+    pub fn compute_baseline_stats(&self) -> AllMetricStats {
+        if self.baseline_samples.len() >= self.max_samples {
+            self.baseline_streaming.compute()
+        } else {
+            Self::compute_all(&self.baseline_samples)
+        }
+    }
+
+    /// Exact percentile stats over the stats buffer, regardless of size
+    ///
+    /// O(n log n) per metric; kept for small windows and for tests that
+    /// assert on the buffer contents rather than the stream.
+    pub fn compute_stats_exact(&self) -> AllMetricStats {
+        Self::compute_all(&self.samples)
+    }
+
+    /// Exact percentile stats over the baseline buffer, regardless of size
+    pub fn compute_baseline_stats_exact(&self) -> AllMetricStats {
+        Self::compute_all(&self.baseline_samples)
+    }
+
+    /// Compute percentile stats for all metrics over a sample buffer
+    fn compute_all(samples: &VecDeque<MetricSample>) -> AllMetricStats {
+        if samples.is_empty() {
             return AllMetricStats::default();
         }
 
         AllMetricStats {
-            gas: self.compute_percentiles(|s| s.total_gas),
-            kv_updates: self.compute_percentiles(|s| s.kv_updates),
-            tx_size: self.compute_percentiles(|s| s.tx_size),
-            da_size: self.compute_percentiles(|s| s.da_size),
-            data_size: self.compute_percentiles(|s| s.data_size),
-            state_growth: self.compute_percentiles(|s| s.state_growth),
+            gas: Self::compute_percentiles(samples, |s| s.total_gas),
+            kv_updates: Self::compute_percentiles(samples, |s| s.kv_updates),
+            tx_size: Self::compute_percentiles(samples, |s| s.tx_size),
+            da_size: Self::compute_percentiles(samples, |s| s.da_size),
+            data_size: Self::compute_percentiles(samples, |s| s.data_size),
+            state_growth: Self::compute_percentiles(samples, |s| s.state_growth),
         }
     }
 
     /// Compute percentiles for a single metric
-    fn compute_percentiles<F>(&self, extractor: F) -> PercentileStats
+    fn compute_percentiles<F>(samples: &VecDeque<MetricSample>, extractor: F) -> PercentileStats
     where
         F: Fn(&MetricSample) -> u64,
     {
-        let mut values: Vec<u64> = self.samples.iter().map(&extractor).collect();
+        let mut values: Vec<u64> = samples.iter().map(&extractor).collect();
 
         if values.is_empty() {
             return PercentileStats::default();
@@ -189,11 +435,11 @@ impl RollingStats {
         values.sort_unstable();
         let n = values.len();
 
-        let p10 = values[n * 10 / 100];
-        let p25 = values[n * 25 / 100];
-        let median = values[n * 50 / 100];
-        let p75 = values[n * 75 / 100];
-        let p90 = values[n * 90 / 100];
+        let p10 = interpolated_percentile(&values, 10.0);
+        let p25 = interpolated_percentile(&values, 25.0);
+        let median = interpolated_percentile(&values, 50.0);
+        let p75 = interpolated_percentile(&values, 75.0);
+        let p90 = interpolated_percentile(&values, 90.0);
 
         PercentileStats {
             p10,
@@ -201,7 +447,7 @@ impl RollingStats {
             median,
             p75,
             p90,
-            iqr: p75.saturating_sub(p25),
+            iqr: p75 - p25,
             min: values[0],
             max: values[n - 1],
             count: n,
@@ -209,6 +455,9 @@ impl RollingStats {
     }
 
     /// Normalize a block's metrics to -100 to +100 scores
+    ///
+    /// Scores are computed against the long baseline window so a short spike
+    /// doesn't immediately re-center the percentiles.
     pub fn normalize_block(
         &self,
         total_gas: u64,
@@ -218,7 +467,7 @@ impl RollingStats {
         data_size: u64,
         state_growth: u64,
     ) -> NormalizedBlockMetrics {
-        let stats = self.compute_stats();
+        let stats = self.compute_baseline_stats();
 
         NormalizedBlockMetrics {
             gas: normalize_metric(
@@ -261,6 +510,64 @@ impl Default for RollingStats {
     }
 }
 
+/// Stats window from STATS_WINDOW_SECS, defaulting to 10 minutes
+pub fn stats_window_from_env() -> Duration {
+    window_from_env("STATS_WINDOW_SECS", DEFAULT_STATS_WINDOW)
+}
+
+/// Baseline window from BASELINE_WINDOW_SECS, defaulting to 1 hour
+pub fn baseline_window_from_env() -> Duration {
+    window_from_env("BASELINE_WINDOW_SECS", DEFAULT_BASELINE_WINDOW)
+}
+
+/// Algorithm R: append while below capacity, then replace a uniformly random
+/// element with probability capacity/seen so the buffer stays an unbiased
+/// sample of the stream
+fn reservoir_push(
+    rng: &mut StdRng,
+    buf: &mut VecDeque<MetricSample>,
+    capacity: usize,
+    seen: &mut u64,
+    sample: MetricSample,
+) {
+    if buf.len() < capacity {
+        buf.push_back(sample);
+        *seen = buf.len() as u64;
+    } else {
+        *seen += 1;
+        let j = rng.gen_range(0..*seen) as usize;
+        if j < capacity {
+            buf[j] = sample;
+        }
+    }
+}
+
+fn window_from_env(var: &str, default: Duration) -> Duration {
+    std::env::var(var)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(default)
+}
+
+/// Percentile of a sorted slice, linearly interpolated between ranks
+///
+/// Uses the `rank = p * (n - 1) / 100` convention, so p50 of [1..=100] is
+/// 50.5 rather than collapsing to a single sample value.
+fn interpolated_percentile(sorted: &[u64], p: f64) -> f64 {
+    match sorted.len() {
+        0 => 0.0,
+        1 => sorted[0] as f64,
+        n => {
+            let rank = (p / 100.0).clamp(0.0, 1.0) * (n - 1) as f64;
+            let lower = (rank.floor() as usize).min(n - 1);
+            let upper = (lower + 1).min(n - 1);
+            let frac = rank - lower as f64;
+            sorted[lower] as f64 + frac * (sorted[upper] as f64 - sorted[lower] as f64)
+        }
+    }
+}
+
 /// Normalize a single metric using Hybrid Sigmoid + Capacity Warning
 ///
 /// Formula:
@@ -273,7 +580,7 @@ fn normalize_metric(value: u64, stats: &PercentileStats, protocol_limit: u64) ->
     let utilization_pct = utilization * 100.0;
 
     // Handle edge case: no data or no spread
-    if stats.count == 0 || stats.iqr == 0 {
+    if stats.count == 0 || stats.iqr == 0.0 {
         // Fallback: just use utilization
         return NormalizedMetric {
             raw: value,
@@ -284,8 +591,8 @@ fn normalize_metric(value: u64, stats: &PercentileStats, protocol_limit: u64) ->
     }
 
     // Sigmoid normalization centered on median
-    let spread = (stats.iqr as f64) * 1.5;
-    let x = (value as f64 - stats.median as f64) / spread;
+    let spread = stats.iqr * 1.5;
+    let x = (value as f64 - stats.median) / spread;
     let mut score = x.tanh() * 100.0;
 
     // Capacity warning: if approaching protocol limit, force towards +100
@@ -310,12 +617,12 @@ mod tests {
     #[test]
     fn test_normalize_metric_at_median() {
         let stats = PercentileStats {
-            p10: 100,
-            p25: 200,
-            median: 500,
-            p75: 800,
-            p90: 1000,
-            iqr: 600,
+            p10: 100.0,
+            p25: 200.0,
+            median: 500.0,
+            p75: 800.0,
+            p90: 1000.0,
+            iqr: 600.0,
             min: 50,
             max: 1200,
             count: 100,
@@ -328,12 +635,12 @@ mod tests {
     #[test]
     fn test_normalize_metric_above_median() {
         let stats = PercentileStats {
-            p10: 100,
-            p25: 200,
-            median: 500,
-            p75: 800,
-            p90: 1000,
-            iqr: 600,
+            p10: 100.0,
+            p25: 200.0,
+            median: 500.0,
+            p75: 800.0,
+            p90: 1000.0,
+            iqr: 600.0,
             min: 50,
             max: 1200,
             count: 100,
@@ -346,12 +653,12 @@ mod tests {
     #[test]
     fn test_normalize_metric_below_median() {
         let stats = PercentileStats {
-            p10: 100,
-            p25: 200,
-            median: 500,
-            p75: 800,
-            p90: 1000,
-            iqr: 600,
+            p10: 100.0,
+            p25: 200.0,
+            median: 500.0,
+            p75: 800.0,
+            p90: 1000.0,
+            iqr: 600.0,
             min: 50,
             max: 1200,
             count: 100,
@@ -364,12 +671,12 @@ mod tests {
     #[test]
     fn test_capacity_warning_override() {
         let stats = PercentileStats {
-            p10: 100,
-            p25: 200,
-            median: 500,
-            p75: 800,
-            p90: 1000,
-            iqr: 600,
+            p10: 100.0,
+            p25: 200.0,
+            median: 500.0,
+            p75: 800.0,
+            p90: 1000.0,
+            iqr: 600.0,
             min: 50,
             max: 1200,
             count: 100,
@@ -379,4 +686,145 @@ mod tests {
         let result = normalize_metric(700_000, &stats, 1_000_000);
         assert!(result.score >= 70.0, "High utilization should force high score");
     }
+
+    #[test]
+    fn test_reservoir_preserves_input_distribution() {
+        // Small reservoir, long stream: FIFO would keep only the tail of the
+        // stream, a real reservoir approximates the whole input distribution
+        let mut stats = RollingStats::with_params(Duration::from_secs(3600), 200);
+        for gas in 1..=10_000u64 {
+            stats.add_block(gas, 1, 1, 1, 1, 1);
+        }
+
+        // The exact accessor reads the buffer itself; compute_stats would
+        // return the streaming estimate once the buffer saturates
+        let gas = stats.compute_stats_exact().gas;
+        assert_eq!(gas.count, 200);
+        // Uniform input over 1..=10_000: the sampled median should sit near
+        // 5_000, not near the 9_900 a recency-biased buffer would report
+        assert!(
+            (3_500.0..=6_500.0).contains(&gas.median),
+            "sampled median {} is biased",
+            gas.median
+        );
+        assert!(gas.p90 > 7_500.0, "sampled p90 {} is biased low", gas.p90);
+        assert!(gas.p10 < 2_500.0, "sampled p10 {} is biased high", gas.p10);
+    }
+
+    #[test]
+    fn test_percentiles_single_sample() {
+        let mut stats = RollingStats::new();
+        stats.add_block(1000, 10, 100, 100, 200, 5);
+
+        let gas = stats.compute_stats().gas;
+        assert_eq!(gas.count, 1);
+        assert_eq!(gas.p10, 1000.0);
+        assert_eq!(gas.median, 1000.0);
+        assert_eq!(gas.p90, 1000.0);
+    }
+
+    #[test]
+    fn test_percentiles_two_samples_interpolate() {
+        let mut stats = RollingStats::new();
+        stats.add_block(10, 1, 1, 1, 1, 1);
+        stats.add_block(20, 1, 1, 1, 1, 1);
+
+        let gas = stats.compute_stats().gas;
+        assert_eq!(gas.median, 15.0);
+        assert_eq!(gas.p10, 11.0);
+        assert_eq!(gas.p90, 19.0);
+        assert_eq!(gas.min, 10);
+        assert_eq!(gas.max, 20);
+    }
+
+    #[test]
+    fn test_percentiles_known_distribution() {
+        let mut stats = RollingStats::new();
+        for gas in 1..=100u64 {
+            stats.add_block(gas, 1, 1, 1, 1, 1);
+        }
+
+        let gas = stats.compute_stats().gas;
+        assert!((gas.median - 50.5).abs() < 1e-9, "p50 was {}", gas.median);
+        assert!((gas.p90 - 90.1).abs() < 1e-9, "p90 was {}", gas.p90);
+        assert!((gas.p10 - 10.9).abs() < 1e-9, "p10 was {}", gas.p10);
+    }
+
+    #[test]
+    fn test_ewma_rates_converge_on_steady_throughput() {
+        let mut stats = RollingStats::new().with_half_life(Duration::from_secs(10));
+
+        // One 1M-gas block per second for 50 seconds
+        let base = Instant::now();
+        for i in 0..50u64 {
+            stats.add_sample(MetricSample {
+                timestamp: base + Duration::from_secs(i),
+                total_gas: 1_000_000,
+                kv_updates: 100,
+                tx_size: 500,
+                da_size: 500,
+                data_size: 1000,
+                state_growth: 10,
+            });
+        }
+
+        let gas_rate = stats.ewma_gas_per_second();
+        let block_rate = stats.ewma_blocks_per_second();
+        assert!((900_000.0..=1_000_000.0).contains(&gas_rate), "gas rate {gas_rate}");
+        assert!((0.9..=1.0).contains(&block_rate), "block rate {block_rate}");
+    }
+
+    #[test]
+    fn test_saturated_stats_match_exact_within_tolerance() {
+        // Long window so nothing evicts: the buffer saturates at 500 and
+        // compute_stats switches to the P² estimates
+        let mut stats = RollingStats::with_params(Duration::from_secs(3600), 500);
+        let mut rng = StdRng::seed_from_u64(0x5032);
+        let mut values: Vec<u64> = Vec::with_capacity(500);
+        for _ in 0..500 {
+            let gas = rng.gen_range(1..=1_000_000u64);
+            stats.add_block(gas, 1, 1, 1, 1, 1);
+            values.push(gas);
+        }
+
+        values.sort_unstable();
+        let streaming = stats.compute_stats().gas;
+        // 3% of the value range: both paths have seen exactly the same
+        // 500 samples, so only P² approximation error separates them
+        let tolerance = 30_000.0;
+        for (estimate, p, label) in [
+            (streaming.p10, 10.0, "p10"),
+            (streaming.p25, 25.0, "p25"),
+            (streaming.median, 50.0, "p50"),
+            (streaming.p75, 75.0, "p75"),
+            (streaming.p90, 90.0, "p90"),
+        ] {
+            let exact = interpolated_percentile(&values, p);
+            assert!(
+                (estimate - exact).abs() <= tolerance,
+                "{label}: streaming {estimate} vs exact {exact}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_spike_does_not_shift_baseline_percentiles() {
+        let mut stats = RollingStats::new();
+
+        // Establish a steady baseline
+        for _ in 0..200 {
+            stats.add_block(1_000_000, 100, 500, 500, 1000, 10);
+        }
+        let before = stats.compute_baseline_stats();
+
+        // A short gas spike
+        for _ in 0..5 {
+            stats.add_block(25_000_000, 100, 500, 500, 1000, 10);
+        }
+        let after = stats.compute_baseline_stats();
+
+        assert_eq!(before.gas.median, after.gas.median, "Median should hold through a short spike");
+        assert_eq!(before.gas.p90, after.gas.p90, "p90 should hold through a short spike");
+        assert_eq!(after.gas.max, 25_000_000, "Spike should still be visible in max");
+    }
 }
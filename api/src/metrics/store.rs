@@ -1,12 +1,29 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 
-use super::types::{BlockMetrics, TransactionMetrics, WindowStats};
+use super::rolling_stats::{MetricSample, NormalizedBlockMetrics, RollingStats};
+use super::types::{
+    BlockMetrics, DeploymentEvent, DeploymentStats, HistogramBucket, MetricHistogram,
+    MiniBlockGasStats, Sparkline, SystemActivityStats, SystemContractActivity, TopAddressesStats,
+    TransactionMetrics, WindowReference, WindowStats,
+};
+use super::types::AddressActivity;
 
-/// Maximum number of blocks to keep in memory (about 10 minutes at 10ms blocks)
-const MAX_BLOCKS: usize = 60_000;
+/// Default maximum number of blocks to keep in memory (about 10 minutes at
+/// 10ms blocks)
+///
+/// Memory cost is roughly 1 KB per block plus ~250 bytes per transaction,
+/// so 60K blocks with modest traffic lands around 100-200 MB.
+const DEFAULT_MAX_BLOCKS: usize = 60_000;
+
+/// Bounds MAX_BLOCKS is clamped to when read from the environment
+const MIN_MAX_BLOCKS: usize = 1_000;
+const MAX_MAX_BLOCKS: usize = 1_000_000;
+
+/// Maximum number of deployment events to keep in memory
+const MAX_DEPLOYMENTS: usize = 10_000;
 
 /// In-memory metrics store with rolling window support
 pub struct MetricsStore {
@@ -14,41 +31,227 @@ pub struct MetricsStore {
     blocks: RwLock<VecDeque<BlockMetrics>>,
     /// Transaction metrics ordered by block number
     transactions: RwLock<VecDeque<TransactionMetrics>>,
+    /// Hash lookup into the transaction window, trimmed with the deque
+    tx_index: RwLock<HashMap<alloy_primitives::B256, TransactionMetrics>>,
     /// Last processed block number
     last_block: RwLock<u64>,
+    /// Rolling stats for normalization baselines and smoothed rates
+    rolling: RwLock<RollingStats>,
+    /// Contract deployments ordered by block number
+    deployments: RwLock<VecDeque<DeploymentEvent>>,
+    /// When the last block was successfully added, for liveness checks
+    last_added_at: RwLock<Option<std::time::Instant>>,
+    /// Chain head as last reported by the poller, for lag computation
+    latest_head: RwLock<u64>,
+    /// Retention cap, in blocks
+    max_blocks: usize,
 }
 
 impl MetricsStore {
     pub fn new() -> Arc<Self> {
+        Self::with_config(DEFAULT_MAX_BLOCKS, RollingStats::from_env())
+    }
+
+    /// Build from the environment
+    ///
+    /// `MAX_BLOCKS` overrides the retention cap (clamped to a sane range);
+    /// `WINDOW_SECONDS` overrides the rolling-stats aggregation window while
+    /// keeping the baseline window from its own env var.
+    pub fn from_env() -> Arc<Self> {
+        let max_blocks = std::env::var("MAX_BLOCKS")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .map(|v| {
+                let clamped = v.clamp(MIN_MAX_BLOCKS, MAX_MAX_BLOCKS);
+                if clamped != v {
+                    tracing::warn!(
+                        "MAX_BLOCKS={} out of range, clamped to {}",
+                        v,
+                        clamped
+                    );
+                }
+                clamped
+            })
+            .unwrap_or(DEFAULT_MAX_BLOCKS);
+
+        let rolling = match std::env::var("WINDOW_SECONDS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+        {
+            Some(secs) => RollingStats::with_windows(
+                std::time::Duration::from_secs(secs),
+                super::rolling_stats::baseline_window_from_env(),
+                2000,
+            ),
+            None => RollingStats::from_env(),
+        };
+
+        Self::with_config(max_blocks, rolling)
+    }
+
+    fn with_config(max_blocks: usize, rolling: RollingStats) -> Arc<Self> {
         Arc::new(Self {
-            blocks: RwLock::new(VecDeque::with_capacity(MAX_BLOCKS)),
-            transactions: RwLock::new(VecDeque::with_capacity(MAX_BLOCKS * 100)),
+            blocks: RwLock::new(VecDeque::with_capacity(max_blocks)),
+            transactions: RwLock::new(VecDeque::with_capacity(max_blocks * 100)),
+            tx_index: RwLock::new(HashMap::new()),
             last_block: RwLock::new(0),
+            rolling: RwLock::new(rolling),
+            deployments: RwLock::new(VecDeque::new()),
+            last_added_at: RwLock::new(None),
+            latest_head: RwLock::new(0),
+            max_blocks,
         })
     }
 
+    /// The retention cap, in blocks
+    pub fn max_blocks(&self) -> usize {
+        self.max_blocks
+    }
+
+    /// Smoothed throughput rates as (gas per second, blocks per second)
+    pub async fn ewma_rates(&self) -> (f64, f64) {
+        let rolling = self.rolling.read().await;
+        (
+            rolling.ewma_gas_per_second(),
+            rolling.ewma_blocks_per_second(),
+        )
+    }
+
     /// Add a new block's metrics
-    pub async fn add_block(&self, block: BlockMetrics, txs: Vec<TransactionMetrics>) {
+    ///
+    /// Returns true when a block with the same number was already stored and
+    /// has been replaced (reorg or poller retry)
+    pub async fn add_block(&self, block: BlockMetrics, txs: Vec<TransactionMetrics>) -> bool {
         let mut blocks = self.blocks.write().await;
         let mut transactions = self.transactions.write().await;
+        let mut tx_index = self.tx_index.write().await;
         let mut last_block = self.last_block.write().await;
 
-        // Add new data
-        blocks.push_back(block.clone());
-        for tx in txs {
-            transactions.push_back(tx);
+        // Replace in place if this block number was already processed
+        let idx = blocks.partition_point(|b| b.block_number < block.block_number);
+        let replaced = idx < blocks.len() && blocks[idx].block_number == block.block_number;
+
+        if replaced {
+            for tx in transactions.iter().filter(|t| t.block_number == block.block_number) {
+                tx_index.remove(&tx.tx_hash);
+            }
+            transactions.retain(|t| t.block_number != block.block_number);
+            blocks[idx] = block.clone();
+
+            // Insert the replacements at the block's position, not the
+            // back: the eviction loop below pops transactions from the
+            // front in block order and would otherwise stall forever on a
+            // relocated mid-deque block
+            let at = transactions.partition_point(|t| t.block_number < block.block_number);
+            for (offset, tx) in txs.into_iter().enumerate() {
+                tx_index.insert(tx.tx_hash, tx.clone());
+                transactions.insert(at + offset, tx);
+            }
+        } else {
+            blocks.push_back(block.clone());
+            for tx in txs {
+                tx_index.insert(tx.tx_hash, tx.clone());
+                transactions.push_back(tx);
+            }
+        }
+        *last_block = (*last_block).max(block.block_number);
+        *self.last_added_at.write().await = Some(std::time::Instant::now());
+
+        // Feed the rolling stats (skip replacements so reorgs don't double-count)
+        if !replaced {
+            self.rolling.write().await.add_sample(MetricSample {
+                timestamp: std::time::Instant::now(),
+                total_gas: block.total_gas,
+                kv_updates: block.kv_updates,
+                tx_size: block.tx_size,
+                da_size: block.da_size,
+                data_size: block.data_size,
+                state_growth: block.state_growth,
+            });
         }
-        *last_block = block.block_number;
 
         // Trim old data if needed
-        while blocks.len() > MAX_BLOCKS {
+        while blocks.len() > self.max_blocks {
             if let Some(old_block) = blocks.pop_front() {
                 // Remove transactions for this block
                 while transactions.front().map(|t| t.block_number) == Some(old_block.block_number) {
-                    transactions.pop_front();
+                    if let Some(old_tx) = transactions.pop_front() {
+                        tx_index.remove(&old_tx.tx_hash);
+                    }
                 }
             }
         }
+
+        replaced
+    }
+
+    /// Record contract deployments observed in a block
+    pub async fn add_deployments(&self, events: Vec<DeploymentEvent>) {
+        if events.is_empty() {
+            return;
+        }
+        let mut deployments = self.deployments.write().await;
+
+        // Re-processed blocks replace their old deployment rows
+        if let Some(block_number) = events.first().map(|e| e.block_number) {
+            deployments.retain(|d| d.block_number != block_number);
+        }
+        deployments.extend(events);
+
+        while deployments.len() > MAX_DEPLOYMENTS {
+            deployments.pop_front();
+        }
+    }
+
+    /// Get deployment activity for the last N seconds
+    pub async fn get_deployment_stats(&self, seconds: u64) -> DeploymentStats {
+        let deployments = self.deployments.read().await;
+
+        let now = Utc::now();
+        let window_start = now - Duration::seconds(seconds as i64);
+
+        let window: Vec<DeploymentEvent> = deployments
+            .iter()
+            .filter(|d| d.timestamp >= window_start)
+            .cloned()
+            .collect();
+
+        let unique_deployers = window
+            .iter()
+            .map(|d| d.deployer)
+            .collect::<std::collections::HashSet<_>>()
+            .len() as u64;
+
+        DeploymentStats {
+            window_start,
+            window_end: now,
+            deploy_count: window.len() as u64,
+            unique_deployers,
+            total_deploy_gas: window.iter().map(|d| d.gas_used).sum(),
+            total_code_size: window.iter().map(|d| d.code_size).sum(),
+            deployments: window,
+        }
+    }
+
+    /// Remove all blocks with numbers >= `number` (reorg rollback)
+    pub async fn remove_blocks_from(&self, number: u64) {
+        let mut blocks = self.blocks.write().await;
+        let mut transactions = self.transactions.write().await;
+        let mut tx_index = self.tx_index.write().await;
+        let mut last_block = self.last_block.write().await;
+
+        let idx = blocks.partition_point(|b| b.block_number < number);
+        blocks.truncate(idx);
+        for tx in transactions.iter().filter(|t| t.block_number >= number) {
+            tx_index.remove(&tx.tx_hash);
+        }
+        transactions.retain(|t| t.block_number < number);
+        *last_block = blocks.back().map(|b| b.block_number).unwrap_or(0);
+
+        self.deployments
+            .write()
+            .await
+            .retain(|d| d.block_number < number);
     }
 
     /// Get the last processed block number
@@ -56,36 +259,111 @@ impl MetricsStore {
         *self.last_block.read().await
     }
 
+    /// Look up a single transaction's metrics by hash
+    ///
+    /// Served from a side index over the transaction window, so this is
+    /// O(1) and only covers transactions still inside block retention.
+    pub async fn get_transaction(
+        &self,
+        hash: alloy_primitives::B256,
+    ) -> Option<TransactionMetrics> {
+        self.tx_index.read().await.get(&hash).cloned()
+    }
+
+    /// How long ago the last block was added, if any has been
+    pub async fn last_block_age(&self) -> Option<std::time::Duration> {
+        self.last_added_at.read().await.map(|at| at.elapsed())
+    }
+
+    /// Record the chain head the poller last observed
+    pub async fn set_latest_head(&self, number: u64) {
+        *self.latest_head.write().await = number;
+    }
+
+    /// Chain head as last reported by the poller (0 before the first poll)
+    pub async fn latest_head(&self) -> u64 {
+        *self.latest_head.read().await
+    }
+
+    /// Score a block's aggregates against the rolling baseline
+    ///
+    /// Used by the poller to flag anomalous blocks (near a protocol limit or
+    /// far above baseline) as they are broadcast.
+    pub async fn normalize_block(&self, block: &BlockMetrics) -> NormalizedBlockMetrics {
+        self.rolling.read().await.normalize_block(
+            block.total_gas,
+            block.kv_updates,
+            block.tx_size,
+            block.da_size,
+            block.data_size,
+            block.state_growth,
+        )
+    }
+
     /// Get block metrics for a specific block
     pub async fn get_block(&self, block_number: u64) -> Option<BlockMetrics> {
         let blocks = self.blocks.read().await;
-        blocks.iter().find(|b| b.block_number == block_number).cloned()
+        block_index(&blocks, block_number).map(|idx| blocks[idx].clone())
+    }
+
+    /// The retained (oldest, newest) block numbers, or None when empty
+    ///
+    /// Lets callers distinguish a block evicted from retention from one
+    /// that was never stored.
+    pub async fn retained_range(&self) -> Option<(u64, u64)> {
+        let blocks = self.blocks.read().await;
+        Some((blocks.front()?.block_number, blocks.back()?.block_number))
     }
 
     /// Get window statistics for the last N seconds
-    pub async fn get_window_stats(&self, seconds: u64) -> WindowStats {
+    ///
+    /// `reference` selects what the window is measured back from: the
+    /// latest block's timestamp (tolerates chain/server clock skew) or the
+    /// wall clock.
+    pub async fn get_window_stats(&self, seconds: u64, reference: WindowReference) -> WindowStats {
+        let now = match reference {
+            WindowReference::Head => self
+                .blocks
+                .read()
+                .await
+                .back()
+                .map(|b| b.timestamp)
+                .unwrap_or_else(Utc::now),
+            WindowReference::Wall => Utc::now(),
+        };
+
+        self.get_window_stats_between(now - Duration::seconds(seconds as i64), now)
+            .await
+    }
+
+    /// Window statistics over an explicit time span
+    ///
+    /// Used by `/stats/compare` to aggregate offset baseline windows that
+    /// are still inside the in-memory retention.
+    pub async fn get_window_stats_between(
+        &self,
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+    ) -> WindowStats {
         let blocks = self.blocks.read().await;
         let transactions = self.transactions.read().await;
 
-        let now = Utc::now();
-        let window_start = now - Duration::seconds(seconds as i64);
-
         // Filter blocks within window
         let window_blocks: Vec<_> = blocks
             .iter()
-            .filter(|b| b.timestamp >= window_start)
+            .filter(|b| b.timestamp >= window_start && b.timestamp <= window_end)
             .collect();
 
         // Filter transactions within window
         let window_txs: Vec<_> = transactions
             .iter()
-            .filter(|t| t.timestamp >= window_start)
+            .filter(|t| t.timestamp >= window_start && t.timestamp <= window_end)
             .collect();
 
         if window_blocks.is_empty() {
             return WindowStats {
                 window_start,
-                window_end: now,
+                window_end,
                 ..Default::default()
             };
         }
@@ -100,9 +378,31 @@ impl MetricsStore {
         let sum_storage_gas: u64 = window_blocks.iter().map(|b| b.storage_gas).sum();
         let sum_tx_size: u64 = window_blocks.iter().map(|b| b.tx_size).sum();
         let sum_da_size: u64 = window_blocks.iter().map(|b| b.da_size).sum();
+        let sum_da_gas: u64 = window_blocks.iter().map(|b| b.da_gas).sum();
         let sum_data_size: u64 = window_blocks.iter().map(|b| b.data_size).sum();
         let sum_kv_updates: u64 = window_blocks.iter().map(|b| b.kv_updates).sum();
         let sum_state_growth: u64 = window_blocks.iter().map(|b| b.state_growth).sum();
+        let sum_burned_fees: u128 = window_blocks.iter().map(|b| b.burned_fees).sum();
+        let sum_value_transferred = window_blocks
+            .iter()
+            .fold(alloy_primitives::U256::ZERO, |acc, b| {
+                acc.saturating_add(b.value_transferred)
+            });
+        let sum_mini_blocks: u64 = window_blocks.iter().map(|b| b.mini_block_count).sum();
+        let mut tx_type_counts = std::collections::BTreeMap::new();
+        for block in &window_blocks {
+            for (tx_type, count) in &block.tx_type_counts {
+                *tx_type_counts.entry(*tx_type).or_insert(0) += count;
+            }
+        }
+        let sampled = window_blocks.iter().any(|b| b.sampled);
+        let sum_failed_txs: u64 = window_blocks.iter().map(|b| b.failed_tx_count).sum();
+        let sum_failed_gas: u64 = window_blocks.iter().map(|b| b.failed_gas).sum();
+        let fail_rate = if tx_count > 0 {
+            sum_failed_txs as f64 / tx_count as f64
+        } else {
+            0.0
+        };
 
         // Calculate means (per block)
         let mean_total_gas = sum_total_gas as f64 / block_count as f64;
@@ -110,9 +410,18 @@ impl MetricsStore {
         let mean_storage_gas = sum_storage_gas as f64 / block_count as f64;
         let mean_tx_size = sum_tx_size as f64 / block_count as f64;
         let mean_da_size = sum_da_size as f64 / block_count as f64;
+        let mean_da_gas = sum_da_gas as f64 / block_count as f64;
         let mean_data_size = sum_data_size as f64 / block_count as f64;
         let mean_kv_updates = sum_kv_updates as f64 / block_count as f64;
         let mean_state_growth = sum_state_growth as f64 / block_count as f64;
+        let mean_burned_fees = sum_burned_fees as f64 / block_count as f64;
+        let mean_mini_blocks = sum_mini_blocks as f64 / block_count as f64;
+        // Ratio of sums, so huge blocks aren't diluted by tiny ones
+        let mean_compression_ratio = if sum_tx_size > 0 {
+            sum_da_size as f64 / sum_tx_size as f64
+        } else {
+            0.0
+        };
 
         // Calculate P95 (per transaction)
         let p95_total_gas = percentile(&window_txs, |t| t.total_gas, 95);
@@ -136,17 +445,27 @@ impl MetricsStore {
 
         WindowStats {
             window_start,
-            window_end: now,
+            window_end,
             block_count,
             tx_count,
+            sum_mini_blocks,
+            mean_mini_blocks,
+            mean_compression_ratio,
             mean_total_gas,
             mean_compute_gas,
             mean_storage_gas,
             mean_tx_size,
             mean_da_size,
+            mean_da_gas,
             mean_data_size,
             mean_kv_updates,
             mean_state_growth,
+            mean_burned_fees,
+            tx_type_counts,
+            sum_failed_txs,
+            sum_failed_gas,
+            fail_rate,
+            sampled,
             p95_total_gas,
             p95_compute_gas,
             p95_storage_gas,
@@ -168,12 +487,336 @@ impl MetricsStore {
             sum_storage_gas,
             sum_tx_size,
             sum_da_size,
+            sum_da_gas,
             sum_data_size,
             sum_kv_updates,
             sum_state_growth,
+            sum_burned_fees,
+            sum_value_transferred: sum_value_transferred.to_string(),
         }
     }
 
+    /// Timestamp of the oldest retained block
+    pub async fn earliest_timestamp(&self) -> Option<DateTime<Utc>> {
+        self.blocks.read().await.front().map(|b| b.timestamp)
+    }
+
+    /// Timestamp of the newest retained block
+    pub async fn latest_timestamp(&self) -> Option<DateTime<Utc>> {
+        self.blocks.read().await.back().map(|b| b.timestamp)
+    }
+
+    /// Get the gas-per-mini-block distribution for the last N seconds
+    /// Exact percentile of one per-transaction metric between two timestamps
+    ///
+    /// Returns the value and the number of samples it was computed over;
+    /// backs the `/stats/percentile-audit` diagnostics route.
+    pub async fn get_tx_percentile_between<F>(
+        &self,
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+        p: usize,
+        extract: F,
+    ) -> (u64, usize)
+    where
+        F: Fn(&TransactionMetrics) -> u64,
+    {
+        let transactions = self.transactions.read().await;
+        let window_txs: Vec<_> = transactions
+            .iter()
+            .filter(|t| t.timestamp >= window_start && t.timestamp <= window_end)
+            .collect();
+
+        (percentile(&window_txs, extract, p), window_txs.len())
+    }
+
+    pub async fn get_mini_block_gas_stats(&self, seconds: u64) -> MiniBlockGasStats {
+        let blocks = self.blocks.read().await;
+
+        let now = Utc::now();
+        let window_start = now - Duration::seconds(seconds as i64);
+
+        let gas_values: Vec<u64> = blocks
+            .iter()
+            .filter(|b| b.timestamp >= window_start)
+            .flat_map(|b| b.mini_block_gas.iter().copied())
+            .collect();
+
+        let block_count = blocks.iter().filter(|b| b.timestamp >= window_start).count() as u64;
+        let mini_block_count = gas_values.len() as u64;
+
+        if gas_values.is_empty() {
+            return MiniBlockGasStats {
+                window_start,
+                window_end: now,
+                block_count,
+                mini_block_count: 0,
+                mean_gas_per_mini_block: 0.0,
+                fill_variance: 0.0,
+                min_gas: 0,
+                max_gas: 0,
+            };
+        }
+
+        let sum: u64 = gas_values.iter().sum();
+        let mean = sum as f64 / mini_block_count as f64;
+        let fill_variance = gas_values
+            .iter()
+            .map(|&g| {
+                let d = g as f64 - mean;
+                d * d
+            })
+            .sum::<f64>()
+            / mini_block_count as f64;
+
+        MiniBlockGasStats {
+            window_start,
+            window_end: now,
+            block_count,
+            mini_block_count,
+            mean_gas_per_mini_block: mean,
+            fill_variance,
+            min_gas: gas_values.iter().copied().min().unwrap_or(0),
+            max_gas: gas_values.iter().copied().max().unwrap_or(0),
+        }
+    }
+
+    /// Get blocks within an inclusive block-number range
+    ///
+    /// The deque is ordered by block number, so we can binary-search for the start
+    /// Bin a per-transaction metric into a histogram over the last N seconds
+    ///
+    /// `metric` is one of gas, tx_size, da_size, kv_updates; returns None for
+    /// anything else so the handler can reject it. Buckets are equal-width,
+    /// or log-spaced when `log` is set (gas spans several orders of magnitude).
+    /// Rank addresses by summed per-transaction metric over a window
+    ///
+    /// Groups window transactions by `to`, keeping the top `limit` by the
+    /// chosen metric. Contract creations (no `to`) are excluded. None means
+    /// the metric name isn't recognized.
+    pub async fn get_top_addresses(
+        &self,
+        seconds: u64,
+        metric: &str,
+        limit: usize,
+    ) -> Option<TopAddressesStats> {
+        let extractor: fn(&TransactionMetrics) -> u64 = match metric {
+            "gas" => |t| t.total_gas,
+            "tx_size" => |t| t.tx_size,
+            "da_size" => |t| t.da_size,
+            "kv_updates" => |t| t.kv_updates,
+            _ => return None,
+        };
+
+        let transactions = self.transactions.read().await;
+        let now = Utc::now();
+        let window_start = now - Duration::seconds(seconds as i64);
+
+        let mut by_address: std::collections::HashMap<alloy_primitives::Address, (u64, u64)> =
+            std::collections::HashMap::new();
+        for tx in transactions.iter().filter(|t| t.timestamp >= window_start) {
+            if let Some(to) = tx.to {
+                let entry = by_address.entry(to).or_insert((0, 0));
+                entry.0 += 1;
+                entry.1 += extractor(tx);
+            }
+        }
+
+        let mut addresses: Vec<AddressActivity> = by_address
+            .into_iter()
+            .map(|(address, (tx_count, total))| AddressActivity {
+                address,
+                tx_count,
+                total,
+            })
+            .collect();
+        addresses.sort_by(|a, b| b.total.cmp(&a.total).then(b.tx_count.cmp(&a.tx_count)));
+        addresses.truncate(limit);
+
+        Some(TopAddressesStats {
+            window_start,
+            window_end: now,
+            metric: metric.to_string(),
+            addresses,
+        })
+    }
+
+    /// Bucket a block metric into equal time slices over the last N seconds
+    ///
+    /// `metric` is one of gas, tx_count, tx_size, da_size, kv_updates,
+    /// state_growth; returns None for anything else so the handler can
+    /// reject it. Each slice carries the sum across its blocks, and empty
+    /// slices are zero-filled so `values.len()` always equals `buckets`.
+    pub async fn get_sparkline(
+        &self,
+        seconds: u64,
+        metric: &str,
+        buckets: usize,
+    ) -> Option<Sparkline> {
+        let extractor: fn(&BlockMetrics) -> u64 = match metric {
+            "gas" => |b| b.total_gas,
+            "tx_count" => |b| b.tx_count,
+            "tx_size" => |b| b.tx_size,
+            "da_size" => |b| b.da_size,
+            "kv_updates" => |b| b.kv_updates,
+            "state_growth" => |b| b.state_growth,
+            _ => return None,
+        };
+
+        let blocks = self.blocks.read().await;
+        let now = Utc::now();
+        let window_start = now - Duration::seconds(seconds as i64);
+
+        let bucket_count = buckets.clamp(1, 600);
+        let bucket_seconds = seconds as f64 / bucket_count as f64;
+        let mut values = vec![0u64; bucket_count];
+
+        for block in blocks.iter().filter(|b| b.timestamp >= window_start) {
+            let offset = (block.timestamp - window_start)
+                .num_milliseconds()
+                .max(0) as f64
+                / 1000.0;
+            let idx = ((offset / bucket_seconds) as usize).min(bucket_count - 1);
+            values[idx] += extractor(block);
+        }
+
+        Some(Sparkline {
+            window_start,
+            window_end: now,
+            metric: metric.to_string(),
+            bucket_seconds,
+            values,
+        })
+    }
+
+    pub async fn get_metric_histogram(
+        &self,
+        seconds: u64,
+        metric: &str,
+        buckets: usize,
+        log: bool,
+    ) -> Option<MetricHistogram> {
+        let extractor: fn(&TransactionMetrics) -> u64 = match metric {
+            "gas" => |t| t.total_gas,
+            "tx_size" => |t| t.tx_size,
+            "da_size" => |t| t.da_size,
+            "kv_updates" => |t| t.kv_updates,
+            _ => return None,
+        };
+
+        let transactions = self.transactions.read().await;
+        let now = Utc::now();
+        let window_start = now - Duration::seconds(seconds as i64);
+
+        let values: Vec<u64> = transactions
+            .iter()
+            .filter(|t| t.timestamp >= window_start)
+            .map(extractor)
+            .collect();
+
+        let bucket_count = buckets.clamp(1, 200);
+        let mut histogram = MetricHistogram {
+            window_start,
+            window_end: now,
+            metric: metric.to_string(),
+            log,
+            tx_count: values.len() as u64,
+            buckets: Vec::with_capacity(bucket_count),
+        };
+
+        if values.is_empty() {
+            return Some(histogram);
+        }
+
+        let min = *values.iter().min().unwrap() as f64;
+        let max = *values.iter().max().unwrap() as f64;
+
+        // Bucket edges; log spacing needs a positive lower bound
+        let (lo, hi) = if log {
+            ((min.max(1.0)).log10(), (max.max(1.0)).log10())
+        } else {
+            (min, max)
+        };
+        let width = ((hi - lo) / bucket_count as f64).max(f64::EPSILON);
+
+        let edge = |i: usize| -> f64 {
+            let e = lo + width * i as f64;
+            if log { 10f64.powf(e) } else { e }
+        };
+
+        let mut counts = vec![0u64; bucket_count];
+        for &value in &values {
+            let v = if log { (value as f64).max(1.0).log10() } else { value as f64 };
+            let idx = (((v - lo) / width) as usize).min(bucket_count - 1);
+            counts[idx] += 1;
+        }
+
+        for (i, count) in counts.into_iter().enumerate() {
+            histogram.buckets.push(HistogramBucket {
+                lower: edge(i),
+                upper: edge(i + 1),
+                count,
+            });
+        }
+
+        Some(histogram)
+    }
+
+    /// Get per-system-contract activity over the last N seconds
+    ///
+    /// Returns one row per catalog entry, with zero counts for contracts
+    /// that saw no traffic in the window.
+    pub async fn get_system_activity(&self, seconds: u64) -> SystemActivityStats {
+        let transactions = self.transactions.read().await;
+
+        let now = Utc::now();
+        let window_start = now - Duration::seconds(seconds as i64);
+
+        let contracts = crate::system_contracts::SYSTEM_CONTRACTS
+            .iter()
+            .map(|contract| {
+                let mut tx_count = 0u64;
+                let mut total_gas = 0u64;
+                for tx in transactions
+                    .iter()
+                    .filter(|t| t.timestamp >= window_start && t.to == Some(contract.address))
+                {
+                    tx_count += 1;
+                    total_gas += tx.total_gas;
+                }
+
+                SystemContractActivity {
+                    address: contract.address,
+                    name: contract.name.to_string(),
+                    category: contract.category.to_string(),
+                    tx_count,
+                    total_gas,
+                }
+            })
+            .collect();
+
+        SystemActivityStats {
+            window_start,
+            window_end: now,
+            contracts,
+        }
+    }
+
+    pub async fn get_blocks_in_range(&self, start: u64, end: u64) -> Vec<BlockMetrics> {
+        let blocks = self.blocks.read().await;
+        // Jump straight to the first in-range block; block_index covers the
+        // gap-free fast path, partition_point the rest
+        let start_idx = block_index(&blocks, start)
+            .unwrap_or_else(|| blocks.partition_point(|b| b.block_number < start));
+
+        blocks
+            .iter()
+            .skip(start_idx)
+            .take_while(|b| b.block_number <= end)
+            .cloned()
+            .collect()
+    }
+
     /// Get recent blocks (last N blocks)
     pub async fn get_recent_blocks(&self, count: usize) -> Vec<BlockMetrics> {
         let blocks = self.blocks.read().await;
@@ -181,6 +824,34 @@ impl MetricsStore {
     }
 }
 
+/// Index of `block_number` in the deque, or None when absent
+///
+/// The deque is usually contiguous in block number, so the index is just the
+/// offset from the front block; a direct probe confirms it. Gaps (blocks the
+/// RPC never returned) break contiguity, so a mismatch falls back to binary
+/// search rather than reporting a miss.
+fn block_index(blocks: &VecDeque<BlockMetrics>, block_number: u64) -> Option<usize> {
+    let front = blocks.front()?.block_number;
+    if block_number < front {
+        return None;
+    }
+
+    // O(1) fast path: contiguous numbering makes the offset the index
+    let offset = (block_number - front) as usize;
+    if let Some(block) = blocks.get(offset) {
+        if block.block_number == block_number {
+            return Some(offset);
+        }
+    }
+
+    // Gapped history: fall back to binary search on the ordered deque
+    let idx = blocks.partition_point(|b| b.block_number < block_number);
+    blocks
+        .get(idx)
+        .filter(|b| b.block_number == block_number)
+        .map(|_| idx)
+}
+
 /// Calculate percentile from a slice
 fn percentile<T, F>(items: &[&T], extract: F, p: usize) -> u64
 where
@@ -196,3 +867,189 @@ where
     let idx = (values.len() * p / 100).min(values.len() - 1);
     values[idx]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::B256;
+
+    fn block_at(number: u64, timestamp: chrono::DateTime<Utc>) -> BlockMetrics {
+        BlockMetrics {
+            block_number: number,
+            block_hash: B256::with_last_byte(number as u8),
+            timestamp,
+            tx_count: 0,
+            total_gas: 1_000,
+            compute_gas: 600,
+            storage_gas: 400,
+            tx_size: 0,
+            da_size: 0,
+            da_gas: 0,
+            data_size: 0,
+            kv_updates: 0,
+            state_growth: 0,
+            compression_ratio: 0.0,
+            base_fee_per_gas: None,
+            burned_fees: 0,
+            value_transferred: alloy_primitives::U256::ZERO,
+            tx_type_counts: Default::default(),
+            failed_tx_count: 0,
+            failed_gas: 0,
+            sampled: false,
+            receipts_complete: true,
+            mini_block_count: 1,
+            mini_block_gas: vec![1_000],
+            gas_limit: 30_000_000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_block_index_front_back_and_evicted() {
+        let store = MetricsStore::with_config(5, RollingStats::new());
+        let now = Utc::now();
+        for n in 1..=8 {
+            store.add_block(block_at(n, now), vec![]).await;
+        }
+
+        // Retention is 5 blocks: 1-3 were trimmed from the front
+        assert_eq!(store.retained_range().await, Some((4, 8)));
+        assert!(store.get_block(4).await.is_some(), "front of retention");
+        assert!(store.get_block(8).await.is_some(), "back of retention");
+        assert!(store.get_block(3).await.is_none(), "just evicted");
+        assert!(store.get_block(9).await.is_none(), "beyond the tip");
+    }
+
+    fn tx_at(block_number: u64, timestamp: chrono::DateTime<Utc>, last_byte: u8) -> TransactionMetrics {
+        TransactionMetrics {
+            tx_hash: B256::with_last_byte(last_byte),
+            block_number,
+            timestamp,
+            to: None,
+            from: alloy_primitives::Address::ZERO,
+            total_gas: 21_000,
+            compute_gas: 15_000,
+            storage_gas: 6_000,
+            tx_size: 100,
+            da_size: 60,
+            da_gas: 1_600,
+            data_size: 0,
+            kv_updates: 2,
+            state_growth: 0,
+            compression_ratio: 0.6,
+            value_transferred: alloy_primitives::U256::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transaction_lookup_and_index_pruning() {
+        let store = MetricsStore::with_config(2, RollingStats::new());
+        let now = Utc::now();
+        for n in 1..=3u64 {
+            store
+                .add_block(block_at(n, now), vec![tx_at(n, now, n as u8)])
+                .await;
+        }
+
+        // Block 1 fell out of the 2-block retention window along with its tx
+        let evicted = B256::with_last_byte(1);
+        assert!(store.get_transaction(evicted).await.is_none(), "evicted tx");
+
+        let found = store.get_transaction(B256::with_last_byte(3)).await.unwrap();
+        assert_eq!(found.block_number, 3);
+        assert_eq!(found.total_gas, 21_000);
+
+        // Rollback prunes the index as well as the deque
+        store.remove_blocks_from(3).await;
+        assert!(store.get_transaction(B256::with_last_byte(3)).await.is_none());
+        assert!(store.get_transaction(B256::with_last_byte(2)).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_eviction_survives_a_mid_retention_replacement() {
+        let store = MetricsStore::with_config(3, RollingStats::new());
+        let now = Utc::now();
+        for n in 1..=3u64 {
+            store
+                .add_block(block_at(n, now), vec![tx_at(n, now, n as u8)])
+                .await;
+        }
+
+        // Re-process block 2 (reorg or poller retry) with a new transaction
+        let replaced = store
+            .add_block(block_at(2, now), vec![tx_at(2, now, 0x22)])
+            .await;
+        assert!(replaced);
+
+        // Overflow retention so blocks 1 and 2 are trimmed, including the
+        // replacement transaction
+        for n in 4..=5u64 {
+            store
+                .add_block(block_at(n, now), vec![tx_at(n, now, n as u8)])
+                .await;
+        }
+
+        assert_eq!(store.retained_range().await, Some((3, 5)));
+        assert!(store.get_transaction(B256::with_last_byte(1)).await.is_none());
+        assert!(store.get_transaction(B256::with_last_byte(0x22)).await.is_none());
+        assert!(store.get_transaction(B256::with_last_byte(3)).await.is_some());
+
+        // The deque itself only holds transactions for retained blocks
+        let transactions = store.transactions.read().await;
+        assert_eq!(transactions.len(), 3);
+        assert!(transactions.iter().all(|t| t.block_number >= 3));
+    }
+
+    #[tokio::test]
+    async fn test_block_index_tolerates_gaps() {
+        let store = MetricsStore::new();
+        let now = Utc::now();
+        // Block 3 never arrives, so offsets past it are shifted
+        for n in [1u64, 2, 4, 5] {
+            store.add_block(block_at(n, now), vec![]).await;
+        }
+
+        assert!(store.get_block(3).await.is_none());
+        assert_eq!(store.get_block(5).await.unwrap().block_number, 5);
+    }
+
+    #[tokio::test]
+    async fn test_head_window_includes_future_timestamped_blocks() {
+        let store = MetricsStore::new();
+
+        // Chain time leading the server clock by a minute: a wall-anchored
+        // 10s window ending at now() sees none of these blocks
+        let skewed = Utc::now() + Duration::seconds(60);
+        for n in 1..=5 {
+            store
+                .add_block(block_at(n, skewed + Duration::seconds(n as i64)), vec![])
+                .await;
+        }
+
+        let head = store.get_window_stats(10, WindowReference::Head).await;
+        assert_eq!(head.block_count, 5);
+
+        let wall = store.get_window_stats(10, WindowReference::Wall).await;
+        assert_eq!(wall.block_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_window_value_sum_serializes_as_decimal_string() {
+        let store = MetricsStore::new();
+        let now = Utc::now();
+
+        // 2^70 wei per block: far beyond what an f64 JSON number can hold
+        let big = alloy_primitives::U256::from(1u128 << 70);
+        for n in 1..=2 {
+            let mut block = block_at(n, now);
+            block.value_transferred = big;
+            store.add_block(block, vec![]).await;
+        }
+
+        let stats = store.get_window_stats(60, WindowReference::Head).await;
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(
+            json["sum_value_transferred"],
+            serde_json::Value::String((big + big).to_string())
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use alloy_primitives::{Address, B256};
+use alloy_primitives::{Address, B256, U256};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -28,12 +28,23 @@ pub struct TransactionMetrics {
     pub tx_size: u64,
     /// Data availability size in bytes (fjord compressed)
     pub da_size: u64,
+    /// DA cost expressed in gas, from the Fjord size estimate at the
+    /// EIP-2028 calldata rate (see `fjord_da_gas` in the processor)
+    #[serde(default)]
+    pub da_gas: u64,
     /// Data size used during execution (from mega-evm)
     pub data_size: u64,
     /// KV updates count (from mega-evm)
     pub kv_updates: u64,
     /// State growth (from mega-evm)
     pub state_growth: u64,
+    /// da_size / tx_size: how compressible this transaction's bytes are
+    /// (0.0 for DA-exempt deposits)
+    #[serde(default)]
+    pub compression_ratio: f64,
+    /// Native value moved by this transaction, in wei
+    #[serde(default)]
+    pub value_transferred: U256,
 }
 
 /// Block-level aggregated metrics
@@ -60,6 +71,10 @@ pub struct BlockMetrics {
     pub tx_size: u64,
     /// Total DA size in block
     pub da_size: u64,
+    /// Total DA cost in gas-equivalent units; comparable against the
+    /// block gas limit on one axis
+    #[serde(default)]
+    pub da_gas: u64,
     /// Total data size in block
     pub data_size: u64,
     /// Total KV updates in block
@@ -67,13 +82,128 @@ pub struct BlockMetrics {
     /// Total state growth in block
     pub state_growth: u64,
 
+    /// Aggregate da_size / tx_size: low means repetitive, compressible
+    /// calldata; near 1.0 means random or encrypted payloads
+    #[serde(default)]
+    pub compression_ratio: f64,
+
+    /// Base fee per gas for this block in wei, when the RPC reports one
+    #[serde(default)]
+    pub base_fee_per_gas: Option<u128>,
+
+    /// Fees burned by this block: base fee times gas used, in wei
+    #[serde(default)]
+    pub burned_fees: u128,
+
+    /// Native value moved by this block's transactions, in wei
+    /// (saturating sum of per-transaction values)
+    #[serde(default)]
+    pub value_transferred: U256,
+
+    /// Transactions per EIP-2718 type (0 legacy, 1 EIP-2930, 2 EIP-1559,
+    /// 3 EIP-4844, 126 deposit); types with no transactions are absent
+    #[serde(default)]
+    pub tx_type_counts: std::collections::BTreeMap<u8, u64>,
+
+    /// Transactions whose receipt reported a failed status
+    #[serde(default)]
+    pub failed_tx_count: u64,
+
+    /// Gas consumed by those failed transactions; they still pay for gas
+    /// and DA despite doing nothing
+    #[serde(default)]
+    pub failed_gas: u64,
+
+    /// True when the poller was sampling (processing every Nth block) when
+    /// this block was committed; window aggregates that include it
+    /// undercount the chain accordingly
+    #[serde(default)]
+    pub sampled: bool,
+
+    /// False when some receipts were missing and gas fell back to limits,
+    /// so consumers can flag estimated blocks
+    #[serde(default = "default_true")]
+    pub receipts_complete: bool,
+
+    /// Number of mini-blocks within this EVM block (from the MegaETH
+    /// `miniBlockCount` field; falls back to the mini_block_gas length)
+    #[serde(default)]
+    pub mini_block_count: u64,
+
+    /// Gas used by each mini-block within this EVM block
+    ///
+    /// Falls back to a single entry holding the whole block's gas when the
+    /// RPC doesn't expose per-mini-block structure
+    #[serde(default)]
+    pub mini_block_gas: Vec<u64>,
+
     // === Per-block limits (for percentage calculations) ===
 
     /// Block gas limit
     pub gas_limit: u64,
 }
 
+fn default_true() -> bool {
+    true
+}
+
+/// Serde helpers for integers that can exceed JavaScript's
+/// `Number.MAX_SAFE_INTEGER` (2^53 - 1)
+///
+/// Serializes as a decimal string so `JSON.parse` can't silently round the
+/// value; deserializes from either a string or a plain number so payloads
+/// written before this change still load.
+mod as_decimal_string {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+        S: Serializer,
+    {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Str(String),
+            // u64 covers every payload written before this change
+            Num(u64),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Str(s) => s.parse().map_err(de::Error::custom),
+            Raw::Num(n) => n.to_string().parse().map_err(de::Error::custom),
+        }
+    }
+}
+
 /// Windowed statistics over a time period
+/// What instant a stats window is measured back from
+///
+/// MegaETH block timestamps can lag or lead the server clock, so windows
+/// anchored to wall time can miss freshly ingested blocks. Anchoring to the
+/// latest block's own timestamp keeps the window populated regardless of
+/// skew, and is the default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowReference {
+    /// Window ends at the latest stored block's timestamp
+    #[default]
+    Head,
+    /// Window ends at the server's wall clock
+    Wall,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WindowStats {
     /// Start of the window
@@ -84,6 +214,13 @@ pub struct WindowStats {
     pub block_count: u64,
     /// Number of transactions in window
     pub tx_count: u64,
+    /// Number of mini-blocks in window
+    pub sum_mini_blocks: u64,
+    /// Mean mini-blocks per EVM block
+    pub mean_mini_blocks: f64,
+    /// Window-wide da_size / tx_size (ratio of sums, not mean of ratios)
+    #[serde(default)]
+    pub mean_compression_ratio: f64,
 
     // === Mean values ===
     pub mean_total_gas: f64,
@@ -91,9 +228,35 @@ pub struct WindowStats {
     pub mean_storage_gas: f64,
     pub mean_tx_size: f64,
     pub mean_da_size: f64,
+    /// Mean DA cost per block in gas-equivalent units
+    #[serde(default)]
+    pub mean_da_gas: f64,
     pub mean_data_size: f64,
     pub mean_kv_updates: f64,
     pub mean_state_growth: f64,
+    /// Mean burned fees per block, in wei
+    #[serde(default)]
+    pub mean_burned_fees: f64,
+
+    /// Transactions per EIP-2718 type across the window, merged from the
+    /// per-block counts
+    #[serde(default)]
+    pub tx_type_counts: std::collections::BTreeMap<u8, u64>,
+
+    /// Failed transactions across the window
+    #[serde(default)]
+    pub sum_failed_txs: u64,
+    /// Gas consumed by failed transactions across the window
+    #[serde(default)]
+    pub sum_failed_gas: u64,
+    /// Failed transactions / all transactions (0.0-1.0)
+    #[serde(default)]
+    pub fail_rate: f64,
+
+    /// True when any block in the window was committed under poller
+    /// sampling, so sums and counts understate the chain
+    #[serde(default)]
+    pub sampled: bool,
 
     // === P95 values ===
     pub p95_total_gas: u64,
@@ -116,14 +279,194 @@ pub struct WindowStats {
     pub max_state_growth: u64,
 
     // === Totals ===
+    // Serialized as decimal strings: gas summed over a long window can
+    // exceed 2^53 and lose precision in JavaScript (see [`as_decimal_string`])
+    #[serde(with = "as_decimal_string")]
     pub sum_total_gas: u64,
+    #[serde(with = "as_decimal_string")]
     pub sum_compute_gas: u64,
+    #[serde(with = "as_decimal_string")]
     pub sum_storage_gas: u64,
+    #[serde(with = "as_decimal_string")]
     pub sum_tx_size: u64,
+    #[serde(with = "as_decimal_string")]
     pub sum_da_size: u64,
+    #[serde(default, with = "as_decimal_string")]
+    pub sum_da_gas: u64,
+    #[serde(with = "as_decimal_string")]
     pub sum_data_size: u64,
+    #[serde(with = "as_decimal_string")]
     pub sum_kv_updates: u64,
+    #[serde(with = "as_decimal_string")]
     pub sum_state_growth: u64,
+    /// Total burned fees across the window, in wei
+    #[serde(default, with = "as_decimal_string")]
+    pub sum_burned_fees: u128,
+    /// Native value moved across the window, in wei
+    ///
+    /// A decimal string: wei totals overflow JSON's f64-safe integer range,
+    /// and a quoted decimal survives every JSON parser unharmed.
+    #[serde(default = "zero_decimal")]
+    pub sum_value_transferred: String,
+}
+
+fn zero_decimal() -> String {
+    "0".to_string()
+}
+
+/// Distribution of gas across mini-blocks over a time window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MiniBlockGasStats {
+    /// Start of the window
+    pub window_start: DateTime<Utc>,
+    /// End of the window
+    pub window_end: DateTime<Utc>,
+    /// Number of EVM blocks in window
+    pub block_count: u64,
+    /// Number of mini-blocks in window
+    pub mini_block_count: u64,
+    /// Mean gas used per mini-block
+    pub mean_gas_per_mini_block: f64,
+    /// Variance of per-mini-block gas (fill variance)
+    pub fill_variance: f64,
+    /// Smallest per-mini-block gas observed
+    pub min_gas: u64,
+    /// Largest per-mini-block gas observed
+    pub max_gas: u64,
+}
+
+/// A contract deployment observed in the live block stream
+#[derive(Debug, Clone, Serialize)]
+pub struct DeploymentEvent {
+    /// Block the deployment landed in
+    pub block_number: u64,
+    /// Block timestamp
+    pub timestamp: DateTime<Utc>,
+    /// Account that sent the creation transaction
+    pub deployer: Address,
+    /// Deployed contract address, when the receipt reports one
+    pub contract_address: Option<Address>,
+    /// Gas spent by the creation transaction
+    pub gas_used: u64,
+    /// Deployed runtime code size in bytes (0 when the fetch failed)
+    pub code_size: u64,
+}
+
+/// Deployment activity over a time window
+#[derive(Debug, Clone, Serialize)]
+pub struct DeploymentStats {
+    /// Start of the window
+    pub window_start: DateTime<Utc>,
+    /// End of the window
+    pub window_end: DateTime<Utc>,
+    /// Contract creations in the window
+    pub deploy_count: u64,
+    /// Distinct deployer addresses
+    pub unique_deployers: u64,
+    /// Gas spent on creations
+    pub total_deploy_gas: u64,
+    /// Total deployed runtime code bytes
+    pub total_code_size: u64,
+    /// The individual deployments, most recent last
+    pub deployments: Vec<DeploymentEvent>,
+}
+
+/// Activity attributed to a single known system contract
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemContractActivity {
+    /// Contract address
+    pub address: Address,
+    /// Human-readable name from the catalog
+    pub name: String,
+    /// Category from the catalog (infrastructure, bridge, defi)
+    pub category: String,
+    /// Transactions targeting this contract in the window
+    pub tx_count: u64,
+    /// Gas consumed by those transactions
+    pub total_gas: u64,
+}
+
+/// A single histogram bucket over a per-transaction metric
+///
+/// Bounds are f64 so log-scale bucket edges don't collapse to integers.
+#[derive(Debug, Clone, Serialize)]
+pub struct HistogramBucket {
+    /// Inclusive lower bound
+    pub lower: f64,
+    /// Exclusive upper bound (inclusive for the last bucket)
+    pub upper: f64,
+    /// Transactions falling in this bucket
+    pub count: u64,
+}
+
+/// Distribution of a per-transaction metric over a time window
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricHistogram {
+    /// Start of the window
+    pub window_start: DateTime<Utc>,
+    /// End of the window
+    pub window_end: DateTime<Utc>,
+    /// Which metric was binned
+    pub metric: String,
+    /// Whether bucket edges are log-spaced
+    pub log: bool,
+    /// Transactions observed in the window
+    pub tx_count: u64,
+    /// The buckets, in ascending order
+    pub buckets: Vec<HistogramBucket>,
+}
+
+/// One address's aggregated activity within a window
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressActivity {
+    /// Target contract or account
+    pub address: Address,
+    /// Transactions sent to this address in the window
+    pub tx_count: u64,
+    /// Sum of the chosen metric across those transactions
+    pub total: u64,
+}
+
+/// Top gas/DA-consuming addresses over a time window
+#[derive(Debug, Clone, Serialize)]
+pub struct TopAddressesStats {
+    /// Start of the window
+    pub window_start: DateTime<Utc>,
+    /// End of the window
+    pub window_end: DateTime<Utc>,
+    /// Which metric was summed
+    pub metric: String,
+    /// Addresses ranked by summed metric, descending
+    pub addresses: Vec<AddressActivity>,
+}
+
+/// Time-bucketed series of a block metric, for sparkline rendering
+#[derive(Debug, Clone, Serialize)]
+pub struct Sparkline {
+    /// Start of the window
+    pub window_start: DateTime<Utc>,
+    /// End of the window
+    pub window_end: DateTime<Utc>,
+    /// Which metric was bucketed
+    pub metric: String,
+    /// Width of each bucket in seconds
+    pub bucket_seconds: f64,
+    /// Per-bucket metric sums, oldest first; empty buckets are zero, so the
+    /// length always equals the requested bucket count
+    pub values: Vec<u64>,
+}
+
+/// Per-system-contract activity over a time window
+///
+/// Every catalog entry gets a row, with zero counts when inactive.
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemActivityStats {
+    /// Start of the window
+    pub window_start: DateTime<Utc>,
+    /// End of the window
+    pub window_end: DateTime<Utc>,
+    /// One row per known system contract
+    pub contracts: Vec<SystemContractActivity>,
 }
 
 impl Default for WindowStats {
@@ -134,14 +477,24 @@ impl Default for WindowStats {
             window_end: now,
             block_count: 0,
             tx_count: 0,
+            sum_mini_blocks: 0,
+            mean_mini_blocks: 0.0,
+            mean_compression_ratio: 0.0,
             mean_total_gas: 0.0,
             mean_compute_gas: 0.0,
             mean_storage_gas: 0.0,
             mean_tx_size: 0.0,
             mean_da_size: 0.0,
+            mean_da_gas: 0.0,
             mean_data_size: 0.0,
             mean_kv_updates: 0.0,
             mean_state_growth: 0.0,
+            mean_burned_fees: 0.0,
+            tx_type_counts: std::collections::BTreeMap::new(),
+            sum_failed_txs: 0,
+            sum_failed_gas: 0,
+            fail_rate: 0.0,
+            sampled: false,
             p95_total_gas: 0,
             p95_compute_gas: 0,
             p95_storage_gas: 0,
@@ -163,9 +516,52 @@ impl Default for WindowStats {
             sum_storage_gas: 0,
             sum_tx_size: 0,
             sum_da_size: 0,
+            sum_da_gas: 0,
             sum_data_size: 0,
             sum_kv_updates: 0,
             sum_state_growth: 0,
+            sum_burned_fees: 0,
+            sum_value_transferred: zero_decimal(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_sums_round_trip_as_strings() {
+        let stats = WindowStats {
+            block_count: 7,
+            sum_total_gas: (1u64 << 60) + 3,
+            sum_burned_fees: 1u128 << 90,
+            ..Default::default()
+        };
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(
+            json["sum_total_gas"],
+            serde_json::Value::String(((1u64 << 60) + 3).to_string())
+        );
+        assert_eq!(
+            json["sum_burned_fees"],
+            serde_json::Value::String((1u128 << 90).to_string())
+        );
+        // Small counts stay plain JSON numbers
+        assert!(json["block_count"].is_number());
+
+        let back: WindowStats = serde_json::from_value(json).unwrap();
+        assert_eq!(back.sum_total_gas, (1u64 << 60) + 3);
+        assert_eq!(back.sum_burned_fees, 1u128 << 90);
+    }
+
+    #[test]
+    fn test_window_sums_still_load_from_plain_numbers() {
+        let mut json = serde_json::to_value(WindowStats::default()).unwrap();
+        json["sum_total_gas"] = serde_json::json!(12_345);
+
+        let stats: WindowStats = serde_json::from_value(json).unwrap();
+        assert_eq!(stats.sum_total_gas, 12_345);
+    }
+}
@@ -1,25 +1,100 @@
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use alloy_primitives::{keccak256, B256};
 use anyhow::Result;
 use chrono::{DateTime, TimeZone, Utc};
+use lru::LruCache;
 
-use crate::metrics::{BlockMetrics, TransactionMetrics};
+use crate::metrics::{BlockMetrics, DeploymentEvent, TransactionMetrics};
 use crate::rpc::{RawBlock, RawReceipt, RawTransaction};
 
 /// Deposit transaction type (Optimism L1->L2 deposits)
 const DEPOSIT_TX_TYPE: u8 = 126;
 
+/// Reject a block when more than this fraction of its receipts are missing
+///
+/// A missing receipt falls back to the gas limit, which massively overstates
+/// total_gas; past this ratio the block is better retried than stored.
+const MAX_MISSING_RECEIPT_RATIO: f64 = 0.1;
+
+/// Entries in the DA-size cache; at ~200 bytes per key/value pair this is a
+/// few hundred KB, enough to cover the hot calldata shapes in a window
+const DA_CACHE_SIZE: usize = 4096;
+
 /// Calculates all 8 MegaETH resource metrics from block data
-pub struct MetricsCalculator;
+pub struct MetricsCalculator {
+    /// FastLZ compression dominates per-block CPU, and many transactions to
+    /// the same contract share calldata shapes. Cache the compressed length
+    /// keyed by input hash + encoded size so identical inputs aren't
+    /// recompressed.
+    da_size_cache: Mutex<LruCache<(B256, u64), u64>>,
+}
+
+impl Default for MetricsCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl MetricsCalculator {
     pub fn new() -> Self {
-        Self
+        Self {
+            da_size_cache: Mutex::new(LruCache::new(
+                NonZeroUsize::new(DA_CACHE_SIZE).unwrap(),
+            )),
+        }
+    }
+
+    /// FastLZ-compressed DA size for a transaction, memoized
+    ///
+    /// Deposits are exempt (not posted to DA). The key includes the encoded
+    /// size so two transactions sharing calldata but differing elsewhere
+    /// can't alias.
+    fn da_size(&self, tx: &RawTransaction) -> u64 {
+        if tx.tx_type == DEPOSIT_TX_TYPE {
+            return 0;
+        }
+
+        let key = (keccak256(&tx.input), tx.encoded_size());
+        if let Some(&cached) = self.da_size_cache.lock().unwrap().get(&key) {
+            return cached;
+        }
+
+        // Use FastLZ compressed size (same compression MegaETH uses for DA)
+        let tx_bytes = tx.to_bytes_for_da();
+        let da_size = op_alloy_flz::flz_compress_len(&tx_bytes) as u64;
+        self.da_size_cache.lock().unwrap().put(key, da_size);
+        da_size
     }
 
     /// Process a block and its receipts to extract all metrics
+    ///
+    /// Errors when too many receipts are missing (gas would fall back to
+    /// limits and overstate the block); the caller should refetch and retry.
     pub fn process_block(
         &self,
         block: &RawBlock,
         receipts: &[RawReceipt],
+    ) -> Result<(BlockMetrics, Vec<TransactionMetrics>)> {
+        self.process_block_inner(block, receipts, true)
+    }
+
+    /// Like [`process_block`](Self::process_block) but never rejects missing
+    /// receipts; the result carries `receipts_complete: false` instead
+    pub fn process_block_lenient(
+        &self,
+        block: &RawBlock,
+        receipts: &[RawReceipt],
+    ) -> Result<(BlockMetrics, Vec<TransactionMetrics>)> {
+        self.process_block_inner(block, receipts, false)
+    }
+
+    fn process_block_inner(
+        &self,
+        block: &RawBlock,
+        receipts: &[RawReceipt],
+        strict: bool,
     ) -> Result<(BlockMetrics, Vec<TransactionMetrics>)> {
         let block_number = block.number;
         let block_hash = block.hash;
@@ -32,9 +107,14 @@ impl MetricsCalculator {
         let mut storage_gas_sum: u64 = 0;
         let mut tx_size_sum: u64 = 0;
         let mut da_size_sum: u64 = 0;
+        let mut da_gas_sum: u64 = 0;
         let mut data_size_sum: u64 = 0;
         let mut kv_updates_sum: u64 = 0;
         let mut state_growth_sum: u64 = 0;
+        let mut value_sum = alloy_primitives::U256::ZERO;
+        let mut tx_type_counts = std::collections::BTreeMap::new();
+        let mut failed_tx_count: u64 = 0;
+        let mut failed_gas: u64 = 0;
 
         // Create a map of receipts by hash for lookup
         let receipt_map: std::collections::HashMap<_, _> = receipts
@@ -43,8 +123,12 @@ impl MetricsCalculator {
             .collect();
 
         // Process each transaction
+        let mut missing_receipts: u64 = 0;
         for tx in &block.transactions {
             let receipt = receipt_map.get(&tx.hash);
+            if receipt.is_none() {
+                missing_receipts += 1;
+            }
 
             // Get gas from receipt if available, otherwise use tx gas
             let total_gas = receipt.map(|r| r.gas_used).unwrap_or(tx.gas);
@@ -52,23 +136,28 @@ impl MetricsCalculator {
             // Calculate tx_size using exact EIP-2718 encoding
             let tx_size = tx.encoded_size();
 
-            // DA size: deposits are exempt (not posted to DA)
-            let is_deposit = tx.tx_type == DEPOSIT_TX_TYPE;
-            let da_size = if is_deposit {
-                0
-            } else {
-                // Use FastLZ compressed size (same compression MegaETH uses for DA)
-                let tx_bytes = tx.to_bytes_for_da();
-                op_alloy_flz::flz_compress_len(&tx_bytes) as u64
-            };
+            // DA size, memoized across identical inputs, and its
+            // gas-equivalent cost under the Fjord formula
+            let da_size = self.da_size(tx);
+            let da_gas = fjord_da_gas(da_size);
 
-            // Estimate mega-evm metrics
+            // Estimate mega-evm metrics, refined by the declared access list
             let input_len = tx.input.len() as u64;
+            let access_addresses = tx.access_list.len() as u64;
+            let access_keys: u64 = tx.access_list.iter().map(|(_, k)| k.len() as u64).sum();
             let (compute_gas, data_size, kv_updates, state_growth) =
-                estimate_mega_evm_metrics(total_gas, input_len);
+                estimate_mega_evm_metrics(total_gas, input_len, access_addresses, access_keys);
 
             let storage_gas = total_gas.saturating_sub(compute_gas);
 
+            // How compressible this transaction's bytes are; DA-exempt
+            // deposits report 0
+            let compression_ratio = if tx_size > 0 && da_size > 0 {
+                da_size as f64 / tx_size as f64
+            } else {
+                0.0
+            };
+
             let metrics = TransactionMetrics {
                 tx_hash: tx.hash,
                 block_number,
@@ -80,9 +169,12 @@ impl MetricsCalculator {
                 storage_gas,
                 tx_size,
                 da_size,
+                da_gas,
                 data_size,
                 kv_updates,
                 state_growth,
+                compression_ratio,
+                value_transferred: tx.value,
             };
 
             // Aggregate sums
@@ -91,13 +183,58 @@ impl MetricsCalculator {
             storage_gas_sum += storage_gas;
             tx_size_sum += tx_size;
             da_size_sum += da_size;
+            da_gas_sum += da_gas;
             data_size_sum += data_size;
             kv_updates_sum += kv_updates;
             state_growth_sum += state_growth;
+            value_sum = value_sum.saturating_add(tx.value);
+            *tx_type_counts.entry(tx.tx_type).or_insert(0) += 1;
+            // Reverted transactions still consume gas and DA; count them
+            // separately so fail-rate spikes are visible
+            if receipt.is_some_and(|r| !r.status) {
+                failed_tx_count += 1;
+                failed_gas += total_gas;
+            }
 
             tx_metrics.push(metrics);
         }
 
+        // Too many gas-limit fallbacks make the block worth retrying
+        let tx_count = block.transactions.len() as u64;
+        if strict
+            && tx_count > 0
+            && missing_receipts as f64 / tx_count as f64 > MAX_MISSING_RECEIPT_RATIO
+        {
+            anyhow::bail!(
+                "block {} is missing {} of {} receipts",
+                block_number,
+                missing_receipts,
+                tx_count
+            );
+        }
+
+        // Per-mini-block gas: degrade to a single mini-block when the RPC
+        // doesn't expose the structure
+        let mini_block_gas = if block.mini_block_gas.is_empty() {
+            vec![total_gas_sum]
+        } else {
+            block.mini_block_gas.clone()
+        };
+
+        // Prefer the RPC-reported count; degrade to the gas vector length
+        let mini_block_count = if block.mini_block_count > 0 {
+            block.mini_block_count
+        } else {
+            mini_block_gas.len() as u64
+        };
+
+        // Burned fees follow EIP-1559: the base-fee portion of every unit
+        // of gas is destroyed
+        let burned_fees = block
+            .base_fee_per_gas
+            .map(|fee| fee.saturating_mul(total_gas_sum as u128))
+            .unwrap_or(0);
+
         let block_metrics = BlockMetrics {
             block_number,
             block_hash,
@@ -108,14 +245,94 @@ impl MetricsCalculator {
             storage_gas: storage_gas_sum,
             tx_size: tx_size_sum,
             da_size: da_size_sum,
+            da_gas: da_gas_sum,
             data_size: data_size_sum,
             kv_updates: kv_updates_sum,
             state_growth: state_growth_sum,
+            compression_ratio: if tx_size_sum > 0 {
+                da_size_sum as f64 / tx_size_sum as f64
+            } else {
+                0.0
+            },
+            base_fee_per_gas: block.base_fee_per_gas,
+            burned_fees,
+            value_transferred: value_sum,
+            tx_type_counts,
+            failed_tx_count,
+            failed_gas,
+            // The poller flips this on when committing under sampling
+            sampled: false,
+            receipts_complete: missing_receipts == 0,
+            mini_block_count,
+            mini_block_gas,
             gas_limit,
         };
 
         Ok((block_metrics, tx_metrics))
     }
+
+    /// Extract contract-creation transactions from a block
+    ///
+    /// `code_size` is left at 0; the poller fills it in from `eth_getCode`
+    /// since fetching is async and this calculator is not.
+    pub fn extract_deployments(
+        &self,
+        block: &RawBlock,
+        receipts: &[RawReceipt],
+    ) -> Vec<DeploymentEvent> {
+        let timestamp = timestamp_to_datetime(block.timestamp);
+
+        let receipt_map: std::collections::HashMap<_, _> = receipts
+            .iter()
+            .map(|r| (r.transaction_hash, r))
+            .collect();
+
+        block
+            .transactions
+            .iter()
+            .filter(|tx| tx.to.is_none())
+            .filter_map(|tx| {
+                let receipt = receipt_map.get(&tx.hash);
+                // A reverted creation deploys nothing
+                if receipt.is_some_and(|r| !r.status) {
+                    return None;
+                }
+                Some(DeploymentEvent {
+                    block_number: block.number,
+                    timestamp,
+                    deployer: tx.from,
+                    contract_address: receipt.and_then(|r| r.contract_address),
+                    gas_used: receipt.map(|r| r.gas_used).unwrap_or(tx.gas),
+                    code_size: 0,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Fjord linear-regression intercept mapping a FastLZ-compressed size to
+/// an estimated on-chain data size, scaled by 1e6 (OP-stack Fjord upgrade)
+const FJORD_INTERCEPT: i64 = -42_585_600;
+/// Fjord linear-regression coefficient on the FastLZ size, scaled by 1e6
+const FJORD_FASTLZ_COEF: i64 = 836_500;
+/// Fjord floor on the estimated size, in bytes
+const FJORD_MIN_TX_SIZE_BYTES: i64 = 100;
+/// EIP-2028 calldata cost per (nonzero) byte
+const CALLDATA_GAS_PER_BYTE: u64 = 16;
+
+/// Express a FastLZ-compressed DA size as an equivalent gas cost
+///
+/// Applies the Fjord estimator `max(minTxSize, intercept + coef * flzSize)`
+/// (all terms scaled by 1e6) to get an effective data size in bytes, then
+/// charges it at the EIP-2028 16 gas/byte calldata rate. DA-exempt
+/// transactions (`da_size == 0`) cost nothing.
+fn fjord_da_gas(da_size: u64) -> u64 {
+    if da_size == 0 {
+        return 0;
+    }
+    let scaled = (FJORD_INTERCEPT + FJORD_FASTLZ_COEF * da_size as i64)
+        .max(FJORD_MIN_TX_SIZE_BYTES * 1_000_000) as u64;
+    scaled.saturating_mul(CALLDATA_GAS_PER_BYTE) / 1_000_000
 }
 
 /// Convert Unix timestamp to DateTime<Utc>
@@ -125,10 +342,26 @@ fn timestamp_to_datetime(timestamp: u64) -> DateTime<Utc> {
         .unwrap_or_else(Utc::now)
 }
 
+/// EIP-2930 upfront charge per access-list address
+const ACCESS_LIST_ADDRESS_COST: u64 = 2_400;
+/// EIP-2930 upfront charge per access-list storage key
+const ACCESS_LIST_STORAGE_KEY_COST: u64 = 1_900;
+
 /// Estimate mega-evm metrics (placeholder until mega-evm is integrated)
-fn estimate_mega_evm_metrics(total_gas: u64, input_len: u64) -> (u64, u64, u64, u64) {
+///
+/// The base split is the flat 70/30 heuristic, refined by the declared
+/// access list: the EIP-2930 upfront charges (2400 per address, 1900 per
+/// storage key) are storage-side gas by definition, so they put a floor
+/// under the storage share. Declared keys also signal storage the
+/// transaction intends to touch, so each one bumps the KV estimate.
+fn estimate_mega_evm_metrics(
+    total_gas: u64,
+    input_len: u64,
+    access_addresses: u64,
+    access_keys: u64,
+) -> (u64, u64, u64, u64) {
     // Estimate compute gas (typically 60-80% of total for contract calls)
-    let compute_gas = if input_len > 4 {
+    let mut compute_gas = if input_len > 4 {
         // Contract call - estimate 70% compute
         (total_gas as f64 * 0.7) as u64
     } else {
@@ -136,14 +369,195 @@ fn estimate_mega_evm_metrics(total_gas: u64, input_len: u64) -> (u64, u64, u64,
         (total_gas as f64 * 0.3) as u64
     };
 
+    // The upfront access-list charge can never be compute gas
+    let access_list_gas =
+        access_addresses * ACCESS_LIST_ADDRESS_COST + access_keys * ACCESS_LIST_STORAGE_KEY_COST;
+    compute_gas = compute_gas.min(total_gas.saturating_sub(access_list_gas));
+
     // Estimate data size from input length
     let data_size = input_len;
 
-    // Estimate KV updates from gas usage
-    let kv_updates = (total_gas / 20_000).max(1);
+    // Estimate KV updates from gas usage, floored by declared storage keys
+    let kv_updates = (total_gas / 20_000).max(access_keys).max(1);
 
     // Estimate state growth (subset of KV updates that are new)
     let state_growth = kv_updates / 5;
 
     (compute_gas, data_size, kv_updates, state_growth)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, Bytes, U256};
+
+    fn tx(index: u8) -> crate::rpc::RawTransaction {
+        crate::rpc::RawTransaction {
+            hash: B256::with_last_byte(index),
+            from: Address::with_last_byte(1),
+            to: Some(Address::with_last_byte(2)),
+            input: Bytes::new(),
+            gas: 1_000_000,
+            tx_type: 2,
+            nonce: index as u64,
+            value: U256::ZERO,
+            gas_price: None,
+            max_fee_per_gas: Some(1_000_000_000),
+            max_priority_fee_per_gas: Some(1_000_000),
+            chain_id: Some(6342),
+            v: 0,
+            r: U256::from(1),
+            s: U256::from(1),
+            access_list: Vec::new(),
+            blob_versioned_hashes: Vec::new(),
+            max_fee_per_blob_gas: None,
+        }
+    }
+
+    fn receipt(index: u8) -> crate::rpc::RawReceipt {
+        crate::rpc::RawReceipt {
+            transaction_hash: B256::with_last_byte(index),
+            gas_used: 21_000,
+            status: true,
+            contract_address: None,
+            from: Address::with_last_byte(1),
+            effective_gas_price: Some(1_000_000_000),
+        }
+    }
+
+    fn block(tx_count: u8) -> RawBlock {
+        RawBlock {
+            number: 1,
+            hash: B256::with_last_byte(1),
+            parent_hash: B256::ZERO,
+            base_fee_per_gas: None,
+            gas_used: 0,
+            gas_limit: 30_000_000,
+            timestamp: 1_700_000_000,
+            extra_data: Bytes::new(),
+            mini_block_count: 1,
+            mini_block_gas: vec![21_000],
+            transactions: (0..tx_count).map(tx).collect(),
+        }
+    }
+
+    #[test]
+    fn test_access_list_shifts_gas_toward_storage() {
+        // Same gas and input; only the access list differs
+        let keys = 20u64;
+        let (compute_plain, _, kv_plain, _) = estimate_mega_evm_metrics(100_000, 100, 0, 0);
+        let (compute_listed, _, kv_listed, _) = estimate_mega_evm_metrics(100_000, 100, 2, keys);
+
+        assert!(
+            compute_listed < compute_plain,
+            "access-list charges must come out of the compute share"
+        );
+        // 2 addresses + 20 keys = 42800 gas of upfront storage charges
+        assert!(compute_listed <= 100_000 - 42_800);
+        assert!(kv_listed >= keys, "declared keys floor the KV estimate");
+        assert!(kv_listed >= kv_plain);
+    }
+
+    #[test]
+    fn test_empty_access_list_keeps_flat_split() {
+        let (compute, _, _, _) = estimate_mega_evm_metrics(100_000, 100, 0, 0);
+        assert_eq!(compute, 70_000);
+    }
+
+    #[test]
+    fn test_fjord_da_gas_known_pairs() {
+        // Below the regression's break-even the 100-byte floor applies:
+        // 100 bytes * 16 gas/byte
+        assert_eq!(fjord_da_gas(51), 1_600);
+        // 1000 FastLZ bytes estimate to ~793.9 on-chain bytes:
+        // (-42_585_600 + 836_500 * 1000) * 16 / 1e6
+        assert_eq!(fjord_da_gas(1_000), 12_702);
+        // DA-exempt transactions cost nothing
+        assert_eq!(fjord_da_gas(0), 0);
+    }
+
+    #[test]
+    fn test_block_da_gas_is_sum_of_tx_da_gas() {
+        let calculator = MetricsCalculator::new();
+        let block = block(3);
+        let receipts: Vec<_> = (0..3).map(receipt).collect();
+
+        let (metrics, txs) = calculator.process_block(&block, &receipts).unwrap();
+        assert!(metrics.da_gas > 0);
+        assert_eq!(metrics.da_gas, txs.iter().map(|t| t.da_gas).sum::<u64>());
+    }
+
+    #[test]
+    fn test_complete_receipts_are_flagged_complete() {
+        let calculator = MetricsCalculator::new();
+        let block = block(4);
+        let receipts: Vec<_> = (0..4).map(receipt).collect();
+
+        let (metrics, _) = calculator.process_block(&block, &receipts).unwrap();
+        assert!(metrics.receipts_complete);
+        assert_eq!(metrics.total_gas, 4 * 21_000);
+    }
+
+    #[test]
+    fn test_tx_type_counts_cover_a_mixed_block() {
+        let calculator = MetricsCalculator::new();
+        let mut block = block(4);
+        block.transactions[0].tx_type = 0; // legacy
+        block.transactions[1].tx_type = 2; // EIP-1559
+        block.transactions[2].tx_type = 2;
+        block.transactions[3].tx_type = 126; // deposit
+        let receipts: Vec<_> = (0..4).map(receipt).collect();
+
+        let (metrics, _) = calculator.process_block(&block, &receipts).unwrap();
+        assert_eq!(metrics.tx_type_counts.get(&0), Some(&1));
+        assert_eq!(metrics.tx_type_counts.get(&2), Some(&2));
+        assert_eq!(metrics.tx_type_counts.get(&126), Some(&1));
+        assert_eq!(metrics.tx_type_counts.values().sum::<u64>(), 4);
+    }
+
+    #[test]
+    fn test_failed_receipts_are_counted() {
+        let calculator = MetricsCalculator::new();
+        let block = block(3);
+        let mut receipts: Vec<_> = (0..3).map(receipt).collect();
+        receipts[1].status = false;
+
+        let (metrics, _) = calculator.process_block(&block, &receipts).unwrap();
+        assert_eq!(metrics.failed_tx_count, 1);
+        assert_eq!(metrics.failed_gas, 21_000);
+        // Failed gas is a subset of total gas, not an addition to it
+        assert_eq!(metrics.total_gas, 3 * 21_000);
+    }
+
+    #[test]
+    fn test_burned_fees_follow_base_fee() {
+        let calculator = MetricsCalculator::new();
+        let mut block = block(2);
+        block.base_fee_per_gas = Some(7);
+        let receipts: Vec<_> = (0..2).map(receipt).collect();
+
+        let (metrics, _) = calculator.process_block(&block, &receipts).unwrap();
+        assert_eq!(metrics.base_fee_per_gas, Some(7));
+        assert_eq!(metrics.burned_fees, 7 * 2 * 21_000);
+    }
+
+    #[test]
+    fn test_too_many_missing_receipts_is_an_error() {
+        let calculator = MetricsCalculator::new();
+        let block = block(4);
+        // Half the receipts missing: gas would fall back to 1M limits
+        let receipts: Vec<_> = (0..2).map(receipt).collect();
+
+        assert!(calculator.process_block(&block, &receipts).is_err());
+    }
+
+    #[test]
+    fn test_lenient_processing_flags_incomplete_receipts() {
+        let calculator = MetricsCalculator::new();
+        let block = block(4);
+        let receipts: Vec<_> = (0..2).map(receipt).collect();
+
+        let (metrics, _) = calculator.process_block_lenient(&block, &receipts).unwrap();
+        assert!(!metrics.receipts_complete);
+    }
+}
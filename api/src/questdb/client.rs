@@ -0,0 +1,44 @@
+use anyhow::{Context, Result};
+use tokio_postgres::{Client, NoTls};
+use tracing::{error, info};
+
+/// Default QuestDB PostgreSQL wire endpoint
+const DEFAULT_PG_CONN: &str = "host=localhost port=8812 user=admin password=quest dbname=qdb";
+
+/// Read-only client for QuestDB over the PostgreSQL wire protocol
+pub struct QuestDBReader {
+    client: Client,
+}
+
+impl QuestDBReader {
+    /// Connect to QuestDB using QUESTDB_PG_CONN or the default local endpoint
+    pub async fn connect() -> Result<Self> {
+        let conn_str = std::env::var("QUESTDB_PG_CONN")
+            .unwrap_or_else(|_| DEFAULT_PG_CONN.to_string());
+
+        Self::connect_with(&conn_str).await
+    }
+
+    /// Connect to QuestDB with an explicit connection string
+    pub async fn connect_with(conn_str: &str) -> Result<Self> {
+        let (client, connection) = tokio_postgres::connect(conn_str, NoTls)
+            .await
+            .context("Failed to connect to QuestDB")?;
+
+        // The connection object performs the actual I/O; drive it in the background
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                error!("QuestDB connection error: {}", e);
+            }
+        });
+
+        info!("Connected to QuestDB");
+
+        Ok(Self { client })
+    }
+
+    /// Access the underlying PostgreSQL client
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+}
@@ -0,0 +1,15 @@
+// QuestDB integration for historical time-series queries
+// Uses the PostgreSQL wire protocol (port 8812 by default)
+
+mod client;
+mod models;
+mod queries;
+mod schema;
+mod writer;
+
+pub use client::QuestDBReader;
+pub use writer::QuestDBWriter;
+pub use models::{
+    BlockBucket, BlockGap, BlockHistoryResponse, BlockRange, DeploymentDetail,
+    DeploymentHeatmapCell, DeploymentHeatmapView,
+};
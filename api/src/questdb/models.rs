@@ -0,0 +1,110 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A single time bucket of block production
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockBucket {
+    /// Bucket start (Unix millis, matches frontend chart expectations)
+    pub timestamp: i64,
+    /// EVM blocks produced in this bucket
+    pub evm_blocks: u64,
+    /// Mini blocks produced in this bucket
+    pub mini_blocks: u64,
+    /// Whether this bucket is fully in the past (incomplete buckets render differently)
+    pub is_complete: bool,
+}
+
+/// A contiguous range of missing block numbers (inclusive on both ends)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl BlockRange {
+    /// Number of blocks in the range
+    pub fn len(&self) -> u64 {
+        self.end.saturating_sub(self.start) + 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end < self.start
+    }
+}
+
+/// A hole in the stored block sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockGap {
+    /// First block number after the gap
+    pub block_number: u64,
+    /// When that block was stored
+    pub timestamp: DateTime<Utc>,
+    /// Distance to the previous stored block (2 means one block is missing)
+    pub gap_size: u64,
+}
+
+/// Block production history for a sampling window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockHistoryResponse {
+    /// Window identifier (e.g. "1m", "1h", "24h")
+    pub window: String,
+    /// Time-ordered buckets
+    pub buckets: Vec<BlockBucket>,
+    /// Total EVM blocks across all buckets
+    pub total_evm_blocks: u64,
+    /// Total mini blocks across all buckets
+    pub total_mini_blocks: u64,
+    /// Gaps detected over the last 2 hours (only when requested)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gaps: Option<Vec<BlockGap>>,
+}
+
+/// Heatmap granularity for deployment activity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeploymentHeatmapView {
+    /// 24 hours × 14 days
+    Daily,
+    /// 7 weekdays × 12 weeks
+    Weekly,
+    /// 31 days × 12 months
+    Monthly,
+}
+
+/// One deployment row from a heatmap drill-down
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeploymentDetail {
+    /// Address the contract was deployed at
+    pub contract_address: String,
+    /// Account that sent the deployment transaction
+    pub deployer_address: String,
+    /// Identified contract type ("unknown" when unidentified)
+    pub contract_type: String,
+}
+
+/// One cell of the contract deployment heatmap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeploymentHeatmapCell {
+    /// Bucket start time
+    pub timestamp: DateTime<Utc>,
+    /// Contracts deployed in this bucket
+    pub contracts_deployed: u64,
+    /// Distinct deployer addresses
+    pub unique_deployers: u64,
+    /// Mean deployed code size (bytes)
+    pub avg_contract_size: f64,
+    /// P95 deployed code size (bytes)
+    pub p95_contract_size: f64,
+    /// Total deployed code bytes
+    pub total_code_bytes: u64,
+    /// Total gas spent on deployments
+    pub total_deploy_gas: u64,
+    /// Mean gas per deployment
+    pub avg_deploy_gas: f64,
+    /// Deployed contract addresses (filled by the details query)
+    pub contract_addresses: Vec<String>,
+    /// Deployer addresses (filled by the details query)
+    pub deployer_addresses: Vec<String>,
+    /// Identified contract types (filled by the details query)
+    pub contract_types: Vec<String>,
+}
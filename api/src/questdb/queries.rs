@@ -1,79 +1,30 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 
+use crate::metrics::WindowStats;
+
 use super::client::QuestDBReader;
-use super::models::{BlockBucket, BlockHistoryResponse, DeploymentHeatmapCell, DeploymentHeatmapView};
+use super::models::{
+    BlockBucket, BlockGap, BlockHistoryResponse, BlockRange, DeploymentDetail,
+    DeploymentHeatmapCell, DeploymentHeatmapView,
+};
 
 impl QuestDBReader {
     /// Get block production history for a specific window
     /// Uses QuestDB's native SAMPLE BY for time-series aggregation
-    pub async fn get_block_history(&self, window: &str) -> Result<BlockHistoryResponse> {
-        // #region agent log
-        use std::fs::OpenOptions;
-        use std::io::Write;
-        // Check for data gaps in QuestDB for last 2 hours
-        let gap_query = r#"
-            WITH block_gaps AS (
-                SELECT 
-                    block_number,
-                    timestamp,
-                    block_number - lag(block_number) OVER (ORDER BY block_number) AS gap
-                FROM block_production
-                WHERE timestamp >= dateadd('h', -2, now())
-                ORDER BY block_number
-            )
-            SELECT block_number, timestamp, gap
-            FROM block_gaps
-            WHERE gap > 1 AND gap IS NOT NULL
-            LIMIT 10
-        "#;
-        
-        let mut detected_gaps = Vec::new();
-        if let Ok(gap_rows) = self.client().query(gap_query, &[]).await {
-            for row in gap_rows {
-                let block_num: i64 = row.get(0);
-                let ts_sys: std::time::SystemTime = row.get(1);
-                let ts: DateTime<Utc> = ts_sys.into();
-                let gap: i64 = row.get(2);
-                detected_gaps.push(serde_json::json!({
-                    "block_number": block_num,
-                    "timestamp": ts.to_rfc3339(),
-                    "gap_size": gap
-                }));
-            }
-        }
-        
-        if let Ok(row) = self.client().query_one("SELECT max(block_number), max(timestamp), count() FROM block_production WHERE timestamp >= dateadd('h', -2, now())", &[]).await {
-            let max_block: Option<i64> = row.get(0);
-            let max_ts_sys: Option<std::time::SystemTime> = row.get(1);
-            let max_ts: Option<DateTime<Utc>> = max_ts_sys.map(|sys| sys.into());
-            let block_count: i64 = row.get(2);
-            
-            if let Ok(mut file) = OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open("/Users/leena/Documents/GitHub/MegaViz/.cursor/debug.log")
-            {
-                let entry = serde_json::json!({
-                    "sessionId": "debug-session",
-                    "runId": "post-fix",
-                    "hypothesisId": "H6",
-                    "location": "queries.rs:get_block_history:gap_check",
-                    "message": "QuestDB gap analysis (last 2h)",
-                    "data": {
-                        "window": window,
-                        "max_block_number": max_block,
-                        "max_timestamp": max_ts.map(|dt| dt.to_rfc3339()),
-                        "block_count_last_2h": block_count,
-                        "detected_gaps": detected_gaps,
-                        "current_time": Utc::now().to_rfc3339()
-                    },
-                    "timestamp": Utc::now().timestamp_millis(),
-                });
-                let _ = writeln!(file, "{}", entry);
-            }
-        }
-        // #endregion
+    ///
+    /// With `trace_gaps` set, also scans the last 2 hours for holes in the
+    /// stored block sequence and returns them in the response.
+    pub async fn get_block_history(
+        &self,
+        window: &str,
+        trace_gaps: bool,
+    ) -> Result<BlockHistoryResponse> {
+        let gaps = if trace_gaps {
+            Some(self.detect_block_gaps().await?)
+        } else {
+            None
+        };
 
         // Match sample interval and lookback period for each window
         let (sample_interval, lookback_hours) = match window {
@@ -129,57 +80,116 @@ impl QuestDBReader {
             });
         }
 
-        // #region agent log
-        if let Ok(mut file) = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open("/Users/leena/Documents/GitHub/MegaViz/.cursor/debug.log")
-        {
-            let first_3: Vec<_> = buckets.iter().take(3).map(|b| {
-                serde_json::json!({
-                    "ts": b.timestamp,
-                    "iso": DateTime::<Utc>::from_timestamp_millis(b.timestamp).map(|dt| dt.to_rfc3339()).unwrap_or_default(),
-                    "evm": b.evm_blocks,
-                    "mini": b.mini_blocks
-                })
-            }).collect();
-            let last_3: Vec<_> = buckets.iter().rev().take(3).rev().map(|b| {
-                serde_json::json!({
-                    "ts": b.timestamp,
-                    "iso": DateTime::<Utc>::from_timestamp_millis(b.timestamp).map(|dt| dt.to_rfc3339()).unwrap_or_default(),
-                    "evm": b.evm_blocks,
-                    "mini": b.mini_blocks
-                })
-            }).collect();
-            let entry = serde_json::json!({
-                "sessionId": "debug-session",
-                "runId": "pre-fix",
-                "hypothesisId": "H1",
-                "location": "queries.rs:get_block_history:buckets_collected",
-                "message": "QuestDB buckets collected",
-                "data": {
-                    "window": window,
-                    "bucket_count": buckets.len(),
-                    "sample_interval": sample_interval,
-                    "first_3_buckets": first_3,
-                    "last_3_buckets": last_3,
-                    "total_evm": total_evm,
-                    "total_mini": total_mini
-                },
-                "timestamp": Utc::now().timestamp_millis(),
-            });
-            let _ = writeln!(file, "{}", entry);
-        }
-        // #endregion
+        tracing::debug!(
+            window,
+            sample_interval,
+            bucket_count = buckets.len(),
+            total_evm,
+            total_mini,
+            "QuestDB block history collected"
+        );
 
         Ok(BlockHistoryResponse {
             window: window.to_string(),
             buckets,
             total_evm_blocks: total_evm,
             total_mini_blocks: total_mini,
+            gaps,
         })
     }
 
+    /// Find holes in the stored block sequence over the last 2 hours
+    async fn detect_block_gaps(&self) -> Result<Vec<BlockGap>> {
+        let gap_query = r#"
+            WITH block_gaps AS (
+                SELECT
+                    block_number,
+                    timestamp,
+                    block_number - lag(block_number) OVER (ORDER BY block_number) AS gap
+                FROM block_production
+                WHERE timestamp >= dateadd('h', -2, now())
+                ORDER BY block_number
+            )
+            SELECT block_number, timestamp, gap
+            FROM block_gaps
+            WHERE gap > 1 AND gap IS NOT NULL
+            LIMIT 10
+        "#;
+
+        let mut gaps = Vec::new();
+        for row in self.client().query(gap_query, &[]).await? {
+            let block_number: i64 = row.get(0);
+            let timestamp_sys: std::time::SystemTime = row.get(1);
+            let gap: i64 = row.get(2);
+            gaps.push(BlockGap {
+                block_number: block_number as u64,
+                timestamp: timestamp_sys.into(),
+                gap_size: gap as u64,
+            });
+        }
+
+        tracing::debug!(gap_count = gaps.len(), "QuestDB gap analysis (last 2h)");
+        Ok(gaps)
+    }
+
+    /// Find contiguous ranges of missing block numbers over the last `hours`
+    ///
+    /// Each gap row says block N follows block N - gap, so the missing range
+    /// is [N - gap + 1, N - 1]. Feed the result to a backfill routine.
+    pub async fn detect_gaps(&self, hours: i64) -> Result<Vec<BlockRange>> {
+        let query = format!(
+            r#"
+            WITH block_gaps AS (
+                SELECT
+                    block_number,
+                    block_number - lag(block_number) OVER (ORDER BY block_number) AS gap
+                FROM block_production
+                WHERE timestamp >= dateadd('h', -{}, now())
+                ORDER BY block_number
+            )
+            SELECT block_number, gap
+            FROM block_gaps
+            WHERE gap > 1 AND gap IS NOT NULL
+            "#,
+            hours
+        );
+
+        let mut ranges = Vec::new();
+        for row in self.client().query(&query, &[]).await? {
+            let block_number: i64 = row.get(0);
+            let gap: i64 = row.get(1);
+            let block_number = block_number as u64;
+            ranges.push(BlockRange {
+                start: block_number - gap as u64 + 1,
+                end: block_number - 1,
+            });
+        }
+
+        Ok(ranges)
+    }
+
+    /// Block numbers already stored within [start, end]
+    ///
+    /// Used to make overlapping backfills idempotent: a backfill skips any
+    /// block another writer landed since the gaps were detected.
+    pub async fn existing_block_numbers(
+        &self,
+        start: u64,
+        end: u64,
+    ) -> Result<std::collections::HashSet<u64>> {
+        let query = format!(
+            "SELECT block_number FROM block_production WHERE block_number >= {} AND block_number <= {}",
+            start, end
+        );
+
+        let mut existing = std::collections::HashSet::new();
+        for row in self.client().query(&query, &[]).await? {
+            let block_number: i64 = row.get(0);
+            existing.insert(block_number as u64);
+        }
+        Ok(existing)
+    }
+
     /// Get deployment heatmap data
     pub async fn get_deployment_heatmap(
         &self,
@@ -277,11 +287,15 @@ impl QuestDBReader {
     }
 
     /// Get detailed deployment info for a specific time bucket
+    ///
+    /// Backs the drill-down from a heatmap cell: the cell only carries
+    /// aggregates, this returns the individual deployments with their
+    /// identified contract types.
     pub async fn get_deployment_details(
         &self,
         bucket_start: DateTime<Utc>,
         bucket_end: DateTime<Utc>,
-    ) -> Result<Vec<(String, String, String)>> {
+    ) -> Result<Vec<DeploymentDetail>> {
         let query = format!(
             r#"
             SELECT
@@ -300,10 +314,11 @@ impl QuestDBReader {
 
         let mut details = Vec::new();
         for row in rows {
-            let contract_address: String = row.get(0);
-            let deployer_address: String = row.get(1);
-            let contract_type: String = row.get(2);
-            details.push((contract_address, deployer_address, contract_type));
+            details.push(DeploymentDetail {
+                contract_address: row.get(0),
+                deployer_address: row.get(1),
+                contract_type: row.get(2),
+            });
         }
 
         Ok(details)
@@ -325,6 +340,117 @@ impl QuestDBReader {
         Ok(count as u64)
     }
 
+    /// Aggregate block metrics between two timestamps into [`WindowStats`]
+    ///
+    /// Serves `/stats/compare` baselines older than the in-memory
+    /// retention. Only block-level counts, sums and means are available
+    /// here: the per-transaction p95/max fields stay at zero, and burned
+    /// fees are not persisted to QuestDB.
+    pub async fn get_window_stats_between(
+        &self,
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+    ) -> Result<WindowStats> {
+        let query = r#"
+            SELECT
+                count() as block_count,
+                sum(tx_count) as tx_count,
+                sum(total_gas) as sum_total_gas,
+                sum(compute_gas) as sum_compute_gas,
+                sum(storage_gas) as sum_storage_gas,
+                sum(tx_size) as sum_tx_size,
+                sum(da_size) as sum_da_size,
+                sum(data_size) as sum_data_size,
+                sum(kv_updates) as sum_kv_updates,
+                sum(state_growth) as sum_state_growth,
+                sum(mini_block_count) as sum_mini_blocks
+            FROM block_production
+            WHERE timestamp >= $1 AND timestamp <= $2
+        "#;
+        let row = self
+            .client()
+            .query_one(query, &[&window_start, &window_end])
+            .await?;
+
+        let block_count = row.get::<_, i64>(0) as u64;
+        // Sums over an empty window come back NULL
+        let sum = |idx: usize| row.get::<_, Option<i64>>(idx).unwrap_or(0) as u64;
+        let tx_count = sum(1);
+        let sum_total_gas = sum(2);
+        let sum_compute_gas = sum(3);
+        let sum_storage_gas = sum(4);
+        let sum_tx_size = sum(5);
+        let sum_da_size = sum(6);
+        let sum_data_size = sum(7);
+        let sum_kv_updates = sum(8);
+        let sum_state_growth = sum(9);
+        let sum_mini_blocks = sum(10);
+
+        let mean = |total: u64| {
+            if block_count > 0 {
+                total as f64 / block_count as f64
+            } else {
+                0.0
+            }
+        };
+
+        Ok(WindowStats {
+            window_start,
+            window_end,
+            block_count,
+            tx_count,
+            sum_mini_blocks,
+            mean_mini_blocks: mean(sum_mini_blocks),
+            // Ratio of sums, matching the in-memory aggregation
+            mean_compression_ratio: if sum_tx_size > 0 {
+                sum_da_size as f64 / sum_tx_size as f64
+            } else {
+                0.0
+            },
+            mean_total_gas: mean(sum_total_gas),
+            mean_compute_gas: mean(sum_compute_gas),
+            mean_storage_gas: mean(sum_storage_gas),
+            mean_tx_size: mean(sum_tx_size),
+            mean_da_size: mean(sum_da_size),
+            mean_data_size: mean(sum_data_size),
+            mean_kv_updates: mean(sum_kv_updates),
+            mean_state_growth: mean(sum_state_growth),
+            sum_total_gas,
+            sum_compute_gas,
+            sum_storage_gas,
+            sum_tx_size,
+            sum_da_size,
+            sum_data_size,
+            sum_kv_updates,
+            sum_state_growth,
+            ..Default::default()
+        })
+    }
+
+    /// Approximate percentile of one transaction_metrics column over a window
+    ///
+    /// `column` is interpolated into the SQL, so callers must validate it
+    /// against a known-column whitelist first. Returns None when the window
+    /// holds no rows.
+    pub async fn approx_tx_percentile(
+        &self,
+        column: &str,
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+        percentile: f64,
+    ) -> Result<Option<f64>> {
+        let query = format!(
+            "SELECT approx_percentile({}, {}) FROM transaction_metrics \
+             WHERE timestamp >= $1 AND timestamp <= $2",
+            column, percentile
+        );
+        let row = self
+            .client()
+            .query_one(&query, &[&window_start, &window_end])
+            .await?;
+        Ok(row.get::<_, Option<f64>>(0))
+    }
+
     /// Get latest block number in QuestDB
     pub async fn get_latest_block_number(&self) -> Result<Option<u64>> {
         let query = "SELECT max(block_number) FROM block_production";
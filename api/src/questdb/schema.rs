@@ -0,0 +1,153 @@
+// Schema DDL for the QuestDB tables this crate writes and reads
+//
+// ILP auto-creates tables on first write, but a fresh QuestDB instance
+// would fail every read until the poller lands data, and auto-created
+// tables lack the contract_type column the reader queries. Creating the
+// schema explicitly at startup makes a fresh instance queryable right
+// away. Every statement is CREATE TABLE IF NOT EXISTS, so running them
+// on each start is a no-op once the tables exist.
+//
+// Column names here must match the writer's ILP field names; a mismatch
+// would silently fork the table into two sets of columns.
+
+use anyhow::{Context, Result};
+use tracing::info;
+
+use super::client::QuestDBReader;
+
+/// One statement per table, in creation order
+const SCHEMA_DDL: [&str; 3] = [
+    r#"
+    CREATE TABLE IF NOT EXISTS block_production (
+        block_number LONG,
+        tx_count LONG,
+        total_gas LONG,
+        compute_gas LONG,
+        storage_gas LONG,
+        tx_size LONG,
+        da_size LONG,
+        data_size LONG,
+        kv_updates LONG,
+        state_growth LONG,
+        mini_block_count LONG,
+        gas_limit LONG,
+        timestamp TIMESTAMP
+    ) timestamp(timestamp) PARTITION BY DAY
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS transaction_metrics (
+        tx_hash STRING,
+        block_number LONG,
+        total_gas LONG,
+        compute_gas LONG,
+        storage_gas LONG,
+        tx_size LONG,
+        da_size LONG,
+        data_size LONG,
+        kv_updates LONG,
+        state_growth LONG,
+        timestamp TIMESTAMP
+    ) timestamp(timestamp) PARTITION BY DAY
+    "#,
+    // contract_type isn't written over ILP yet, but the details query
+    // selects it; declaring it here keeps that query valid on a fresh
+    // instance
+    r#"
+    CREATE TABLE IF NOT EXISTS contract_deployments (
+        contract_address STRING,
+        deployer_address STRING,
+        contract_type STRING,
+        block_number LONG,
+        gas_used LONG,
+        code_size_bytes LONG,
+        timestamp TIMESTAMP
+    ) timestamp(timestamp) PARTITION BY DAY
+    "#,
+];
+
+impl QuestDBReader {
+    /// Create any missing tables; safe to call on every startup
+    pub async fn ensure_schema(&self) -> Result<()> {
+        for ddl in SCHEMA_DDL {
+            self.client()
+                .batch_execute(ddl)
+                .await
+                .context("QuestDB schema DDL failed")?;
+        }
+        info!("QuestDB schema verified");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_ddl_is_idempotent() {
+        // Idempotency rests on IF NOT EXISTS; every table also needs its
+        // designated timestamp and daily partitioning for SAMPLE BY
+        for ddl in SCHEMA_DDL {
+            assert!(ddl.trim_start().starts_with("CREATE TABLE IF NOT EXISTS"));
+            assert!(ddl.contains("timestamp(timestamp) PARTITION BY DAY"));
+        }
+    }
+
+    #[test]
+    fn test_schema_covers_writer_columns() {
+        // Field names the ILP writer emits (see writer.rs); a column
+        // missing here would fork the table schema on first write
+        let tables: [(&str, &[&str]); 3] = [
+            (
+                "block_production",
+                &[
+                    "block_number",
+                    "tx_count",
+                    "total_gas",
+                    "compute_gas",
+                    "storage_gas",
+                    "tx_size",
+                    "da_size",
+                    "data_size",
+                    "kv_updates",
+                    "state_growth",
+                    "mini_block_count",
+                    "gas_limit",
+                ],
+            ),
+            (
+                "transaction_metrics",
+                &[
+                    "tx_hash",
+                    "block_number",
+                    "total_gas",
+                    "compute_gas",
+                    "storage_gas",
+                    "tx_size",
+                    "da_size",
+                    "data_size",
+                    "kv_updates",
+                    "state_growth",
+                ],
+            ),
+            (
+                "contract_deployments",
+                &[
+                    "contract_address",
+                    "deployer_address",
+                    "contract_type",
+                    "block_number",
+                    "gas_used",
+                    "code_size_bytes",
+                ],
+            ),
+        ];
+
+        for ((table, columns), ddl) in tables.iter().zip(SCHEMA_DDL) {
+            assert!(ddl.contains(table), "DDL for {} names the wrong table", table);
+            for column in *columns {
+                assert!(ddl.contains(column), "{} DDL is missing column {}", table, column);
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::metrics::{BlockMetrics, DeploymentEvent, TransactionMetrics};
+
+/// Default QuestDB ILP (InfluxDB line protocol) TCP endpoint
+const DEFAULT_ILP_ADDR: &str = "localhost:9009";
+
+/// Flush once this many lines are buffered
+const FLUSH_LINES: usize = 1000;
+
+/// Flush at least this often regardless of buffer size
+const FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Persists block and transaction metrics to QuestDB over ILP
+///
+/// Writes are buffered and flushed in batches so MegaETH's block rate
+/// doesn't turn into one TCP round trip per block.
+pub struct QuestDBWriter {
+    addr: String,
+    inner: Mutex<WriterInner>,
+}
+
+struct WriterInner {
+    stream: Option<TcpStream>,
+    buffer: String,
+    pending_lines: usize,
+    last_flush: Instant,
+}
+
+impl QuestDBWriter {
+    /// Connect to QuestDB using QUESTDB_ILP_ADDR or the default local endpoint
+    pub async fn connect() -> Result<Self> {
+        let addr = std::env::var("QUESTDB_ILP_ADDR")
+            .unwrap_or_else(|_| DEFAULT_ILP_ADDR.to_string());
+
+        let stream = TcpStream::connect(&addr)
+            .await
+            .context("Failed to connect to QuestDB ILP endpoint")?;
+
+        info!("Connected to QuestDB ILP at {}", addr);
+
+        Ok(Self {
+            addr,
+            inner: Mutex::new(WriterInner {
+                stream: Some(stream),
+                buffer: String::new(),
+                pending_lines: 0,
+                last_flush: Instant::now(),
+            }),
+        })
+    }
+
+    /// Buffer a block and its transactions, flushing if the batch is due
+    pub async fn write_block(
+        &self,
+        block: &BlockMetrics,
+        txs: &[TransactionMetrics],
+    ) -> Result<()> {
+        let mut inner = self.inner.lock().await;
+
+        let block_ts = block.timestamp.timestamp_nanos_opt().unwrap_or(0);
+
+        inner.buffer.push_str(&format!(
+            "block_production block_number={}i,tx_count={}i,total_gas={}i,compute_gas={}i,\
+             storage_gas={}i,tx_size={}i,da_size={}i,data_size={}i,kv_updates={}i,\
+             state_growth={}i,mini_block_count={}i,gas_limit={}i {}\n",
+            block.block_number,
+            block.tx_count,
+            block.total_gas,
+            block.compute_gas,
+            block.storage_gas,
+            block.tx_size,
+            block.da_size,
+            block.data_size,
+            block.kv_updates,
+            block.state_growth,
+            block.mini_block_gas.len(),
+            block.gas_limit,
+            block_ts,
+        ));
+        inner.pending_lines += 1;
+
+        for tx in txs {
+            inner.buffer.push_str(&format!(
+                "transaction_metrics tx_hash=\"{:?}\",block_number={}i,total_gas={}i,\
+                 compute_gas={}i,storage_gas={}i,tx_size={}i,da_size={}i,data_size={}i,\
+                 kv_updates={}i,state_growth={}i {}\n",
+                tx.tx_hash,
+                tx.block_number,
+                tx.total_gas,
+                tx.compute_gas,
+                tx.storage_gas,
+                tx.tx_size,
+                tx.da_size,
+                tx.data_size,
+                tx.kv_updates,
+                tx.state_growth,
+                block_ts,
+            ));
+            inner.pending_lines += 1;
+        }
+
+        if inner.pending_lines >= FLUSH_LINES || inner.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush_inner(&mut inner).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Buffer contract deployment events for the contract_deployments table
+    pub async fn write_deployments(&self, deployments: &[DeploymentEvent]) -> Result<()> {
+        if deployments.is_empty() {
+            return Ok(());
+        }
+
+        let mut inner = self.inner.lock().await;
+
+        for event in deployments {
+            let ts = event.timestamp.timestamp_nanos_opt().unwrap_or(0);
+            let contract = event
+                .contract_address
+                .map(|a| format!("{:?}", a))
+                .unwrap_or_default();
+            inner.buffer.push_str(&format!(
+                "contract_deployments contract_address=\"{}\",deployer_address=\"{:?}\",\
+                 block_number={}i,gas_used={}i,code_size_bytes={}i {}\n",
+                contract,
+                event.deployer,
+                event.block_number,
+                event.gas_used,
+                event.code_size,
+                ts,
+            ));
+            inner.pending_lines += 1;
+        }
+
+        if inner.pending_lines >= FLUSH_LINES || inner.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush_inner(&mut inner).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Force any buffered lines out to QuestDB
+    pub async fn flush(&self) -> Result<()> {
+        let mut inner = self.inner.lock().await;
+        self.flush_inner(&mut inner).await
+    }
+
+    async fn flush_inner(&self, inner: &mut WriterInner) -> Result<()> {
+        if inner.buffer.is_empty() {
+            inner.last_flush = Instant::now();
+            return Ok(());
+        }
+
+        // Reconnect lazily if a previous flush dropped the stream
+        if inner.stream.is_none() {
+            match TcpStream::connect(&self.addr).await {
+                Ok(stream) => {
+                    info!("Reconnected to QuestDB ILP at {}", self.addr);
+                    inner.stream = Some(stream);
+                }
+                Err(e) => {
+                    warn!("QuestDB ILP reconnect failed: {}", e);
+                    anyhow::bail!("QuestDB ILP endpoint unavailable: {}", e);
+                }
+            }
+        }
+
+        let buffer = std::mem::take(&mut inner.buffer);
+        inner.pending_lines = 0;
+        inner.last_flush = Instant::now();
+
+        if let Some(stream) = inner.stream.as_mut() {
+            if let Err(e) = stream.write_all(buffer.as_bytes()).await {
+                // Drop the connection; the next flush reconnects
+                inner.stream = None;
+                anyhow::bail!("QuestDB ILP write failed: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -2,15 +2,34 @@
 // Hybrid approach: Hot in-memory cache + RocksDB persistent storage
 // Provides 100% accurate state with bounded memory and unlimited storage
 
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use futures::future::{BoxFuture, Shared};
+use futures::FutureExt;
 use dashmap::DashMap;
 use lru::LruCache;
-use rocksdb::{DB, Options};
+use rocksdb::{BlockBasedOptions, Cache, DBCompressionType, Options, DB};
 use alloy_primitives::{Address, Bytes, U256, B256, keccak256};
 use revm::Database;
 use revm::state::{AccountInfo, Bytecode};
-use crate::rpc::MegaEthClient;
+use crate::rpc::{EthRpc, MegaEthClient};
+
+/// Hot cache capacity when none is given: roughly the working set of
+/// contracts touched by recent blocks
+const DEFAULT_HOT_CACHE_CAPACITY: usize = 1000;
+
+/// Concurrent eth_getStorageAt calls issued by prefetch_storage
+const STORAGE_PREFETCH_CONCURRENCY: usize = 16;
+
+/// Column family holding contract bytecode, keyed by raw address
+const CF_CODE: &str = "code";
+/// Column family persisting storage slots read during historical replays,
+/// keyed by block tag + address + slot (see [`storage_cold_key`])
+const CF_STORAGE: &str = "storage";
+
+/// Balance given to accounts when real balances aren't being fetched: plenty
+/// to pass gas-payment validation without an RPC call per sender
+const SYNTHETIC_BALANCE: U256 = U256::from_limbs([1_000_000_000_000_000_000, 0, 0, 0]);
 
 /// Database error type
 #[derive(Debug, Clone)]
@@ -26,6 +45,37 @@ impl std::error::Error for DatabaseError {}
 
 impl revm::database::DBErrorMarker for DatabaseError {}
 
+/// Serializable view of the cache counters, for monitoring over HTTP
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CacheStatsSnapshot {
+    /// Code lookups served from the in-memory hot cache
+    pub hot_hits: u64,
+    /// Code lookups served from RocksDB
+    pub cold_hits: u64,
+    /// Code lookups that went to RPC
+    pub rpc_fetches: u64,
+    /// Storage slot lookups served from the LRU cache
+    pub storage_hits: u64,
+    /// Storage slot lookups served from RocksDB (historical replays only)
+    pub cold_storage_hits: u64,
+    /// Storage slot lookups that went to RPC
+    pub storage_misses: u64,
+    /// Lookups that piggybacked on another task's in-flight RPC fetch
+    pub coalesced_hits: u64,
+    /// Storage slots loaded ahead of execution by prefetch_storage
+    pub prefetched_slots: u64,
+    /// Prefetched slots that execution actually read
+    pub prefetch_hits: u64,
+    /// Fraction of prefetched slots later read, as a percentage
+    pub prefetch_hit_rate: f64,
+    /// Storage hit rate as a percentage (0 before any lookups)
+    pub storage_hit_rate: f64,
+    /// Accounts currently cached
+    pub account_count: usize,
+    /// Contracts currently in the hot cache
+    pub hot_cache_size: usize,
+}
+
 /// Smart caching database with hybrid storage
 ///
 /// Architecture:
@@ -36,13 +86,19 @@ impl revm::database::DBErrorMarker for DatabaseError {}
 ///
 /// Memory usage: ~150MB (bounded forever)
 /// Disk usage: Unlimited (grows with unique contracts)
-pub struct SmartCacheDB {
+///
+/// Generic over the RPC so tests can run against a mock; production code
+/// uses the default [`MegaEthClient`].
+pub struct SmartCacheDB<R: EthRpc = MegaEthClient> {
     /// RPC client for fetching state
-    rpc: Arc<MegaEthClient>,
+    rpc: Arc<R>,
 
-    /// HOT cache: Last 1000 contracts used (in-memory, instant)
+    /// HOT cache: most recently used contracts (in-memory, instant)
     /// Maps: Address → Bytecode
-    hot_cache: Arc<DashMap<Address, Bytes>>,
+    ///
+    /// A std mutex is fine here: the lock is only held for map operations,
+    /// never across an await point.
+    hot_cache: Arc<std::sync::Mutex<LruCache<Address, Bytes>>>,
 
     /// COLD cache: All contracts (RocksDB, microseconds)
     /// Persists across restarts - no pre-warming needed!
@@ -50,7 +106,11 @@ pub struct SmartCacheDB {
 
     /// Storage cache with LRU eviction (100K slots = ~6MB)
     /// Maps: (Address, Slot) → Value
-    storage_cache: Arc<Mutex<LruCache<(Address, U256), U256>>>,
+    ///
+    /// A std mutex, like the hot cache: it is only held for map operations,
+    /// never across an await point, and a sync lock lets `commit` write
+    /// changed slots without re-entering the runtime.
+    storage_cache: Arc<std::sync::Mutex<LruCache<(Address, U256), U256>>>,
 
     /// Account info cache (balance, nonce, code hash)
     /// Maps: Address → AccountInfo
@@ -58,6 +118,28 @@ pub struct SmartCacheDB {
 
     /// Statistics for monitoring cache performance
     stats: Arc<CacheStats>,
+
+    /// Block tag all RPC state reads are pinned to ("latest" or a hex block
+    /// number); shared across clones so the replayer can repoint it per block
+    block_tag: Arc<std::sync::RwLock<String>>,
+
+    /// In-flight code fetches, so concurrent misses share one RPC call
+    inflight_code: Arc<std::sync::Mutex<HashMap<Address, SharedFetch<Bytes>>>>,
+
+    /// In-flight storage fetches, keyed by (address, slot)
+    inflight_storage: Arc<std::sync::Mutex<HashMap<(Address, U256), SharedFetch<U256>>>>,
+
+    /// Slots loaded by prefetch_storage but not yet read by execution, for
+    /// measuring how useful access-list prefetching actually is
+    prefetched_keys: Arc<std::sync::Mutex<std::collections::HashSet<(Address, U256)>>>,
+
+    /// Fetch real balances via eth_getBalance at the replay block tag
+    /// instead of seeding [`SYNTHETIC_BALANCE`]
+    ///
+    /// Off by default: the synthetic balance is enough for gas-payment
+    /// validation and avoids an RPC call per sender. Turn it on for
+    /// accuracy-sensitive replays that track value flows.
+    fetch_real_balances: bool,
 }
 
 #[derive(Default)]
@@ -66,34 +148,235 @@ pub struct CacheStats {
     pub cold_hits: std::sync::atomic::AtomicU64,
     pub rpc_fetches: std::sync::atomic::AtomicU64,
     pub storage_hits: std::sync::atomic::AtomicU64,
+    pub cold_storage_hits: std::sync::atomic::AtomicU64,
     pub storage_misses: std::sync::atomic::AtomicU64,
+    pub coalesced_hits: std::sync::atomic::AtomicU64,
+    pub prefetched_slots: std::sync::atomic::AtomicU64,
+    pub prefetch_hits: std::sync::atomic::AtomicU64,
+}
+
+/// A fetch several tasks can await together; errors are carried as strings
+/// because anyhow::Error isn't Clone
+type SharedFetch<V> = Shared<BoxFuture<'static, Result<V, String>>>;
+
+/// Await a shared in-flight fetch for `key`, starting `fetch` only when no
+/// fetch for that key is already running
+///
+/// Returns the result and whether this call coalesced onto an existing
+/// fetch. The task that started the fetch removes it from the map once done.
+async fn single_flight<K, V>(
+    map: &std::sync::Mutex<HashMap<K, SharedFetch<V>>>,
+    key: K,
+    fetch: BoxFuture<'static, Result<V, String>>,
+) -> (Result<V, String>, bool)
+where
+    K: Eq + std::hash::Hash + Clone,
+    V: Clone,
+{
+    let (fut, coalesced) = {
+        let mut inflight = map.lock().unwrap();
+        if let Some(existing) = inflight.get(&key) {
+            (existing.clone(), true)
+        } else {
+            let fut = fetch.shared();
+            inflight.insert(key.clone(), fut.clone());
+            (fut, false)
+        }
+    };
+
+    let result = fut.await;
+
+    if !coalesced {
+        map.lock().unwrap().remove(&key);
+    }
+
+    (result, coalesced)
+}
+
+/// Tuning for the RocksDB cold store
+///
+/// Callers that want control (tests, the replay CLI pointing at scratch
+/// space) construct this directly; [`ColdStoreConfig::from_env`] reads the
+/// deployment knobs:
+///
+///   CACHE_DB_PATH            - database directory (default ./data/contract_cache)
+///   CACHE_DB_BLOCK_CACHE_MB  - read block cache size (default 64)
+///   CACHE_DB_WRITE_BUFFER_MB - memtable size before flush (default 32)
+///   CACHE_DB_COMPRESSION     - none | snappy | lz4 | zstd (default lz4)
+#[derive(Debug, Clone)]
+pub struct ColdStoreConfig {
+    pub path: std::path::PathBuf,
+    pub block_cache_mb: usize,
+    pub write_buffer_mb: usize,
+    pub compression: String,
+}
+
+impl ColdStoreConfig {
+    pub fn from_env() -> Self {
+        Self {
+            path: std::env::var("CACHE_DB_PATH")
+                .unwrap_or_else(|_| "./data/contract_cache".to_string())
+                .into(),
+            block_cache_mb: std::env::var("CACHE_DB_BLOCK_CACHE_MB")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(64),
+            write_buffer_mb: std::env::var("CACHE_DB_WRITE_BUFFER_MB")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(32),
+            compression: std::env::var("CACHE_DB_COMPRESSION")
+                .unwrap_or_else(|_| "lz4".to_string()),
+        }
+    }
+}
+
+/// Open the RocksDB cold store with its column families
+///
+/// Code previously stored in the default column family is not migrated:
+/// this is a cache, so old entries simply refetch into the code CF.
+fn open_cold_store(config: &ColdStoreConfig) -> anyhow::Result<DB> {
+    let compression = match config.compression.as_str() {
+        "none" => DBCompressionType::None,
+        "snappy" => DBCompressionType::Snappy,
+        "lz4" => DBCompressionType::Lz4,
+        "zstd" => DBCompressionType::Zstd,
+        other => anyhow::bail!(
+            "Unknown cold-store compression '{}' (expected none, snappy, lz4 or zstd)",
+            other
+        ),
+    };
+
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.create_missing_column_families(true);
+    opts.set_max_open_files(256);
+    opts.set_compression_type(compression);
+    opts.set_write_buffer_size(config.write_buffer_mb * 1024 * 1024);
+
+    let mut block_opts = BlockBasedOptions::default();
+    block_opts.set_block_cache(&Cache::new_lru_cache(config.block_cache_mb * 1024 * 1024));
+    opts.set_block_based_table_factory(&block_opts);
+
+    Ok(DB::open_cf(&opts, &config.path, [CF_CODE, CF_STORAGE])?)
+}
+
+/// Cold-storage key: the pinned block tag plus address plus slot
+///
+/// The block tag is part of the key so values persisted while replaying one
+/// historical block are never served when the cache is later pinned to a
+/// different one.
+fn storage_cold_key(block_tag: &str, address: Address, index: U256) -> Vec<u8> {
+    let mut key = Vec::with_capacity(block_tag.len() + 20 + 32);
+    key.extend_from_slice(block_tag.as_bytes());
+    key.extend_from_slice(address.as_slice());
+    key.extend_from_slice(&index.to_be_bytes::<32>());
+    key
 }
 
-impl SmartCacheDB {
-    /// Create a new SmartCacheDB with hybrid caching
-    pub fn new(rpc: Arc<MegaEthClient>) -> anyhow::Result<Self> {
-        // Open RocksDB for persistent contract storage
-        let mut opts = Options::default();
-        opts.create_if_missing(true);
-        opts.set_max_open_files(256);
+impl<R: EthRpc> SmartCacheDB<R> {
+    /// Create a new SmartCacheDB with hybrid caching, the default hot cache
+    /// capacity and env-driven cold-store tuning
+    pub fn new(rpc: Arc<R>) -> anyhow::Result<Self> {
+        Self::with_hot_capacity(rpc, DEFAULT_HOT_CACHE_CAPACITY)
+    }
 
-        let db_path = std::env::var("CACHE_DB_PATH")
-            .unwrap_or_else(|_| "./data/contract_cache".to_string());
+    /// Create a new SmartCacheDB keeping at most `hot_capacity` contracts in
+    /// the in-memory hot cache, evicting the least recently used beyond that
+    pub fn with_hot_capacity(rpc: Arc<R>, hot_capacity: usize) -> anyhow::Result<Self> {
+        Self::with_config(rpc, ColdStoreConfig::from_env(), hot_capacity)
+    }
 
-        let cold_cache = DB::open(&opts, db_path)?;
+    /// Create a new SmartCacheDB with explicit cold-store placement and
+    /// tuning; the other constructors defer to the environment
+    pub fn with_config(
+        rpc: Arc<R>,
+        config: ColdStoreConfig,
+        hot_capacity: usize,
+    ) -> anyhow::Result<Self> {
+        let cold_cache = open_cold_store(&config)?;
 
         Ok(Self {
             rpc,
-            hot_cache: Arc::new(DashMap::new()),
+            hot_cache: Arc::new(std::sync::Mutex::new(LruCache::new(
+                std::num::NonZeroUsize::new(hot_capacity.max(1)).unwrap(),
+            ))),
             cold_cache: Arc::new(cold_cache),
-            storage_cache: Arc::new(Mutex::new(LruCache::new(
+            storage_cache: Arc::new(std::sync::Mutex::new(LruCache::new(
                 std::num::NonZeroUsize::new(100_000).unwrap()  // 100K slots = ~6MB
             ))),
             accounts: Arc::new(DashMap::new()),
             stats: Arc::new(CacheStats::default()),
+            block_tag: Arc::new(std::sync::RwLock::new("latest".to_string())),
+            inflight_code: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            inflight_storage: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            prefetched_keys: Arc::new(std::sync::Mutex::new(std::collections::HashSet::new())),
+            fetch_real_balances: false,
         })
     }
 
+    /// The bytecode column family handle
+    fn code_cf(&self) -> &rocksdb::ColumnFamily {
+        self.cold_cache
+            .cf_handle(CF_CODE)
+            .expect("code column family is opened by the constructor")
+    }
+
+    /// The persisted-storage column family handle
+    fn storage_cf(&self) -> &rocksdb::ColumnFamily {
+        self.cold_cache
+            .cf_handle(CF_STORAGE)
+            .expect("storage column family is opened by the constructor")
+    }
+
+    /// Fetch real balances at the replay block tag instead of the synthetic
+    /// placeholder (see [`Self::account_balance`])
+    pub fn with_real_balances(mut self, enabled: bool) -> Self {
+        self.fetch_real_balances = enabled;
+        self
+    }
+
+    /// Load storage slots ahead of execution with bounded concurrency
+    ///
+    /// Lazily fetching one slot per RPC round-trip serializes network latency
+    /// during replay; an access-list-bearing transaction tells us most of the
+    /// slots it will touch up front, so prime the LRU before executing it.
+    /// Already-cached slots are skipped; fetch failures are ignored (the lazy
+    /// path will retry them).
+    pub async fn prefetch_storage(&self, accesses: &[(Address, U256)]) {
+        // Drop slots the cache already holds
+        let mut wanted = Vec::new();
+        {
+            let mut cache = self.storage_cache.lock().unwrap();
+            for &key in accesses {
+                if !wanted.contains(&key) && cache.get(&key).is_none() {
+                    wanted.push(key);
+                }
+            }
+        }
+
+        let block_tag = self.block_tag();
+        for chunk in wanted.chunks(STORAGE_PREFETCH_CONCURRENCY) {
+            let fetched = futures::future::join_all(chunk.iter().map(|&(address, index)| {
+                let fut = self.rpc.get_storage_at_block(address, index, &block_tag);
+                async move { ((address, index), fut.await) }
+            }))
+            .await;
+
+            let mut cache = self.storage_cache.lock().unwrap();
+            let mut prefetched = self.prefetched_keys.lock().unwrap();
+            for (key, result) in fetched {
+                if let Ok(value) = result {
+                    cache.put(key, value);
+                    prefetched.insert(key);
+                    self.stats
+                        .prefetched_slots
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
     /// Pre-warm the cache by fetching recent blocks
     ///
     /// This is now OPTIONAL - RocksDB persists across restarts!
@@ -127,13 +410,13 @@ impl SmartCacheDB {
             if (block_num - start_block) % 100 == 0 {
                 println!("  📦 Processed {} blocks, {} contracts in hot cache",
                     block_num - start_block,
-                    self.hot_cache.len()
+                    self.hot_len()
                 );
             }
         }
 
         println!("✅ Cache warmed: {} unique contracts", unique_contracts.len());
-        println!("   Hot cache: {} contracts", self.hot_cache.len());
+        println!("   Hot cache: {} contracts", self.hot_len());
 
         Ok(())
     }
@@ -141,23 +424,23 @@ impl SmartCacheDB {
     /// Fetch and cache contract code (stores in both hot and cold cache)
     async fn fetch_and_cache_code(&self, address: Address) {
         // Check if already in hot cache
-        if self.hot_cache.contains_key(&address) {
+        if self.hot_cache.lock().unwrap().contains(&address) {
             return;
         }
 
         // Check if in cold cache (RocksDB)
         let addr_bytes = address.as_slice();
-        if let Ok(Some(_)) = self.cold_cache.get(addr_bytes) {
+        if let Ok(Some(_)) = self.cold_cache.get_cf(self.code_cf(), addr_bytes) {
             // Already in persistent storage, no need to fetch
             return;
         }
 
         // Fetch from RPC
-        match self.rpc.get_code(address).await {
+        match self.rpc.get_code_at(address, &self.block_tag()).await {
             Ok(code) => {
                 // Store in both caches
-                self.hot_cache.insert(address, code.clone());
-                let _ = self.cold_cache.put(addr_bytes, code.as_ref());
+                self.hot_put(address, code.clone());
+                let _ = self.cold_cache.put_cf(self.code_cf(), addr_bytes, code.as_ref());
             }
             Err(e) => {
                 eprintln!("⚠️  Failed to fetch code for {:?}: {}", address, e);
@@ -167,70 +450,152 @@ impl SmartCacheDB {
 
     /// Get contract code (3-tier lookup: hot → cold → RPC)
     async fn get_code(&self, address: Address) -> anyhow::Result<Bytes> {
-        // Tier 1: Check hot cache (instant)
-        if let Some(code) = self.hot_cache.get(&address) {
+        // Tier 1: Check hot cache (instant); this also refreshes recency
+        if let Some(code) = self.hot_get(&address) {
             self.stats.hot_hits.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-            return Ok(code.clone());
+            return Ok(code);
         }
 
         // Tier 2: Check cold cache (RocksDB, ~10μs)
         let addr_bytes = address.as_slice();
-        if let Ok(Some(code_bytes)) = self.cold_cache.get(addr_bytes) {
+        if let Ok(Some(code_bytes)) = self.cold_cache.get_cf(self.code_cf(), addr_bytes) {
             self.stats.cold_hits.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
             let code = Bytes::from(code_bytes.to_vec());
 
-            // Promote to hot cache
-            self.hot_cache.insert(address, code.clone());
-
-            // Evict oldest from hot cache if too large (keep last 1000)
-            if self.hot_cache.len() > 1000 {
-                // DashMap doesn't have built-in LRU, but with 1000 limit we're fine
-                // In practice, hot contracts stay hot
-            }
+            // Promote to hot cache; LruCache evicts the oldest entry at capacity
+            self.hot_put(address, code.clone());
 
             return Ok(code);
         }
 
-        // Tier 3: Fetch from RPC (~100ms)
-        self.stats.rpc_fetches.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        // Tier 3: Fetch from RPC (~100ms), coalescing concurrent misses
+        let rpc = Arc::clone(&self.rpc);
+        let block_tag = self.block_tag();
+        let (result, coalesced) = single_flight(
+            &self.inflight_code,
+            address,
+            async move {
+                rpc.get_code_at(address, &block_tag)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+            .boxed(),
+        )
+        .await;
+
+        if coalesced {
+            // Another task's fetch served us; it also fills the caches
+            self.stats.coalesced_hits.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            return result.map_err(|e| anyhow::anyhow!(e));
+        }
 
-        let code = self.rpc.get_code(address).await?;
+        self.stats.rpc_fetches.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let code = result.map_err(|e| anyhow::anyhow!(e))?;
 
         // Store in both caches
-        self.hot_cache.insert(address, code.clone());
-        let _ = self.cold_cache.put(addr_bytes, code.as_ref());
+        self.hot_put(address, code.clone());
+        let _ = self.cold_cache.put_cf(self.code_cf(), addr_bytes, code.as_ref());
 
         Ok(code)
     }
 
-    /// Get storage value (LRU cached or fetch from RPC)
+    /// Get storage value (LRU cached, cold-persisted for historical blocks,
+    /// or fetch from RPC)
     async fn get_storage(&self, address: Address, index: U256) -> anyhow::Result<U256> {
         let key = (address, index);
 
         // Check LRU cache first
         {
-            let mut cache = self.storage_cache.lock().await;
+            let mut cache = self.storage_cache.lock().unwrap();
             if let Some(value) = cache.get(&key) {
                 self.stats.storage_hits.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                // Count each prefetched slot's first read toward the
+                // prefetch hit rate
+                if self.prefetched_keys.lock().unwrap().remove(&key) {
+                    self.stats
+                        .prefetch_hits
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                }
                 return Ok(*value);
             }
         }
 
-        // Cache miss - fetch from RPC
-        self.stats.storage_misses.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        // Historical blocks are immutable, so their slots can come from (and
+        // go to) the persistent storage CF; "latest" must stay RPC-fresh
+        let block_tag = self.block_tag();
+        let historical = block_tag != "latest";
+        if historical {
+            let cold_key = storage_cold_key(&block_tag, address, index);
+            if let Ok(Some(bytes)) = self.cold_cache.get_cf(self.storage_cf(), &cold_key) {
+                if bytes.len() == 32 {
+                    let value = U256::from_be_slice(&bytes);
+                    self.stats
+                        .cold_storage_hits
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    self.storage_cache.lock().unwrap().put(key, value);
+                    return Ok(value);
+                }
+            }
+        }
 
-        let value = self.rpc.get_storage_at(address, index).await?;
+        // Cache miss - fetch from RPC, coalescing concurrent misses
+        let rpc = Arc::clone(&self.rpc);
+        let rpc_tag = block_tag.clone();
+        let (result, coalesced) = single_flight(
+            &self.inflight_storage,
+            key,
+            async move {
+                rpc.get_storage_at_block(address, index, &rpc_tag)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+            .boxed(),
+        )
+        .await;
+
+        if coalesced {
+            self.stats.coalesced_hits.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            return result.map_err(|e| anyhow::anyhow!(e));
+        }
+
+        self.stats.storage_misses.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let value = result.map_err(|e| anyhow::anyhow!(e))?;
 
         // Insert into LRU cache
         {
-            let mut cache = self.storage_cache.lock().await;
+            let mut cache = self.storage_cache.lock().unwrap();
             cache.put(key, value);
         }
 
+        // And into the persistent tier, where staleness is impossible
+        if historical {
+            let _ = self.cold_cache.put_cf(
+                self.storage_cf(),
+                storage_cold_key(&block_tag, address, index),
+                value.to_be_bytes::<32>(),
+            );
+        }
+
         Ok(value)
     }
 
+    /// Balance to seed an account with
+    ///
+    /// Committed balances in the account cache win (so value transfers made
+    /// earlier in the replay are visible); otherwise the real balance is
+    /// fetched at the replay block tag when enabled, and the synthetic
+    /// placeholder is used when not.
+    async fn account_balance(&self, address: Address) -> anyhow::Result<U256> {
+        if !self.fetch_real_balances {
+            return Ok(SYNTHETIC_BALANCE);
+        }
+        if let Some(info) = self.accounts.get(&address) {
+            return Ok(info.balance);
+        }
+        self.rpc.get_balance_at(address, &self.block_tag()).await
+    }
+
     /// Get account info (balance, nonce, code)
     ///
     /// NOTE: For replay, we use the transaction nonce as the account nonce.
@@ -247,9 +612,8 @@ impl SmartCacheDB {
         };
 
         // Use transaction nonce as account nonce for replay validation
-        // Balance doesn't affect tracking accuracy
         let info = AccountInfo {
-            balance: U256::from(1_000_000_000_000_000_000u128), // Give enough balance
+            balance: self.account_balance(address).await?,
             nonce: tx_nonce,  // Match transaction nonce to pass validation
             code_hash,
             code: if code.is_empty() { None } else { Some(Bytecode::new_legacy(code)) },
@@ -266,7 +630,7 @@ impl SmartCacheDB {
         }
 
         let code = self.get_code(address).await?;
-        let balance = U256::from(1_000_000_000_000_000_000u128);
+        let balance = self.account_balance(address).await?;
         let nonce = 0;  // Start at 0 for new accounts
 
         let code_hash = if code.is_empty() {
@@ -299,13 +663,86 @@ impl SmartCacheDB {
         self.accounts.entry(address)
             .and_modify(|info| info.nonce = nonce)  // Update existing account's nonce
             .or_insert_with(|| AccountInfo {
-                balance: U256::from(1_000_000_000_000_000_000u128), // Plenty of balance
+                balance: SYNTHETIC_BALANCE,
                 nonce,
                 code_hash: B256::ZERO,
                 code: None,
             });
     }
 
+    /// Pin all RPC state reads to the given block number
+    ///
+    /// The replayer sets this to the parent of the block being replayed so
+    /// code and storage reads reflect pre-block state, not present-day state.
+    /// Cached entries are NOT invalidated: callers switching between distant
+    /// blocks should use a fresh cache.
+    pub fn set_block_number(&self, number: u64) {
+        *self.block_tag.write().unwrap() = format!("0x{:x}", number);
+    }
+
+    /// The block tag RPC state reads are currently pinned to
+    fn block_tag(&self) -> String {
+        self.block_tag.read().unwrap().clone()
+    }
+
+    /// Look up a contract in the hot cache, refreshing its recency
+    fn hot_get(&self, address: &Address) -> Option<Bytes> {
+        self.hot_cache.lock().unwrap().get(address).cloned()
+    }
+
+    /// Insert a contract into the hot cache, evicting the LRU entry at capacity
+    fn hot_put(&self, address: Address, code: Bytes) {
+        self.hot_cache.lock().unwrap().put(address, code);
+    }
+
+    /// Contracts currently in the hot cache
+    fn hot_len(&self) -> usize {
+        self.hot_cache.lock().unwrap().len()
+    }
+
+    /// Take a serializable snapshot of the cache counters
+    ///
+    /// This is what `/cache/stats` serves; `print_stats` renders the same
+    /// numbers to stdout for CLI use.
+    pub fn stats_snapshot(&self) -> CacheStatsSnapshot {
+        let hot_hits = self.stats.hot_hits.load(std::sync::atomic::Ordering::Relaxed);
+        let cold_hits = self.stats.cold_hits.load(std::sync::atomic::Ordering::Relaxed);
+        let rpc_fetches = self.stats.rpc_fetches.load(std::sync::atomic::Ordering::Relaxed);
+        let storage_hits = self.stats.storage_hits.load(std::sync::atomic::Ordering::Relaxed);
+        let cold_storage_hits = self.stats.cold_storage_hits.load(std::sync::atomic::Ordering::Relaxed);
+        let storage_misses = self.stats.storage_misses.load(std::sync::atomic::Ordering::Relaxed);
+        let coalesced_hits = self.stats.coalesced_hits.load(std::sync::atomic::Ordering::Relaxed);
+        let prefetched_slots = self.stats.prefetched_slots.load(std::sync::atomic::Ordering::Relaxed);
+        let prefetch_hits = self.stats.prefetch_hits.load(std::sync::atomic::Ordering::Relaxed);
+
+        let storage_hit_rate = if storage_hits + storage_misses > 0 {
+            (storage_hits as f64 / (storage_hits + storage_misses) as f64) * 100.0
+        } else {
+            0.0
+        };
+        let prefetch_hit_rate = if prefetched_slots > 0 {
+            (prefetch_hits as f64 / prefetched_slots as f64) * 100.0
+        } else {
+            0.0
+        };
+
+        CacheStatsSnapshot {
+            hot_hits,
+            cold_hits,
+            rpc_fetches,
+            storage_hits,
+            cold_storage_hits,
+            storage_misses,
+            coalesced_hits,
+            prefetched_slots,
+            prefetch_hits,
+            prefetch_hit_rate,
+            storage_hit_rate,
+            account_count: self.accounts.len(),
+            hot_cache_size: self.hot_len(),
+        }
+    }
+
     /// Print cache statistics
     pub fn print_stats(&self) {
         let hot_hits = self.stats.hot_hits.load(std::sync::atomic::Ordering::Relaxed);
@@ -340,12 +777,16 @@ impl SmartCacheDB {
         println!("     RPC fetches: {}", rpc_fetches);
         println!("   Storage cache: {:.1}% hit rate ({} hits, {} misses)",
             storage_hit_rate, storage_hits, storage_misses);
+        println!("   Cold storage hits (RocksDB): {}",
+            self.stats.cold_storage_hits.load(std::sync::atomic::Ordering::Relaxed));
+        println!("   Coalesced in-flight hits: {}",
+            self.stats.coalesced_hits.load(std::sync::atomic::Ordering::Relaxed));
         println!("   Accounts: {} cached", self.accounts.len());
     }
 }
 
 // Implement Clone for SmartCacheDB (all fields are Arc, so this is cheap)
-impl Clone for SmartCacheDB {
+impl<R: EthRpc> Clone for SmartCacheDB<R> {
     fn clone(&self) -> Self {
         Self {
             rpc: Arc::clone(&self.rpc),
@@ -354,15 +795,20 @@ impl Clone for SmartCacheDB {
             storage_cache: Arc::clone(&self.storage_cache),
             accounts: Arc::clone(&self.accounts),
             stats: Arc::clone(&self.stats),
+            block_tag: Arc::clone(&self.block_tag),
+            inflight_code: Arc::clone(&self.inflight_code),
+            inflight_storage: Arc::clone(&self.inflight_storage),
+            prefetched_keys: Arc::clone(&self.prefetched_keys),
+            fetch_real_balances: self.fetch_real_balances,
         }
     }
 }
 
 // Implement Debug for SmartCacheDB
-impl std::fmt::Debug for SmartCacheDB {
+impl<R: EthRpc> std::fmt::Debug for SmartCacheDB<R> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("SmartCacheDB")
-            .field("hot_cache_size", &self.hot_cache.len())
+            .field("hot_cache_size", &self.hot_len())
             .field("accounts_size", &self.accounts.len())
             .finish()
     }
@@ -370,7 +816,7 @@ impl std::fmt::Debug for SmartCacheDB {
 
 // Implement revm Database trait for SmartCacheDB
 // This allows it to be used as a drop-in replacement for MemoryDatabase
-impl Database for SmartCacheDB {
+impl<R: EthRpc> Database for SmartCacheDB<R> {
     type Error = DatabaseError;
 
     fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
@@ -406,8 +852,10 @@ impl Database for SmartCacheDB {
 }
 
 // Implement DatabaseCommit for SmartCacheDB
-// This allows state changes to be committed after each transaction during replay
-impl revm::DatabaseCommit for SmartCacheDB {
+// This allows state changes to be committed after each transaction during replay.
+// Inserting the post-transaction AccountInfo also supersedes any balance
+// fetched by account_balance, so later reads see committed value flows.
+impl<R: EthRpc> revm::DatabaseCommit for SmartCacheDB<R> {
     fn commit(&mut self, changes: revm::primitives::HashMap<Address, revm::state::Account>) {
         // Apply state changes to our caches so subsequent transactions see the updates
         for (address, account) in changes {
@@ -421,20 +869,19 @@ impl revm::DatabaseCommit for SmartCacheDB {
                 let bytes = Bytes::copy_from_slice(bytecode);
 
                 // Store in hot cache
-                self.hot_cache.insert(address, bytes.clone());
+                self.hot_put(address, bytes.clone());
 
                 // Store in cold cache (RocksDB)
-                let _ = self.cold_cache.put(address.as_slice(), bytes.as_ref());
+                let _ = self.cold_cache.put_cf(self.code_cf(), address.as_slice(), bytes.as_ref());
             }
 
-            // Update storage cache with changed storage slots
-            for (slot, value) in account.storage {
-                tokio::task::block_in_place(|| {
-                    tokio::runtime::Handle::current().block_on(async {
-                        let mut storage = self.storage_cache.lock().await;
-                        storage.put((address, slot.into()), value.present_value.into());
-                    })
-                });
+            // Update storage cache with changed storage slots; one lock for
+            // the whole account instead of a runtime block per slot
+            if !account.storage.is_empty() {
+                let mut storage = self.storage_cache.lock().unwrap();
+                for (slot, value) in account.storage {
+                    storage.put((address, slot.into()), value.present_value.into());
+                }
             }
         }
     }
@@ -443,6 +890,136 @@ impl revm::DatabaseCommit for SmartCacheDB {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn test_cold_store_persists_across_reopen() {
+        let path = std::env::temp_dir().join(format!(
+            "megaviz-coldstore-test-{}",
+            std::process::id()
+        ));
+        let config = ColdStoreConfig {
+            path: path.clone(),
+            block_cache_mb: 8,
+            write_buffer_mb: 8,
+            compression: "lz4".to_string(),
+        };
+        let address = Address::repeat_byte(0x42);
+        let code = [0x60u8, 0x80];
+
+        {
+            let db = open_cold_store(&config).unwrap();
+            db.put_cf(db.cf_handle(CF_CODE).unwrap(), address.as_slice(), code)
+                .unwrap();
+        }
+
+        // Reopen: the write must have survived the close
+        let db = open_cold_store(&config).unwrap();
+        let read = db
+            .get_cf(db.cf_handle(CF_CODE).unwrap(), address.as_slice())
+            .unwrap()
+            .expect("code persisted across reopen");
+        assert_eq!(read, code);
+
+        drop(db);
+        let _ = DB::destroy(&Options::default(), &path);
+    }
+
+    #[test]
+    fn test_cold_storage_persists_across_reopen() {
+        let path = std::env::temp_dir().join(format!(
+            "megaviz-coldstorage-test-{}",
+            std::process::id()
+        ));
+        let config = ColdStoreConfig {
+            path: path.clone(),
+            block_cache_mb: 8,
+            write_buffer_mb: 8,
+            compression: "lz4".to_string(),
+        };
+        let address = Address::repeat_byte(0x24);
+        let slot = U256::from(7);
+        let value = U256::from(99);
+        let key = storage_cold_key("0x64", address, slot);
+
+        {
+            let db = open_cold_store(&config).unwrap();
+            db.put_cf(
+                db.cf_handle(CF_STORAGE).unwrap(),
+                &key,
+                value.to_be_bytes::<32>(),
+            )
+            .unwrap();
+        }
+
+        let db = open_cold_store(&config).unwrap();
+        let read = db
+            .get_cf(db.cf_handle(CF_STORAGE).unwrap(), &key)
+            .unwrap()
+            .expect("slot persisted across reopen");
+        assert_eq!(U256::from_be_slice(&read), value);
+
+        // The block tag is part of the key, so another block sees nothing
+        let other = storage_cold_key("0x65", address, slot);
+        assert!(db
+            .get_cf(db.cf_handle(CF_STORAGE).unwrap(), &other)
+            .unwrap()
+            .is_none());
+
+        drop(db);
+        let _ = DB::destroy(&Options::default(), &path);
+    }
+
+    #[test]
+    fn test_unknown_compression_is_rejected() {
+        let config = ColdStoreConfig {
+            path: std::env::temp_dir().join("megaviz-coldstore-badcfg"),
+            block_cache_mb: 8,
+            write_buffer_mb: 8,
+            compression: "brotli".to_string(),
+        };
+        assert!(open_cold_store(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_single_flight_coalesces_concurrent_fetches() {
+        let map = Arc::new(std::sync::Mutex::new(HashMap::new()));
+        let fetches = Arc::new(AtomicU64::new(0));
+
+        let mut handles = Vec::new();
+        for _ in 0..8 {
+            let map = Arc::clone(&map);
+            let fetches = Arc::clone(&fetches);
+            handles.push(tokio::spawn(async move {
+                let counter = Arc::clone(&fetches);
+                single_flight(
+                    &map,
+                    Address::with_last_byte(1),
+                    async move {
+                        counter.fetch_add(1, Ordering::Relaxed);
+                        // Hold the fetch open long enough for the other
+                        // tasks to pile onto it
+                        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                        Ok(Bytes::from(vec![0x60]))
+                    }
+                    .boxed(),
+                )
+                .await
+            }));
+        }
+
+        let mut coalesced_count = 0;
+        for handle in handles {
+            let (result, coalesced) = handle.await.unwrap();
+            assert_eq!(result.unwrap(), Bytes::from(vec![0x60]));
+            if coalesced {
+                coalesced_count += 1;
+            }
+        }
+
+        assert_eq!(fetches.load(Ordering::Relaxed), 1, "exactly one RPC fetch");
+        assert_eq!(coalesced_count, 7);
+    }
 
     #[tokio::test]
     async fn test_cache_structure() {
@@ -450,4 +1027,42 @@ mod tests {
         let client = Arc::new(MegaEthClient::new("https://carrot.megaeth.com/rpc").await.unwrap());
         let _cache_db = SmartCacheDB::new(client).unwrap();
     }
+
+    #[tokio::test]
+    async fn test_hot_cache_evicts_least_recently_used() {
+        // Own database directory: the env-default path is shared with
+        // test_cache_structure, and RocksDB's LOCK file rejects a second
+        // concurrent open
+        let path = std::env::temp_dir().join(format!(
+            "megaviz-hotcache-test-{}",
+            std::process::id()
+        ));
+        let config = ColdStoreConfig {
+            path: path.clone(),
+            block_cache_mb: 8,
+            write_buffer_mb: 8,
+            compression: "lz4".to_string(),
+        };
+        let client = Arc::new(MegaEthClient::new("https://carrot.megaeth.com/rpc").await.unwrap());
+        let db = SmartCacheDB::with_config(client, config, 3).unwrap();
+
+        for i in 1u8..=3 {
+            db.hot_put(Address::with_last_byte(i), Bytes::from(vec![i]));
+        }
+
+        // Touch 1 so 2 becomes the least recently used entry
+        assert!(db.hot_get(&Address::with_last_byte(1)).is_some());
+
+        // A fourth insert evicts exactly one entry: the LRU (2)
+        db.hot_put(Address::with_last_byte(4), Bytes::from(vec![4]));
+
+        assert_eq!(db.hot_len(), 3);
+        assert!(db.hot_get(&Address::with_last_byte(2)).is_none());
+        for i in [1u8, 3, 4] {
+            assert!(db.hot_get(&Address::with_last_byte(i)).is_some());
+        }
+
+        drop(db);
+        let _ = DB::destroy(&Options::default(), &path);
+    }
 }
@@ -0,0 +1,634 @@
+// Replay executor: drives revm over the smart cache to measure per-transaction
+// resource usage, falling back to the heuristic estimate when a transaction
+// cannot be replayed (unsupported opcode, missing precompile, RPC error).
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+use alloy_primitives::{Address, B256, U256};
+use anyhow::{Context as _, Result};
+use serde::Serialize;
+use revm::context::TxEnv;
+use revm::primitives::TxKind;
+use revm::{Context, ExecuteEvm, MainBuilder, MainContext};
+use tracing::warn;
+
+use super::SmartCacheDB;
+use crate::metrics::{BlockMetrics, TransactionMetrics};
+use crate::processor::MetricsCalculator;
+use crate::rpc::{RawBlock, RawReceipt, RawTransaction};
+
+/// Errors callers need to branch on, beyond generic replay failures
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    /// A sender's transactions carry nonces that don't line up with the
+    /// seeded account nonce; replaying anyway would silently produce wrong
+    /// results
+    #[error("nonce mismatch for sender {sender}: expected {expected}, tx {tx_hash} carries {found}")]
+    NonceMismatch {
+        sender: Address,
+        expected: u64,
+        found: u64,
+        tx_hash: B256,
+    },
+}
+
+/// Where a transaction's metrics came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricProvenance {
+    /// Measured by replaying the transaction in revm
+    Replayed,
+    /// Heuristic estimate (replay failed or was skipped)
+    Estimated,
+}
+
+/// Per-transaction metrics tagged with their provenance
+#[derive(Debug, Clone)]
+pub struct ReplayedTxMetrics {
+    pub metrics: TransactionMetrics,
+    pub provenance: MetricProvenance,
+}
+
+/// Resource usage measured from a single replayed transaction
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayUsage {
+    pub total_gas: u64,
+    pub kv_updates: u64,
+    pub state_growth: u64,
+}
+
+/// Storage and account changes measured from one replayed transaction
+///
+/// Only addresses the transaction actually touched appear, so the diff
+/// stays small no matter how much state the cache holds.
+#[derive(Debug, Clone, Serialize)]
+pub struct StateDiff {
+    pub tx_hash: B256,
+    /// Per-address changes
+    pub accounts: HashMap<Address, AccountDiff>,
+}
+
+/// Changes to one account: (before, after) pairs, present only when the
+/// value actually changed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AccountDiff {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<(U256, U256)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<(u64, u64)>,
+    /// Changed storage slots: slot -> (before, after)
+    pub storage: HashMap<U256, (U256, U256)>,
+}
+
+/// Counters tracking how often replay falls back to estimation
+#[derive(Default)]
+pub struct ReplayStats {
+    attempted: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl ReplayStats {
+    pub fn record_attempt(&self) {
+        self.attempted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_failure(&self) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn attempted(&self) -> u64 {
+        self.attempted.load(Ordering::Relaxed)
+    }
+
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Fraction of replay attempts that fell back to estimation (0.0-1.0)
+    pub fn failure_rate(&self) -> f64 {
+        let attempted = self.attempted();
+        if attempted == 0 {
+            return 0.0;
+        }
+        self.failed() as f64 / attempted as f64
+    }
+}
+
+/// Process-wide replay counters, readable from the metrics endpoint
+pub fn replay_stats() -> &'static ReplayStats {
+    static STATS: OnceLock<ReplayStats> = OnceLock::new();
+    STATS.get_or_init(ReplayStats::default)
+}
+
+/// Replays whole blocks, one transaction at a time
+pub struct BlockReplayer {
+    db: SmartCacheDB,
+    calculator: MetricsCalculator,
+}
+
+impl BlockReplayer {
+    pub fn new(db: SmartCacheDB) -> Self {
+        Self {
+            db,
+            calculator: MetricsCalculator::new(),
+        }
+    }
+
+    /// Replay every transaction in a block
+    ///
+    /// A transaction that fails to replay keeps its heuristic estimate
+    /// (provenance Estimated) instead of zeroing out or aborting the block;
+    /// the rest of the block continues to replay normally.
+    pub fn replay_block(
+        &self,
+        block: &RawBlock,
+        receipts: &[RawReceipt],
+    ) -> Result<(BlockMetrics, Vec<ReplayedTxMetrics>)> {
+        // Pin state reads to the parent block so replay sees pre-block state
+        self.db.set_block_number(block.number.saturating_sub(1));
+
+        // Seed each sender at its lowest nonce in the block and let revm
+        // increment through the rest; bail early when the block's own nonces
+        // don't line up, which would otherwise replay silently wrong
+        let starting_nonces = sender_starting_nonces(block)?;
+        for (sender, nonce) in &starting_nonces {
+            self.db.seed_account_nonce(*sender, *nonce);
+        }
+
+        // Access lists tell us most slots the block will touch; prime the
+        // cache concurrently instead of paying one RPC round-trip per slot
+        // during execution
+        let accesses: Vec<(alloy_primitives::Address, alloy_primitives::U256)> = block
+            .transactions
+            .iter()
+            .flat_map(|tx| tx.access_list.iter())
+            .flat_map(|(address, keys)| {
+                keys.iter()
+                    .map(move |key| (*address, alloy_primitives::U256::from_be_bytes(key.0)))
+            })
+            .collect();
+        if !accesses.is_empty() {
+            tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current()
+                    .block_on(self.db.prefetch_storage(&accesses))
+            });
+        }
+
+        replay_block_with(&self.calculator, block, receipts, |tx| self.replay_tx(tx))
+    }
+
+    /// Replay one transaction of a block and return its state diff
+    ///
+    /// Transactions before `tx_index` are replayed and committed first so
+    /// the target sees accurate mid-block state; their individual failures
+    /// are logged but don't abort the diff.
+    pub fn replay_tx_with_diff(&self, block: &RawBlock, tx_index: usize) -> Result<StateDiff> {
+        let tx = block.transactions.get(tx_index).with_context(|| {
+            format!("block {} has no transaction at index {}", block.number, tx_index)
+        })?;
+
+        self.db.set_block_number(block.number.saturating_sub(1));
+        let starting_nonces = sender_starting_nonces(block)?;
+        for (sender, nonce) in &starting_nonces {
+            self.db.seed_account_nonce(*sender, *nonce);
+        }
+
+        for prior in &block.transactions[..tx_index] {
+            match self.execute_tx(prior) {
+                Ok(result) => {
+                    let mut db = self.db.clone();
+                    revm::DatabaseCommit::commit(&mut db, result.state);
+                }
+                Err(e) => warn!(
+                    "Replay of earlier tx {:?} failed, diff may miss its state changes: {}",
+                    prior.hash, e
+                ),
+            }
+        }
+
+        let result = self.execute_tx(tx)?;
+
+        let mut db = self.db.clone();
+        let mut accounts = HashMap::new();
+        for (address, account) in result.state {
+            if !account.is_touched() {
+                continue;
+            }
+
+            // The change set carries post-state; pre-state comes from the
+            // (not yet committed) cache
+            let before = revm::Database::basic(&mut db, address).ok().flatten();
+
+            let mut diff = AccountDiff::default();
+            if let Some(before) = &before {
+                if before.balance != account.info.balance {
+                    diff.balance = Some((before.balance, account.info.balance));
+                }
+                if before.nonce != account.info.nonce {
+                    diff.nonce = Some((before.nonce, account.info.nonce));
+                }
+            }
+            for (slot, value) in &account.storage {
+                if value.is_changed() {
+                    diff.storage
+                        .insert(*slot, (value.original_value(), value.present_value()));
+                }
+            }
+
+            if diff.balance.is_some() || diff.nonce.is_some() || !diff.storage.is_empty() {
+                accounts.insert(address, diff);
+            }
+        }
+
+        Ok(StateDiff {
+            tx_hash: tx.hash,
+            accounts,
+        })
+    }
+
+    /// Execute a single transaction against the cache DB without committing
+    fn execute_tx(&self, tx: &RawTransaction) -> Result<revm::context::result::ResultAndState> {
+        let mut evm = Context::mainnet()
+            .with_db(self.db.clone())
+            .build_mainnet();
+
+        let tx_env = TxEnv {
+            caller: tx.from,
+            gas_limit: tx.gas,
+            gas_price: tx.gas_price.unwrap_or_default(),
+            kind: match tx.to {
+                Some(to) => TxKind::Call(to),
+                None => TxKind::Create,
+            },
+            value: tx.value,
+            data: tx.input.clone(),
+            nonce: tx.nonce,
+            ..Default::default()
+        };
+
+        evm.transact(tx_env)
+            .map_err(|e| anyhow::anyhow!("revm execution failed: {e:?}"))
+    }
+
+    /// Replay a single transaction and measure its resource usage
+    fn replay_tx(&self, tx: &RawTransaction) -> Result<ReplayUsage> {
+        let result = self.execute_tx(tx)?;
+
+        // Count storage writes and newly-created state from the change set
+        let mut kv_updates = 0u64;
+        let mut state_growth = 0u64;
+        for account in result.state.values() {
+            if account.is_created() {
+                state_growth += 1;
+            }
+            for slot in account.storage.values() {
+                if slot.is_changed() {
+                    kv_updates += 1;
+                    if slot.original_value().is_zero() && !slot.present_value().is_zero() {
+                        state_growth += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(ReplayUsage {
+            total_gas: result.result.gas_used(),
+            kv_updates,
+            state_growth,
+        })
+    }
+}
+
+/// Per-sender starting nonce for a block, verifying nonces are consecutive
+///
+/// The first transaction from a sender defines the nonce to seed; every
+/// later transaction from the same sender must be exactly one higher than
+/// the previous, matching how revm increments the seeded account.
+fn sender_starting_nonces(block: &RawBlock) -> Result<HashMap<Address, u64>, ReplayError> {
+    let mut starting: HashMap<Address, u64> = HashMap::new();
+    let mut next_expected: HashMap<Address, u64> = HashMap::new();
+
+    for tx in &block.transactions {
+        match next_expected.get_mut(&tx.from) {
+            None => {
+                starting.insert(tx.from, tx.nonce);
+                next_expected.insert(tx.from, tx.nonce + 1);
+            }
+            Some(expected) => {
+                if tx.nonce != *expected {
+                    return Err(ReplayError::NonceMismatch {
+                        sender: tx.from,
+                        expected: *expected,
+                        found: tx.nonce,
+                        tx_hash: tx.hash,
+                    });
+                }
+                *expected += 1;
+            }
+        }
+    }
+
+    Ok(starting)
+}
+
+/// Replay a block using an injectable per-transaction replay function
+///
+/// Split out from BlockReplayer so the fallback behavior is testable without
+/// an EVM or a live cache database.
+fn replay_block_with<F>(
+    calculator: &MetricsCalculator,
+    block: &RawBlock,
+    receipts: &[RawReceipt],
+    mut replay_fn: F,
+) -> Result<(BlockMetrics, Vec<ReplayedTxMetrics>)>
+where
+    F: FnMut(&RawTransaction) -> Result<ReplayUsage>,
+{
+    // Heuristic estimates first: they are the fallback for any transaction
+    // that fails to replay, and already carry tx_size/da_size exactly
+    let (mut block_metrics, estimated) = calculator.process_block(block, receipts)?;
+
+    let stats = replay_stats();
+    let mut per_tx = Vec::with_capacity(estimated.len());
+
+    for (tx, mut metrics) in block.transactions.iter().zip(estimated) {
+        stats.record_attempt();
+
+        match replay_fn(tx) {
+            Ok(usage) => {
+                metrics.total_gas = usage.total_gas;
+                metrics.kv_updates = usage.kv_updates;
+                metrics.state_growth = usage.state_growth;
+                // Storage gas follows the measured KV writes; the remainder
+                // of the measured gas is compute
+                metrics.storage_gas = (usage.kv_updates * 20_000).min(usage.total_gas);
+                metrics.compute_gas = usage.total_gas - metrics.storage_gas;
+
+                per_tx.push(ReplayedTxMetrics {
+                    metrics,
+                    provenance: MetricProvenance::Replayed,
+                });
+            }
+            Err(e) => {
+                stats.record_failure();
+                warn!(
+                    "Replay failed for tx {:?}, keeping heuristic estimate: {}",
+                    tx.hash, e
+                );
+                per_tx.push(ReplayedTxMetrics {
+                    metrics,
+                    provenance: MetricProvenance::Estimated,
+                });
+            }
+        }
+    }
+
+    // Re-aggregate block sums from the mixed replayed/estimated tx metrics
+    block_metrics.total_gas = per_tx.iter().map(|t| t.metrics.total_gas).sum();
+    block_metrics.compute_gas = per_tx.iter().map(|t| t.metrics.compute_gas).sum();
+    block_metrics.storage_gas = per_tx.iter().map(|t| t.metrics.storage_gas).sum();
+    block_metrics.kv_updates = per_tx.iter().map(|t| t.metrics.kv_updates).sum();
+    block_metrics.state_growth = per_tx.iter().map(|t| t.metrics.state_growth).sum();
+
+    Ok((block_metrics, per_tx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_tx(gas: u64) -> RawTransaction {
+        RawTransaction {
+            hash: Default::default(),
+            from: Default::default(),
+            to: Some(Default::default()),
+            input: Default::default(),
+            gas,
+            tx_type: 0,
+            nonce: 0,
+            value: Default::default(),
+            gas_price: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            chain_id: None,
+            v: 0,
+            r: Default::default(),
+            s: Default::default(),
+            access_list: vec![],
+            blob_versioned_hashes: vec![],
+            max_fee_per_blob_gas: None,
+        }
+    }
+
+    fn test_block(transactions: Vec<RawTransaction>) -> RawBlock {
+        RawBlock {
+            number: 1,
+            hash: Default::default(),
+            parent_hash: Default::default(),
+            base_fee_per_gas: None,
+            gas_used: 42_000,
+            gas_limit: 30_000_000,
+            timestamp: 0,
+            extra_data: Default::default(),
+            mini_block_count: 0,
+            mini_block_gas: vec![],
+            transactions,
+        }
+    }
+
+    /// Mock RPC serving bytecode for one contract and empty state otherwise
+    struct MockRpc {
+        token: Address,
+        code: alloy_primitives::Bytes,
+    }
+
+    impl crate::rpc::EthRpc for MockRpc {
+        async fn get_latest_block_number(&self) -> Result<u64> {
+            Ok(1)
+        }
+
+        async fn get_block_number(&self) -> Result<u64> {
+            Ok(1)
+        }
+
+        async fn get_block(&self, _block_number: u64) -> Result<Option<RawBlock>> {
+            Ok(None)
+        }
+
+        async fn get_block_receipts(&self, _block_number: u64) -> Result<Vec<RawReceipt>> {
+            Ok(vec![])
+        }
+
+        async fn get_block_with_receipts(
+            &self,
+            _block_number: u64,
+        ) -> Result<Option<(RawBlock, Vec<RawReceipt>)>> {
+            Ok(None)
+        }
+
+        async fn get_code(&self, address: Address) -> Result<alloy_primitives::Bytes> {
+            self.get_code_at(address, "latest").await
+        }
+
+        async fn get_code_at(
+            &self,
+            address: Address,
+            _block_tag: &str,
+        ) -> Result<alloy_primitives::Bytes> {
+            if address == self.token {
+                Ok(self.code.clone())
+            } else {
+                Ok(alloy_primitives::Bytes::new())
+            }
+        }
+
+        async fn get_balance_at(&self, _address: Address, _block_tag: &str) -> Result<U256> {
+            Ok(U256::ZERO)
+        }
+
+        async fn get_nonce_at(&self, _address: Address, _block_tag: &str) -> Result<u64> {
+            Ok(0)
+        }
+
+        async fn get_storage_at_block(
+            &self,
+            _address: Address,
+            _index: U256,
+            _block_tag: &str,
+        ) -> Result<U256> {
+            Ok(U256::ZERO)
+        }
+    }
+
+    /// block_in_place inside the cache DB needs a multi-thread runtime
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_diff_captures_both_balance_slots_of_a_transfer() {
+        // Contract writing two storage slots, the way an ERC-20 transfer
+        // writes the sender's and recipient's balance slots:
+        //   PUSH1 1 PUSH1 5 SSTORE  PUSH1 2 PUSH1 6 SSTORE  STOP
+        let code = alloy_primitives::Bytes::from(vec![
+            0x60, 0x01, 0x60, 0x05, 0x55, 0x60, 0x02, 0x60, 0x06, 0x55, 0x00,
+        ]);
+        let token = Address::repeat_byte(0x22);
+
+        // Explicit scratch config: setting CACHE_DB_PATH would leak into
+        // every other test reading ColdStoreConfig::from_env concurrently
+        let cold = super::super::ColdStoreConfig {
+            path: std::env::temp_dir().join(format!("megaviz-diff-test-{}", std::process::id())),
+            block_cache_mb: 8,
+            write_buffer_mb: 8,
+            compression: "lz4".to_string(),
+        };
+        let db = super::super::SmartCacheDB::with_config(
+            std::sync::Arc::new(MockRpc { token, code }),
+            cold,
+            64,
+        )
+        .unwrap();
+        let replayer = BlockReplayer::new(db);
+
+        let mut tx = test_tx(100_000);
+        tx.from = Address::repeat_byte(0x11);
+        tx.to = Some(token);
+        let block = test_block(vec![tx]);
+
+        let diff = tokio::task::spawn_blocking(move || replayer.replay_tx_with_diff(&block, 0))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let token_diff = diff.accounts.get(&token).expect("token account touched");
+        assert_eq!(token_diff.storage.len(), 2, "both balance slots changed");
+        assert_eq!(
+            token_diff.storage[&U256::from(5)],
+            (U256::ZERO, U256::from(1))
+        );
+        assert_eq!(
+            token_diff.storage[&U256::from(6)],
+            (U256::ZERO, U256::from(2))
+        );
+    }
+
+    #[test]
+    fn test_same_sender_seeds_lowest_nonce() {
+        let sender = Address::repeat_byte(0xaa);
+        let mut first = test_tx(21_000);
+        first.from = sender;
+        first.nonce = 5;
+        let mut second = test_tx(21_000);
+        second.from = sender;
+        second.nonce = 6;
+
+        let starting = sender_starting_nonces(&test_block(vec![first, second])).unwrap();
+        assert_eq!(starting.len(), 1);
+        assert_eq!(starting[&sender], 5);
+    }
+
+    #[test]
+    fn test_out_of_order_nonces_are_a_mismatch() {
+        let sender = Address::repeat_byte(0xaa);
+        let mut first = test_tx(21_000);
+        first.from = sender;
+        first.nonce = 5;
+        // Skips nonce 6: seeding 5 and letting revm increment would execute
+        // this transaction with the wrong nonce
+        let mut second = test_tx(21_000);
+        second.from = sender;
+        second.nonce = 7;
+
+        let err = sender_starting_nonces(&test_block(vec![first, second])).unwrap_err();
+        match err {
+            ReplayError::NonceMismatch { expected, found, .. } => {
+                assert_eq!(expected, 6);
+                assert_eq!(found, 7);
+            }
+        }
+    }
+
+    #[test]
+    fn test_one_failing_tx_falls_back_to_estimate() {
+        let calculator = MetricsCalculator::new();
+
+        let block = RawBlock {
+            number: 1,
+            hash: Default::default(),
+            parent_hash: Default::default(),
+            base_fee_per_gas: None,
+            gas_used: 42_000,
+            gas_limit: 30_000_000,
+            timestamp: 0,
+            extra_data: Default::default(),
+            mini_block_count: 0,
+            mini_block_gas: vec![],
+            transactions: vec![test_tx(21_000), test_tx(21_000)],
+        };
+
+        // First tx replays with measured usage, second fails
+        let mut calls = 0;
+        let (block_metrics, per_tx) = replay_block_with(&calculator, &block, &[], |_tx| {
+            calls += 1;
+            if calls == 1 {
+                Ok(ReplayUsage {
+                    total_gas: 30_000,
+                    kv_updates: 2,
+                    state_growth: 1,
+                })
+            } else {
+                Err(anyhow::anyhow!("unsupported opcode"))
+            }
+        })
+        .unwrap();
+
+        assert_eq!(per_tx.len(), 2);
+        assert_eq!(per_tx[0].provenance, MetricProvenance::Replayed);
+        assert_eq!(per_tx[1].provenance, MetricProvenance::Estimated);
+
+        // Replayed tx carries measured values, estimated tx keeps its
+        // heuristic values, and the block aggregates both
+        assert_eq!(per_tx[0].metrics.total_gas, 30_000);
+        assert_eq!(per_tx[1].metrics.total_gas, 21_000);
+        assert_eq!(block_metrics.total_gas, 51_000);
+        assert_eq!(block_metrics.tx_count, 2);
+    }
+}
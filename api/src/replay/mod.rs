@@ -1,4 +1,9 @@
 // Replay module for 100% accurate metrics extraction
 mod cache_db;
+mod executor;
 
-pub use cache_db::SmartCacheDB;
+pub use cache_db::{CacheStatsSnapshot, ColdStoreConfig, SmartCacheDB};
+pub use executor::{
+    replay_stats, AccountDiff, BlockReplayer, MetricProvenance, ReplayError, ReplayStats,
+    ReplayUsage, ReplayedTxMetrics, StateDiff,
+};
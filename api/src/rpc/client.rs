@@ -1,18 +1,28 @@
 use alloy_primitives::{Address, Bytes, B256, U256};
+use super::rlp::{rlp_length_prefix_size, rlp_u128_size, rlp_u256_size, rlp_uint_size};
 use anyhow::{Context, Result};
 use reqwest::Client;
 use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::Instrument;
 
 /// Raw block data from MegaETH RPC
 #[derive(Debug, Clone)]
 pub struct RawBlock {
     pub number: u64,
     pub hash: B256,
+    /// Hash of the parent block, for walking the chain during reorg checks
+    pub parent_hash: B256,
+    /// Base fee per gas in wei; None when the RPC omits it
+    pub base_fee_per_gas: Option<u128>,
     pub gas_used: u64,
     pub gas_limit: u64,
     pub timestamp: u64,
     pub extra_data: Bytes,
     pub mini_block_count: u64,  // Direct from RPC
+    pub mini_block_gas: Vec<u64>,  // Gas used per mini-block, empty if RPC omits structure
     pub transactions: Vec<RawTransaction>,
 }
 
@@ -35,11 +45,129 @@ pub struct RawTransaction {
     pub r: U256,
     pub s: U256,
     pub access_list: Vec<(Address, Vec<B256>)>,
+    /// EIP-4844 blob commitments (empty for non-blob transactions)
+    pub blob_versioned_hashes: Vec<B256>,
+    /// EIP-4844 blob gas fee cap
+    pub max_fee_per_blob_gas: Option<u128>,
 }
 
 impl RawTransaction {
+    /// Re-encode the transaction to its EIP-2718 wire bytes
+    ///
+    /// Supported for legacy, EIP-2930, EIP-1559 and EIP-4844 transactions;
+    /// returns None for deposits and unknown types, which fall back to the
+    /// estimate. Blob transactions encode in their canonical (block) form,
+    /// versioned hashes only -- blob payloads travel the blob DA channel,
+    /// not calldata.
+    pub fn encode_2718(&self) -> Option<Vec<u8>> {
+        use alloy::consensus::{
+            Signed, TxEip1559, TxEip2930, TxEip4844, TxEip4844Variant, TxEnvelope, TxLegacy,
+        };
+        use alloy::eips::eip2718::Encodable2718;
+        use alloy::eips::eip2930::{AccessList, AccessListItem};
+        use alloy_primitives::{Signature, TxKind};
+
+        let to = match self.to {
+            Some(addr) => TxKind::Call(addr),
+            None => TxKind::Create,
+        };
+        let access_list = AccessList(
+            self.access_list
+                .iter()
+                .map(|(address, keys)| AccessListItem {
+                    address: *address,
+                    storage_keys: keys.clone(),
+                })
+                .collect(),
+        );
+
+        // y-parity: typed txs carry 0/1 directly, legacy folds it into v
+        let parity = match self.tx_type {
+            0 if self.v >= 35 => (self.v - 35) % 2 == 1,
+            0 => self.v == 28,
+            _ => self.v == 1,
+        };
+        let signature = Signature::new(self.r, self.s, parity);
+
+        let envelope = match self.tx_type {
+            0 => {
+                let tx = TxLegacy {
+                    chain_id: self.chain_id,
+                    nonce: self.nonce,
+                    gas_price: self.gas_price.unwrap_or(0),
+                    gas_limit: self.gas,
+                    to,
+                    value: self.value,
+                    input: self.input.clone(),
+                };
+                TxEnvelope::Legacy(Signed::new_unchecked(tx, signature, self.hash))
+            }
+            1 => {
+                let tx = TxEip2930 {
+                    chain_id: self.chain_id.unwrap_or(0),
+                    nonce: self.nonce,
+                    gas_price: self.gas_price.unwrap_or(0),
+                    gas_limit: self.gas,
+                    to,
+                    value: self.value,
+                    access_list,
+                    input: self.input.clone(),
+                };
+                TxEnvelope::Eip2930(Signed::new_unchecked(tx, signature, self.hash))
+            }
+            2 => {
+                let tx = TxEip1559 {
+                    chain_id: self.chain_id.unwrap_or(0),
+                    nonce: self.nonce,
+                    gas_limit: self.gas,
+                    max_fee_per_gas: self.max_fee_per_gas.unwrap_or(0),
+                    max_priority_fee_per_gas: self.max_priority_fee_per_gas.unwrap_or(0),
+                    to,
+                    value: self.value,
+                    access_list,
+                    input: self.input.clone(),
+                };
+                TxEnvelope::Eip1559(Signed::new_unchecked(tx, signature, self.hash))
+            }
+            3 => {
+                let tx = TxEip4844 {
+                    chain_id: self.chain_id.unwrap_or(0),
+                    nonce: self.nonce,
+                    gas_limit: self.gas,
+                    max_fee_per_gas: self.max_fee_per_gas.unwrap_or(0),
+                    max_priority_fee_per_gas: self.max_priority_fee_per_gas.unwrap_or(0),
+                    // Blob transactions cannot create contracts
+                    to: self.to?,
+                    value: self.value,
+                    access_list,
+                    blob_versioned_hashes: self.blob_versioned_hashes.clone(),
+                    max_fee_per_blob_gas: self.max_fee_per_blob_gas.unwrap_or(0),
+                    input: self.input.clone(),
+                };
+                TxEnvelope::Eip4844(Signed::new_unchecked(
+                    TxEip4844Variant::TxEip4844(tx),
+                    signature,
+                    self.hash,
+                ))
+            }
+            _ => return None,
+        };
+
+        Some(envelope.encoded_2718())
+    }
+
     /// Calculate EIP-2718 encoded size
+    ///
+    /// Exact for transaction types we can re-encode; estimated otherwise.
     pub fn encoded_size(&self) -> u64 {
+        if let Some(encoded) = self.encode_2718() {
+            return encoded.len() as u64;
+        }
+        self.estimated_size()
+    }
+
+    /// Estimate the encoded size for types we cannot re-encode (deposits)
+    fn estimated_size(&self) -> u64 {
         // Base size: signature (65) + nonce (1-9) + gas (1-9) + to (21) + value (1-32)
         let mut size: u64 = 0;
 
@@ -81,6 +209,16 @@ impl RawTransaction {
                 size += self.access_list_size();
                 size += 1; // tx type byte
             }
+            3 => {
+                // EIP-4844: 1559 fee fields + blob fee cap + versioned hashes
+                size += rlp_u128_size(self.max_priority_fee_per_gas.unwrap_or(0));
+                size += rlp_u128_size(self.max_fee_per_gas.unwrap_or(0));
+                size += self.access_list_size();
+                size += rlp_u128_size(self.max_fee_per_blob_gas.unwrap_or(0));
+                let hashes_len = self.blob_versioned_hashes.len() as u64 * 33;
+                size += rlp_length_prefix_size(hashes_len) + hashes_len;
+                size += 1; // tx type byte
+            }
             126 => {
                 // Deposit tx (L1->L2)
                 size += 1; // tx type byte
@@ -111,7 +249,7 @@ impl RawTransaction {
         }
 
         let mut size: u64 = 0;
-        for (addr, keys) in &self.access_list {
+        for (_addr, keys) in &self.access_list {
             size += 21; // Address
             size += 1 + (keys.len() as u64 * 33); // Keys list
         }
@@ -119,7 +257,14 @@ impl RawTransaction {
     }
 
     /// Get bytes for DA size calculation
+    ///
+    /// The true EIP-2718 wire bytes where re-encoding is supported, so FastLZ
+    /// compresses what actually gets posted; otherwise an approximation.
     pub fn to_bytes_for_da(&self) -> Vec<u8> {
+        if let Some(encoded) = self.encode_2718() {
+            return encoded;
+        }
+
         // Reconstruct approximate transaction bytes for FastLZ compression
         let mut bytes = Vec::with_capacity(self.encoded_size() as usize);
 
@@ -144,52 +289,6 @@ impl RawTransaction {
     }
 }
 
-/// Calculate RLP size for a u64
-fn rlp_uint_size(val: u64) -> u64 {
-    if val == 0 {
-        1
-    } else if val < 128 {
-        1
-    } else {
-        1 + ((64 - val.leading_zeros()) as u64 + 7) / 8
-    }
-}
-
-/// Calculate RLP size for a u128
-fn rlp_u128_size(val: u128) -> u64 {
-    if val == 0 {
-        1
-    } else if val < 128 {
-        1
-    } else {
-        1 + ((128 - val.leading_zeros()) as u64 + 7) / 8
-    }
-}
-
-/// Calculate RLP size for a U256
-fn rlp_u256_size(val: U256) -> u64 {
-    if val.is_zero() {
-        1
-    } else {
-        let bytes = val.to_be_bytes::<32>();
-        let leading_zeros = bytes.iter().take_while(|&&b| b == 0).count();
-        let significant_bytes = 32 - leading_zeros;
-        if significant_bytes == 1 && bytes[31] < 128 {
-            1
-        } else {
-            1 + significant_bytes as u64
-        }
-    }
-}
-
-/// Calculate RLP length prefix size
-fn rlp_length_prefix_size(len: u64) -> u64 {
-    if len < 56 {
-        1
-    } else {
-        1 + ((64 - len.leading_zeros()) as u64 + 7) / 8
-    }
-}
 
 /// Receipt data from MegaETH RPC
 #[derive(Debug, Clone)]
@@ -202,11 +301,132 @@ pub struct RawReceipt {
     pub effective_gas_price: Option<u128>,
 }
 
+/// A log entry returned by eth_getLogs
+#[derive(Debug, Clone)]
+pub struct RawLog {
+    /// Contract that emitted the log
+    pub address: Address,
+    /// Indexed topics; topics[0] is the event signature hash
+    pub topics: Vec<B256>,
+    /// Non-indexed event data
+    pub data: Bytes,
+    /// Block the log was emitted in
+    pub block_number: u64,
+}
+
+/// Parsed `eth_feeHistory` response
+#[derive(Debug, Clone)]
+pub struct FeeHistory {
+    /// First block covered by the arrays below
+    pub oldest_block: u64,
+    /// Per-block base fee in wei; one more entry than blocks requested (the
+    /// last entry is the projected base fee for the block after the newest)
+    pub base_fee_per_gas: Vec<u128>,
+    /// Per-block gas used / gas limit (0.0-1.0)
+    pub gas_used_ratio: Vec<f64>,
+    /// Per-block priority-fee percentiles in wei; one inner vec per block,
+    /// aligned with the requested percentiles. Empty when none were requested.
+    pub reward: Vec<Vec<u128>>,
+}
+
+/// Consecutive failures that trip the circuit breaker open
+const CIRCUIT_FAILURE_THRESHOLD: u32 = 5;
+/// How long an open circuit fails fast before admitting a trial request
+pub(crate) const CIRCUIT_COOLDOWN: Duration = Duration::from_secs(5);
+
+/// Error returned when the circuit breaker is failing fast
+///
+/// Distinct from ordinary RPC failures so callers can back off instead of
+/// retrying; check with `err.downcast_ref::<CircuitOpen>()`.
+#[derive(Debug, thiserror::Error)]
+#[error("RPC circuit open; failing fast until the cooldown elapses")]
+pub struct CircuitOpen;
+
+/// Trips after repeated RPC failures so a dead endpoint isn't hammered
+///
+/// Closed under normal operation. After [`CIRCUIT_FAILURE_THRESHOLD`]
+/// consecutive failures it opens and every call fails fast with
+/// [`CircuitOpen`] for [`CIRCUIT_COOLDOWN`]; then one trial request per
+/// cooldown is admitted until a success closes the circuit again.
+#[derive(Clone)]
+struct CircuitBreaker {
+    inner: Arc<Mutex<BreakerInner>>,
+    threshold: u32,
+    cooldown: Duration,
+}
+
+struct BreakerInner {
+    consecutive_failures: u32,
+    /// When the circuit last opened (or last admitted a trial); None while
+    /// closed
+    opened_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(BreakerInner {
+                consecutive_failures: 0,
+                opened_at: None,
+            })),
+            threshold,
+            cooldown,
+        }
+    }
+
+    /// Whether a request may go out right now
+    ///
+    /// When the circuit is open and the cooldown has elapsed this admits the
+    /// caller as the trial and restarts the cooldown, so concurrent callers
+    /// keep failing fast until the trial resolves.
+    fn try_acquire(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.opened_at {
+            None => true,
+            Some(at) if at.elapsed() >= self.cooldown => {
+                inner.opened_at = Some(Instant::now());
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+    }
+
+    fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        if inner.consecutive_failures >= self.threshold {
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Current state for health reporting
+    fn state(&self) -> &'static str {
+        let inner = self.inner.lock().unwrap();
+        match inner.opened_at {
+            None => "closed",
+            Some(at) if at.elapsed() >= self.cooldown => "half_open",
+            Some(_) => "open",
+        }
+    }
+}
+
 /// Client for interacting with MegaETH RPC using raw JSON-RPC
 #[derive(Clone)]
 pub struct MegaEthClient {
     client: Client,
     rpc_url: String,
+    /// Monotonic JSON-RPC request id, shared across clones so every call
+    /// this process makes carries a unique id
+    next_id: Arc<AtomicU64>,
+    /// Shared across clones: every user of this client (and its clones)
+    /// sees the same view of endpoint health
+    circuit: CircuitBreaker,
 }
 
 impl MegaEthClient {
@@ -214,19 +434,53 @@ impl MegaEthClient {
         Ok(Self {
             client: Client::new(),
             rpc_url: rpc_url.to_string(),
+            next_id: Arc::new(AtomicU64::new(1)),
+            circuit: CircuitBreaker::new(CIRCUIT_FAILURE_THRESHOLD, CIRCUIT_COOLDOWN),
         })
     }
 
+    /// Current circuit-breaker state: "closed", "open" or "half_open"
+    pub fn circuit_state(&self) -> &'static str {
+        self.circuit.state()
+    }
+
+    /// The JSON-RPC endpoint this client talks to
+    pub fn rpc_url(&self) -> &str {
+        &self.rpc_url
+    }
+
+    /// Shorten the circuit cooldown so tests don't wait out the real one
+    #[cfg(test)]
+    fn with_circuit_cooldown(mut self, cooldown: Duration) -> Self {
+        self.circuit.cooldown = cooldown;
+        self
+    }
+
+    /// Allocate a unique JSON-RPC request id
+    fn next_request_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
     async fn rpc_call(&self, method: &str, params: Value) -> Result<Value> {
         const MAX_RETRIES: u32 = 3;
+
+        // Fail fast instead of adding retries on top of a known-dead endpoint
+        if !self.circuit.try_acquire() {
+            return Err(anyhow::Error::new(CircuitOpen));
+        }
+
         let mut last_error = None;
 
         for attempt in 0..MAX_RETRIES {
             let result = self.rpc_call_once(method, params.clone()).await;
 
             match result {
-                Ok(value) => return Ok(value),
+                Ok(value) => {
+                    self.circuit.record_success();
+                    return Ok(value);
+                }
                 Err(e) => {
+                    self.circuit.record_failure();
                     last_error = Some(e);
 
                     // Only retry on transient errors (network, timeout, 5xx)
@@ -243,6 +497,19 @@ impl MegaEthClient {
     }
 
     async fn rpc_call_once(&self, method: &str, params: Value) -> Result<Value> {
+        let request_id = self.next_request_id();
+        let span = tracing::debug_span!("rpc_call", method, id = request_id);
+
+        let started = std::time::Instant::now();
+        let result = self
+            .rpc_call_once_inner(method, params, request_id)
+            .instrument(span)
+            .await;
+        crate::telemetry::telemetry().record_rpc_call(method, started.elapsed(), result.is_ok());
+        result
+    }
+
+    async fn rpc_call_once_inner(&self, method: &str, params: Value, request_id: u64) -> Result<Value> {
         let response = self
             .client
             .post(&self.rpc_url)
@@ -251,7 +518,7 @@ impl MegaEthClient {
                 "jsonrpc": "2.0",
                 "method": method,
                 "params": params,
-                "id": 1
+                "id": request_id
             }))
             .send()
             .await
@@ -275,9 +542,213 @@ impl MegaEthClient {
             anyhow::bail!("RPC error for method {}: {}", method, error);
         }
 
+        // A response echoing a different id means this reply belongs to some
+        // other request; treat it as a protocol error rather than guessing
+        let echoed = resp.get("id").and_then(|v| v.as_u64());
+        if echoed != Some(request_id) {
+            anyhow::bail!(
+                "RPC response id mismatch for method {}: sent {}, got {:?}",
+                method,
+                request_id,
+                echoed
+            );
+        }
+
         Ok(resp["result"].clone())
     }
 
+    /// Issue several JSON-RPC calls in one HTTP request, with the same retry
+    /// policy as `rpc_call`
+    ///
+    /// Results come back in the same order as `calls`, regardless of the
+    /// order the server answered in.
+    async fn rpc_batch(&self, calls: Vec<(&str, Value)>) -> Result<Vec<Value>> {
+        const MAX_RETRIES: u32 = 3;
+
+        if !self.circuit.try_acquire() {
+            return Err(anyhow::Error::new(CircuitOpen));
+        }
+
+        let mut last_error = None;
+
+        for attempt in 0..MAX_RETRIES {
+            match self.rpc_batch_once(&calls).await {
+                Ok(values) => {
+                    self.circuit.record_success();
+                    return Ok(values);
+                }
+                Err(e) => {
+                    self.circuit.record_failure();
+                    last_error = Some(e);
+                    if attempt < MAX_RETRIES - 1 {
+                        let delay = std::time::Duration::from_millis(100 * (1 << attempt));
+                        tokio::time::sleep(delay).await;
+                    }
+                }
+            }
+        }
+
+        Err(last_error.unwrap())
+    }
+
+    async fn rpc_batch_once(&self, calls: &[(&str, Value)]) -> Result<Vec<Value>> {
+        let first_id = self.next_id.fetch_add(calls.len() as u64, Ordering::Relaxed);
+        let span = tracing::debug_span!("rpc_batch", calls = calls.len(), first_id);
+
+        let started = std::time::Instant::now();
+        let result = self
+            .rpc_batch_once_inner(calls, first_id)
+            .instrument(span)
+            .await
+            .and_then(|entries| entries.into_iter().collect());
+
+        // Each method in the batch shares the batch round-trip latency
+        let elapsed = started.elapsed();
+        for (method, _) in calls {
+            crate::telemetry::telemetry().record_rpc_call(method, elapsed, result.is_ok());
+        }
+
+        result
+    }
+
+    /// Like [`rpc_batch`](Self::rpc_batch), but entry-level JSON-RPC errors
+    /// (e.g. a reverted `eth_call`) come back as the `Err` in that entry's
+    /// slot instead of failing the whole batch; only transport-level
+    /// failures retry and count against the circuit breaker
+    async fn rpc_batch_lenient(&self, calls: Vec<(&str, Value)>) -> Result<Vec<Result<Value>>> {
+        const MAX_RETRIES: u32 = 3;
+
+        if !self.circuit.try_acquire() {
+            return Err(anyhow::Error::new(CircuitOpen));
+        }
+
+        let mut last_error = None;
+
+        for attempt in 0..MAX_RETRIES {
+            match self.rpc_batch_lenient_once(&calls).await {
+                Ok(entries) => {
+                    self.circuit.record_success();
+                    return Ok(entries);
+                }
+                Err(e) => {
+                    self.circuit.record_failure();
+                    last_error = Some(e);
+                    if attempt < MAX_RETRIES - 1 {
+                        let delay = std::time::Duration::from_millis(100 * (1 << attempt));
+                        tokio::time::sleep(delay).await;
+                    }
+                }
+            }
+        }
+
+        Err(last_error.unwrap())
+    }
+
+    async fn rpc_batch_lenient_once(&self, calls: &[(&str, Value)]) -> Result<Vec<Result<Value>>> {
+        let first_id = self.next_id.fetch_add(calls.len() as u64, Ordering::Relaxed);
+        let span = tracing::debug_span!("rpc_batch", calls = calls.len(), first_id);
+
+        let started = std::time::Instant::now();
+        let result = self.rpc_batch_once_inner(calls, first_id).instrument(span).await;
+
+        let elapsed = started.elapsed();
+        for (method, _) in calls {
+            crate::telemetry::telemetry().record_rpc_call(method, elapsed, result.is_ok());
+        }
+
+        result
+    }
+
+    /// Shared transport layer for both batch flavors: entry-level errors
+    /// stay in their slot, shape problems (bad HTTP status, wrong entry
+    /// count, unknown ids) fail the whole batch
+    async fn rpc_batch_once_inner(
+        &self,
+        calls: &[(&str, Value)],
+        first_id: u64,
+    ) -> Result<Vec<Result<Value>>> {
+        let batch: Vec<Value> = calls
+            .iter()
+            .enumerate()
+            .map(|(i, (method, params))| {
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": first_id + i as u64
+                })
+            })
+            .collect();
+
+        let response = self
+            .client
+            .post(&self.rpc_url)
+            .timeout(std::time::Duration::from_secs(10))
+            .json(&batch)
+            .send()
+            .await
+            .context("RPC batch request failed")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!(
+                "RPC HTTP error {}: {} for batch request",
+                status.as_u16(),
+                status.canonical_reason().unwrap_or("")
+            );
+        }
+
+        let body_text = response
+            .text()
+            .await
+            .context("Failed to read batch response body")?;
+        let resp: Value = serde_json::from_str(&body_text).context(format!(
+            "Failed to parse batch JSON response. Body: {}",
+            &body_text[..body_text.len().min(500)]
+        ))?;
+
+        let entries = resp
+            .as_array()
+            .context("Batch response is not a JSON array")?;
+        if entries.len() != calls.len() {
+            anyhow::bail!(
+                "Batch response has {} entries, expected {}",
+                entries.len(),
+                calls.len()
+            );
+        }
+
+        // Servers may answer out of order: re-order by id
+        let mut results: Vec<Option<Result<Value>>> = (0..calls.len()).map(|_| None).collect();
+        for entry in entries {
+            let id = entry
+                .get("id")
+                .and_then(|v| v.as_u64())
+                .context("Batch response entry missing id")?;
+            let index = id
+                .checked_sub(first_id)
+                .filter(|&i| i < calls.len() as u64)
+                .with_context(|| format!("Batch response entry has unknown id {}", id))?
+                as usize;
+            results[index] = Some(match entry.get("error") {
+                Some(error) => Err(anyhow::anyhow!(
+                    "RPC error for method {}: {}",
+                    calls[index].0,
+                    error
+                )),
+                None => Ok(entry["result"].clone()),
+            });
+        }
+
+        results
+            .into_iter()
+            .enumerate()
+            .map(|(i, r)| {
+                r.context(format!("Batch response missing entry for id {}", first_id + i as u64))
+            })
+            .collect()
+    }
+
     pub async fn get_latest_block_number(&self) -> Result<u64> {
         let result = self.rpc_call("eth_blockNumber", json!([])).await?;
         let hex = result.as_str().context("Invalid block number")?;
@@ -288,74 +759,69 @@ impl MegaEthClient {
         let block_hex = format!("0x{:x}", block_number);
         let result = self.rpc_call("eth_getBlockByNumber", json!([block_hex, true])).await?;
 
-        if result.is_null() {
-            return Ok(None);
-        }
+        parse_raw_block(&result)
+    }
 
-        let block = result.as_object().context("Block response is not a JSON object")?;
-
-        let number = parse_hex_u64(block.get("number")).context("Failed to parse 'number' field")?;
-        let hash = parse_b256(block.get("hash")).context("Failed to parse 'hash' field")?;
-        let gas_used = parse_hex_u64(block.get("gasUsed")).context("Failed to parse 'gasUsed' field")?;
-        let gas_limit = parse_hex_u64(block.get("gasLimit")).context("Failed to parse 'gasLimit' field")?;
-        let timestamp = parse_hex_u64(block.get("timestamp")).context("Failed to parse 'timestamp' field")?;
-        
-        // Parse extraData (for backwards compatibility)
-        let extra_data = block
-            .get("extraData")
-            .and_then(|v| v.as_str())
-            .map(|s| {
-                let s = s.strip_prefix("0x").unwrap_or(s);
-                Bytes::from(hex::decode(s).unwrap_or_default())
-            })
-            .unwrap_or_default();
-
-        // Parse miniBlockCount directly from RPC (MegaETH-specific field)
-        let mini_block_count = block
-            .get("miniBlockCount")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(1);  // Default to 1 if not present
-
-        let txs = block
-            .get("transactions")
-            .and_then(|t| t.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|tx| parse_transaction(tx).ok())
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        Ok(Some(RawBlock {
-            number,
-            hash,
-            gas_used,
-            gas_limit,
-            timestamp,
-            extra_data,
-            mini_block_count,
-            transactions: txs,
-        }))
+    /// Fetch a block by its hash, with full transaction objects
+    ///
+    /// Used for walking `parentHash` links back along the canonical chain
+    /// when checking for reorgs; returns None for unknown hashes.
+    pub async fn get_block_by_hash(&self, hash: B256) -> Result<Option<RawBlock>> {
+        let hash_hex = format!("{:?}", hash);
+        let result = self.rpc_call("eth_getBlockByHash", json!([hash_hex, true])).await?;
+
+        parse_raw_block(&result)
     }
 
     pub async fn get_block_receipts(&self, block_number: u64) -> Result<Vec<RawReceipt>> {
         let block_hex = format!("0x{:x}", block_number);
         let result = self.rpc_call("eth_getBlockReceipts", json!([block_hex])).await?;
 
-        if result.is_null() {
-            return Ok(vec![]);
-        }
+        Ok(parse_raw_receipts(&result))
+    }
 
-        let receipts = result
-            .as_array()
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|r| parse_receipt(r).ok())
-                    .collect()
-            })
-            .unwrap_or_default();
+    /// Fetch a block and its receipts in a single JSON-RPC batch
+    ///
+    /// Halves the per-block request count compared to calling `get_block`
+    /// and `get_block_receipts` separately.
+    pub async fn get_block_with_receipts(
+        &self,
+        block_number: u64,
+    ) -> Result<Option<(RawBlock, Vec<RawReceipt>)>> {
+        let block_hex = format!("0x{:x}", block_number);
+        let results = self
+            .rpc_batch(vec![
+                ("eth_getBlockByNumber", json!([block_hex, true])),
+                ("eth_getBlockReceipts", json!([block_hex])),
+            ])
+            .await?;
+
+        let Some(block) = parse_raw_block(&results[0])? else {
+            return Ok(None);
+        };
+        let receipts = parse_raw_receipts(&results[1]);
+
+        Ok(Some((block, receipts)))
+    }
 
-        Ok(receipts)
+    /// Get logs emitted by a contract over a block range
+    pub async fn get_logs(
+        &self,
+        address: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<RawLog>> {
+        let params = json!([{
+            "address": format!("{:?}", address),
+            "fromBlock": format!("0x{:x}", from_block),
+            "toBlock": format!("0x{:x}", to_block),
+        }]);
+        let result = self.rpc_call("eth_getLogs", params).await?;
+
+        let logs = result
+            .as_array()
+            .context("eth_getLogs response is not an array")?;
+        logs.iter().map(parse_log).collect()
     }
 
     pub async fn get_chain_id(&self) -> Result<u64> {
@@ -364,15 +830,42 @@ impl MegaEthClient {
         Ok(u64::from_str_radix(hex.trim_start_matches("0x"), 16)?)
     }
 
+    /// Fetch base-fee and priority-fee history via `eth_feeHistory`
+    ///
+    /// `newest_block: None` means "latest". `reward_percentiles` asks the
+    /// node for per-block priority-fee percentiles (e.g. `&[50.0]`).
+    pub async fn get_fee_history(
+        &self,
+        block_count: u64,
+        newest_block: Option<u64>,
+        reward_percentiles: &[f64],
+    ) -> Result<FeeHistory> {
+        let newest = match newest_block {
+            Some(n) => format!("0x{:x}", n),
+            None => "latest".to_string(),
+        };
+        let params = json!([format!("0x{:x}", block_count), newest, reward_percentiles]);
+        let result = self.rpc_call("eth_feeHistory", params).await?;
+        parse_fee_history(&result)
+    }
+
     /// Get the latest block number
     pub async fn get_block_number(&self) -> Result<u64> {
         self.get_latest_block_number().await
     }
 
-    /// Get contract code at an address
+    /// Get contract code at an address (latest state)
     pub async fn get_code(&self, address: Address) -> Result<Bytes> {
+        self.get_code_at(address, "latest").await
+    }
+
+    /// Get contract code at an address at a specific block tag
+    ///
+    /// `block_tag` is a raw JSON-RPC tag: "latest" or a hex block number
+    /// like "0x1a2b"
+    pub async fn get_code_at(&self, address: Address, block_tag: &str) -> Result<Bytes> {
         let addr_hex = format!("{:?}", address);
-        let result = self.rpc_call("eth_getCode", json!([addr_hex, "latest"])).await?;
+        let result = self.rpc_call("eth_getCode", json!([addr_hex, block_tag])).await?;
 
         let hex = result.as_str().context("Invalid code response")?;
         let hex = hex.trim_start_matches("0x");
@@ -385,30 +878,50 @@ impl MegaEthClient {
         Ok(Bytes::from(bytes))
     }
 
-    /// Get account balance
+    /// Get account balance (latest state)
     pub async fn get_balance(&self, address: Address) -> Result<U256> {
+        self.get_balance_at(address, "latest").await
+    }
+
+    /// Get account balance at a specific block tag
+    pub async fn get_balance_at(&self, address: Address, block_tag: &str) -> Result<U256> {
         let addr_hex = format!("{:?}", address);
-        let result = self.rpc_call("eth_getBalance", json!([addr_hex, "latest"])).await?;
+        let result = self.rpc_call("eth_getBalance", json!([addr_hex, block_tag])).await?;
 
         let hex = result.as_str().context("Invalid balance response")?;
         hex.parse().context("Failed to parse balance")
     }
 
-    /// Get account nonce
+    /// Get account nonce (latest state)
     pub async fn get_nonce(&self, address: Address) -> Result<u64> {
+        self.get_nonce_at(address, "latest").await
+    }
+
+    /// Get account nonce at a specific block tag
+    pub async fn get_nonce_at(&self, address: Address, block_tag: &str) -> Result<u64> {
         let addr_hex = format!("{:?}", address);
-        let result = self.rpc_call("eth_getTransactionCount", json!([addr_hex, "latest"])).await?;
+        let result = self.rpc_call("eth_getTransactionCount", json!([addr_hex, block_tag])).await?;
 
         let hex = result.as_str().context("Invalid nonce response")?;
         u64::from_str_radix(hex.trim_start_matches("0x"), 16)
             .context("Failed to parse nonce")
     }
 
-    /// Get storage value at a specific slot
+    /// Get storage value at a specific slot (latest state)
     pub async fn get_storage_at(&self, address: Address, index: U256) -> Result<U256> {
+        self.get_storage_at_block(address, index, "latest").await
+    }
+
+    /// Get storage value at a specific slot at a specific block tag
+    pub async fn get_storage_at_block(
+        &self,
+        address: Address,
+        index: U256,
+        block_tag: &str,
+    ) -> Result<U256> {
         let addr_hex = format!("{:?}", address);
         let index_hex = format!("{:#x}", index);
-        let result = self.rpc_call("eth_getStorageAt", json!([addr_hex, index_hex, "latest"])).await?;
+        let result = self.rpc_call("eth_getStorageAt", json!([addr_hex, index_hex, block_tag])).await?;
 
         let hex = result.as_str().context("Invalid storage response")?;
         hex.parse().context("Failed to parse storage value")
@@ -422,47 +935,100 @@ impl MegaEthClient {
             "data": data
         }, "latest"])).await?;
 
-        let hex = result.as_str().context("Invalid eth_call response")?;
-        let hex = hex.trim_start_matches("0x");
+        decode_call_result(&result)
+    }
 
-        if hex.is_empty() {
-            return Ok(Bytes::new());
+    /// Issue several read-only contract calls in one JSON-RPC batch
+    ///
+    /// Results come back in call order with per-call failures in place: a
+    /// reverted call is an `Err` in its slot, not a failure of the whole
+    /// batch. Interface detection probes several selectors per contract,
+    /// so one round trip here replaces up to five.
+    pub async fn eth_call_batch(&self, calls: &[(Address, String)]) -> Result<Vec<Result<Bytes>>> {
+        if calls.is_empty() {
+            return Ok(Vec::new());
         }
 
-        let bytes = hex::decode(hex).context("Failed to decode eth_call result")?;
-        Ok(Bytes::from(bytes))
+        let specs: Vec<(&str, Value)> = calls
+            .iter()
+            .map(|(to, data)| {
+                ("eth_call", json!([{
+                    "to": format!("{:?}", to),
+                    "data": data
+                }, "latest"]))
+            })
+            .collect();
+
+        let entries = self.rpc_batch_lenient(specs).await?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| entry.and_then(|result| decode_call_result(&result)))
+            .collect())
     }
 }
 
-fn parse_hex_u64(val: Option<&Value>) -> Result<u64> {
-    let hex = val.and_then(|v| v.as_str())
-        .context("Required field is missing or not a string")?;
-    u64::from_str_radix(hex.trim_start_matches("0x"), 16)
-        .context(format!("Failed to parse hex u64 value: {}", hex))
+/// Decode an `eth_call` result field (hex string) into bytes; an empty
+/// `0x` result is empty bytes, not an error
+fn decode_call_result(result: &Value) -> Result<Bytes> {
+    let hex = result.as_str().context("Invalid eth_call response")?;
+    let hex = hex.trim_start_matches("0x");
+
+    if hex.is_empty() {
+        return Ok(Bytes::new());
+    }
+
+    let bytes = hex::decode(hex).context("Failed to decode eth_call result")?;
+    Ok(Bytes::from(bytes))
 }
 
-fn parse_hex_u64_opt(val: Option<&Value>) -> u64 {
-    val.and_then(|v| v.as_str())
-        .and_then(|hex| u64::from_str_radix(hex.trim_start_matches("0x"), 16).ok())
-        .unwrap_or(0)
+/// Required u64 that providers encode as either a hex string or a bare
+/// JSON number
+fn parse_hex_u64(val: Option<&Value>) -> Result<u64> {
+    match val {
+        Some(Value::String(hex)) => u64::from_str_radix(hex.trim_start_matches("0x"), 16)
+            .context(format!("Failed to parse hex u64 value: {}", hex)),
+        Some(Value::Number(n)) => n
+            .as_u64()
+            .context("Numeric u64 field is negative or fractional"),
+        _ => anyhow::bail!("Required field is missing or not a string or number"),
+    }
 }
 
+/// Optional u128, hex string or JSON number; absent and null stay None
 fn parse_hex_u128(val: Option<&Value>) -> Option<u128> {
-    val.and_then(|v| v.as_str())
-        .and_then(|hex| u128::from_str_radix(hex.trim_start_matches("0x"), 16).ok())
+    match val {
+        Some(Value::String(hex)) => u128::from_str_radix(hex.trim_start_matches("0x"), 16).ok(),
+        Some(Value::Number(n)) => n.as_u64().map(u128::from),
+        _ => None,
+    }
 }
 
+/// Required 32-byte hash; a missing field is an error, not a zero hash
+/// (callers with genuinely optional hashes default at the call site)
 fn parse_b256(val: Option<&Value>) -> Result<B256> {
     let hex = val
         .and_then(|v| v.as_str())
-        .unwrap_or("0x0000000000000000000000000000000000000000000000000000000000000000");
+        .context("Required hash field is missing or not a string")?;
     hex.parse().context("Invalid B256")
 }
 
-fn parse_u256(val: Option<&Value>) -> U256 {
-    val.and_then(|v| v.as_str())
-        .and_then(|hex| hex.parse().ok())
-        .unwrap_or(U256::ZERO)
+/// U256 from a hex string or JSON number
+///
+/// Absent and null fields are zero (deposits carry no signature, so `r`
+/// and `s` are legitimately missing); a present-but-malformed value is an
+/// error rather than a silently wrong metric.
+fn parse_u256(val: Option<&Value>) -> Result<U256> {
+    match val {
+        None | Some(Value::Null) => Ok(U256::ZERO),
+        Some(Value::String(hex)) => hex
+            .parse()
+            .context(format!("Failed to parse U256 value: {}", hex)),
+        Some(Value::Number(n)) => n
+            .as_u64()
+            .map(U256::from)
+            .context("Numeric U256 field is negative or fractional"),
+        Some(other) => anyhow::bail!("U256 field has unexpected type: {}", other),
+    }
 }
 
 fn parse_address(val: Option<&Value>) -> Result<Option<Address>> {
@@ -474,6 +1040,115 @@ fn parse_address(val: Option<&Value>) -> Result<Option<Address>> {
     }
 }
 
+/// Parse an `eth_getBlockByNumber` result into a RawBlock
+fn parse_raw_block(result: &Value) -> Result<Option<RawBlock>> {
+    if result.is_null() {
+        return Ok(None);
+    }
+
+    let block = result.as_object().context("Block response is not a JSON object")?;
+
+    let number = parse_hex_u64(block.get("number")).context("Failed to parse 'number' field")?;
+    let hash = parse_b256(block.get("hash")).context("Failed to parse 'hash' field")?;
+    // Genesis and test stubs may omit these; default rather than fail
+    let parent_hash = parse_b256(block.get("parentHash")).unwrap_or(B256::ZERO);
+    let base_fee_per_gas = parse_hex_u128(block.get("baseFeePerGas"));
+    let gas_used = parse_hex_u64(block.get("gasUsed")).context("Failed to parse 'gasUsed' field")?;
+    let gas_limit = parse_hex_u64(block.get("gasLimit")).context("Failed to parse 'gasLimit' field")?;
+    let timestamp = parse_hex_u64(block.get("timestamp")).context("Failed to parse 'timestamp' field")?;
+
+    // Parse extraData (for backwards compatibility)
+    let extra_data = block
+        .get("extraData")
+        .and_then(|v| v.as_str())
+        .map(|s| {
+            let s = s.strip_prefix("0x").unwrap_or(s);
+            Bytes::from(hex::decode(s).unwrap_or_default())
+        })
+        .unwrap_or_default();
+
+    // Parse miniBlockCount directly from RPC (MegaETH-specific field)
+    let mini_block_count = block
+        .get("miniBlockCount")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1);  // Default to 1 if not present
+
+    // Parse per-mini-block gas if the RPC exposes the mini-block structure
+    let mini_block_gas = block
+        .get("miniBlocks")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .map(|mb| parse_hex_u64(mb.get("gasUsed")).unwrap_or(0))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let txs = block
+        .get("transactions")
+        .and_then(|t| t.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|tx| parse_transaction(tx).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(Some(RawBlock {
+        number,
+        hash,
+        parent_hash,
+        base_fee_per_gas,
+        gas_used,
+        gas_limit,
+        timestamp,
+        extra_data,
+        mini_block_count,
+        mini_block_gas,
+        transactions: txs,
+    }))
+}
+
+/// Parse a single eth_getLogs entry
+fn parse_log(log: &Value) -> Result<RawLog> {
+    let address = parse_address(log.get("address"))
+        .context("Failed to parse log 'address'")?
+        .context("Log entry missing 'address'")?;
+    let topics = log
+        .get("topics")
+        .and_then(|t| t.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|t| t.as_str().and_then(|s| s.parse().ok()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let data = log
+        .get("data")
+        .and_then(|v| v.as_str())
+        .map(|s| {
+            let s = s.strip_prefix("0x").unwrap_or(s);
+            Bytes::from(hex::decode(s).unwrap_or_default())
+        })
+        .unwrap_or_default();
+    let block_number = parse_hex_u64(log.get("blockNumber")).unwrap_or(0);
+
+    Ok(RawLog {
+        address,
+        topics,
+        data,
+        block_number,
+    })
+}
+
+/// Parse an `eth_getBlockReceipts` result, tolerating nulls
+fn parse_raw_receipts(result: &Value) -> Vec<RawReceipt> {
+    result
+        .as_array()
+        .map(|arr| arr.iter().filter_map(|r| parse_receipt(r).ok()).collect())
+        .unwrap_or_default()
+}
+
 fn parse_transaction(tx: &Value) -> Result<RawTransaction> {
     let hash = parse_b256(tx.get("hash")).context("Failed to parse tx 'hash'")?;
     let from = tx
@@ -491,7 +1166,7 @@ fn parse_transaction(tx: &Value) -> Result<RawTransaction> {
 
     let gas = parse_hex_u64(tx.get("gas")).context("Failed to parse tx 'gas'")?;
     let nonce = parse_hex_u64(tx.get("nonce")).context("Failed to parse tx 'nonce'")?;
-    let value = parse_u256(tx.get("value"));
+    let value = parse_u256(tx.get("value")).context("Failed to parse tx 'value'")?;
 
     let tx_type = tx
         .get("type")
@@ -508,9 +1183,16 @@ fn parse_transaction(tx: &Value) -> Result<RawTransaction> {
         .and_then(|v| v.as_str())
         .and_then(|hex| u64::from_str_radix(hex.trim_start_matches("0x"), 16).ok());
 
+    let blob_versioned_hashes: Vec<B256> = tx
+        .get("blobVersionedHashes")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|h| h.as_str()?.parse().ok()).collect())
+        .unwrap_or_default();
+    let max_fee_per_blob_gas = parse_hex_u128(tx.get("maxFeePerBlobGas"));
+
     let v = parse_hex_u64(tx.get("v")).context("Failed to parse tx 'v'")?;
-    let r = parse_u256(tx.get("r"));
-    let s = parse_u256(tx.get("s"));
+    let r = parse_u256(tx.get("r")).context("Failed to parse tx 'r'")?;
+    let s = parse_u256(tx.get("s")).context("Failed to parse tx 's'")?;
 
     // Parse access list if present
     let access_list = tx
@@ -549,6 +1231,8 @@ fn parse_transaction(tx: &Value) -> Result<RawTransaction> {
         r,
         s,
         access_list,
+        blob_versioned_hashes,
+        max_fee_per_blob_gas,
     })
 }
 
@@ -586,3 +1270,534 @@ fn parse_receipt(r: &Value) -> Result<RawReceipt> {
         effective_gas_price,
     })
 }
+
+/// Parse an `eth_feeHistory` result into a FeeHistory
+fn parse_fee_history(result: &Value) -> Result<FeeHistory> {
+    let oldest_block = parse_hex_u64(result.get("oldestBlock"))
+        .context("Failed to parse feeHistory 'oldestBlock'")?;
+
+    let base_fee_per_gas = result
+        .get("baseFeePerGas")
+        .and_then(|v| v.as_array())
+        .context("feeHistory 'baseFeePerGas' is not an array")?
+        .iter()
+        .map(|v| parse_hex_u128(Some(v)).context("Invalid baseFeePerGas entry"))
+        .collect::<Result<Vec<_>>>()?;
+
+    let gas_used_ratio = result
+        .get("gasUsedRatio")
+        .and_then(|v| v.as_array())
+        .map(|a| a.iter().map(|v| v.as_f64().unwrap_or(0.0)).collect())
+        .unwrap_or_default();
+
+    // Absent entirely when no percentiles were requested
+    let reward = result
+        .get("reward")
+        .and_then(|v| v.as_array())
+        .map(|blocks| {
+            blocks
+                .iter()
+                .map(|per_block| {
+                    per_block
+                        .as_array()
+                        .map(|a| a.iter().filter_map(|v| parse_hex_u128(Some(v))).collect())
+                        .unwrap_or_default()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(FeeHistory {
+        oldest_block,
+        base_fee_per_gas,
+        gas_used_ratio,
+        reward,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Spin up a local JSON-RPC stub answering every call with `response`
+    async fn rpc_stub(response: Value) -> String {
+        use axum::routing::post;
+
+        let app = axum::Router::new().route(
+            "/",
+            post(move |axum::Json(req): axum::Json<Value>| async move {
+                let mut resp = response.clone();
+                if resp.get("id") == Some(&json!("echo")) {
+                    resp["id"] = req["id"].clone();
+                }
+                axum::Json(resp)
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        url
+    }
+
+    /// Stub that answers HTTP 500 for the first `failures` requests, then
+    /// echoes a successful block-number response
+    async fn flaky_rpc_stub(failures: u64) -> String {
+        use axum::routing::post;
+        use std::sync::atomic::AtomicU64;
+
+        let seen = Arc::new(AtomicU64::new(0));
+        let app = axum::Router::new().route(
+            "/",
+            post(move |axum::Json(req): axum::Json<Value>| {
+                let seen = seen.clone();
+                async move {
+                    if seen.fetch_add(1, Ordering::Relaxed) < failures {
+                        Err(axum::http::StatusCode::INTERNAL_SERVER_ERROR)
+                    } else {
+                        Ok(axum::Json(
+                            json!({"jsonrpc": "2.0", "id": req["id"], "result": "0x10"}),
+                        ))
+                    }
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        url
+    }
+
+    /// Stub answering JSON-RPC batches of eth_call: calls whose data
+    /// starts with 0xdead get an execution-reverted error entry, everything
+    /// else gets a 32-byte word
+    async fn batch_call_stub() -> String {
+        use axum::routing::post;
+
+        let app = axum::Router::new().route(
+            "/",
+            post(|axum::Json(req): axum::Json<Value>| async move {
+                let entries: Vec<Value> = req
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|call| {
+                        let data = call["params"][0]["data"].as_str().unwrap_or("");
+                        if data.starts_with("0xdead") {
+                            json!({
+                                "jsonrpc": "2.0",
+                                "id": call["id"],
+                                "error": {"code": 3, "message": "execution reverted"}
+                            })
+                        } else {
+                            json!({
+                                "jsonrpc": "2.0",
+                                "id": call["id"],
+                                "result": format!("0x{}01", "00".repeat(31))
+                            })
+                        }
+                    })
+                    .collect();
+                axum::Json(Value::Array(entries))
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_and_closes_again() {
+        // Fail the first 8 requests: two calls of 3 attempts each trip the
+        // breaker (5 consecutive failures), and the later trial succeeds on
+        // its final retry
+        let url = flaky_rpc_stub(8).await;
+        let client = MegaEthClient::new(&url)
+            .await
+            .unwrap()
+            .with_circuit_cooldown(Duration::from_millis(50));
+
+        assert_eq!(client.circuit_state(), "closed");
+        assert!(client.get_block_number().await.is_err());
+        assert!(client.get_block_number().await.is_err());
+        assert_eq!(client.circuit_state(), "open");
+
+        // While open, calls fail fast with the distinct error and never
+        // reach the endpoint
+        let err = client.get_block_number().await.unwrap_err();
+        assert!(err.downcast_ref::<CircuitOpen>().is_some(), "{}", err);
+
+        // After the cooldown a trial is admitted; its success closes the
+        // circuit
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(client.circuit_state(), "half_open");
+        assert_eq!(client.get_block_number().await.unwrap(), 0x10);
+        assert_eq!(client.circuit_state(), "closed");
+    }
+
+    #[tokio::test]
+    async fn test_echoed_request_ids_are_accepted() {
+        let url = rpc_stub(json!({"jsonrpc": "2.0", "id": "echo", "result": "0x10"})).await;
+        let client = MegaEthClient::new(&url).await.unwrap();
+
+        // Each call gets a fresh id; the stub echoes whatever was sent
+        assert_eq!(client.get_block_number().await.unwrap(), 0x10);
+        assert_eq!(client.get_block_number().await.unwrap(), 0x10);
+    }
+
+    #[tokio::test]
+    async fn test_eth_call_batch_keeps_per_call_errors_in_place() {
+        let url = batch_call_stub().await;
+        let client = MegaEthClient::new(&url).await.unwrap();
+
+        let target = Address::repeat_byte(0x11);
+        let calls = vec![
+            (target, "0x18160ddd".to_string()), // answered
+            (target, "0xdeadbeef".to_string()), // reverts
+            (target, "0x95d89b41".to_string()), // answered
+        ];
+        let results = client.eth_call_batch(&calls).await.unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().len(), 32);
+        let err = results[1].as_ref().unwrap_err();
+        assert!(err.to_string().contains("execution reverted"), "{}", err);
+        assert!(results[2].is_ok());
+
+        // A revert is a per-call outcome, not an endpoint failure: the
+        // breaker stays closed and later calls go straight through
+        assert_eq!(client.circuit_state(), "closed");
+    }
+
+    #[tokio::test]
+    async fn test_get_block_by_hash_parses_stub_block() {
+        let hash = B256::repeat_byte(0xab);
+        let url = rpc_stub(json!({
+            "jsonrpc": "2.0",
+            "id": "echo",
+            "result": {
+                "number": "0x2a",
+                "hash": format!("{:?}", hash),
+                "gasUsed": "0x5208",
+                "gasLimit": "0x1c9c380",
+                "timestamp": "0x68b0f000",
+                "transactions": []
+            }
+        }))
+        .await;
+        let client = MegaEthClient::new(&url).await.unwrap();
+
+        let block = client
+            .get_block_by_hash(hash)
+            .await
+            .unwrap()
+            .expect("stub block parses");
+        assert_eq!(block.number, 0x2a);
+        assert_eq!(block.hash, hash);
+        assert_eq!(block.gas_used, 21_000);
+        assert!(block.transactions.is_empty());
+    }
+
+    /// A recorded MegaETH `eth_getBlockByNumber` payload: MegaETH-specific
+    /// mini-block fields, one EIP-1559 transaction with an access list and
+    /// one legacy transaction. Locks in the parsing path against schema
+    /// drift.
+    fn recorded_megaeth_block() -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": "echo",
+            "result": {
+                "number": "0x89d421",
+                "hash": "0x7d5a4369273c723454ac137f48a4f142b097aa2779464e6505f1b1c5e37b5382",
+                "parentHash": "0x60e9537b2ce31ed4e9d2526ee55b4a07aecc1f132f7505caa0f529eb84b8b1c2",
+                "baseFeePerGas": "0x342770c0",
+                "gasUsed": "0x1f8a2",
+                "gasLimit": "0x1c9c380",
+                "timestamp": "0x68b0f2a8",
+                "extraData": "0x6d65676145544800",
+                "miniBlockCount": 10,
+                "miniBlocks": [
+                    {"gasUsed": "0x15f90"},
+                    {"gasUsed": "0x9912"}
+                ],
+                "transactions": [
+                    {
+                        "hash": "0x0a93d9cd5e229a96873da23ca33f325e9072a2d35572eea2876d5e5ff5ca4c6b",
+                        "from": "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266",
+                        "to": "0x5fbdb2315678afecb367f032d93f642f64180aa3",
+                        "input": "0xa9059cbb00000000000000000000000070997970c51812dc3a010c7d01b50e0d17dc79c80000000000000000000000000000000000000000000000000de0b6b3a7640000",
+                        "gas": "0x15f90",
+                        "nonce": "0x2a",
+                        "value": "0x0",
+                        "type": "0x2",
+                        "chainId": "0x18c6",
+                        "maxFeePerGas": "0x77359400",
+                        "maxPriorityFeePerGas": "0xf4240",
+                        "accessList": [
+                            {
+                                "address": "0x5fbdb2315678afecb367f032d93f642f64180aa3",
+                                "storageKeys": [
+                                    "0x0000000000000000000000000000000000000000000000000000000000000003",
+                                    "0x000000000000000000000000000000000000000000000000000000000000000a"
+                                ]
+                            }
+                        ],
+                        "v": "0x1",
+                        "r": "0x4f7b6cd0a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c",
+                        "s": "0x2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f70"
+                    },
+                    {
+                        "hash": "0xb7e1b5b3e0e2cf10af9f26b9cb548dcf44c5b1a0e25f8f2a51d2f91a7a7acf52",
+                        "from": "0x70997970c51812dc3a010c7d01b50e0d17dc79c8",
+                        "to": "0x3c44cdddb6a900fa2b585dd299e03d12fa4293bc",
+                        "input": "0x",
+                        "gas": "0x5208",
+                        "nonce": "0x0",
+                        "value": "0xde0b6b3a7640000",
+                        "type": "0x0",
+                        "gasPrice": "0x3b9aca00",
+                        "v": "0x31af",
+                        "r": "0x1",
+                        "s": "0x1"
+                    }
+                ]
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_get_block_parses_recorded_megaeth_response() {
+        let url = rpc_stub(recorded_megaeth_block()).await;
+        let client = MegaEthClient::new(&url).await.unwrap();
+
+        let block = client
+            .get_block(0x89d421)
+            .await
+            .unwrap()
+            .expect("recorded block parses");
+
+        assert_eq!(block.number, 0x89d421);
+        assert_eq!(
+            block.hash,
+            "0x7d5a4369273c723454ac137f48a4f142b097aa2779464e6505f1b1c5e37b5382"
+                .parse::<B256>()
+                .unwrap()
+        );
+        assert_eq!(block.base_fee_per_gas, Some(0x342770c0));
+        assert_eq!(block.gas_used, 0x1f8a2);
+        assert_eq!(block.extra_data.as_ref(), b"megaETH\0");
+
+        // MegaETH-specific mini-block structure
+        assert_eq!(block.mini_block_count, 10);
+        assert_eq!(block.mini_block_gas, vec![90_000, 39_186]);
+
+        // EIP-1559 transaction with its declared access list
+        assert_eq!(block.transactions.len(), 2);
+        let eip1559 = &block.transactions[0];
+        assert_eq!(eip1559.tx_type, 2);
+        assert_eq!(eip1559.nonce, 0x2a);
+        assert_eq!(eip1559.chain_id, Some(0x18c6));
+        assert_eq!(eip1559.max_priority_fee_per_gas, Some(1_000_000));
+        assert_eq!(eip1559.input.len(), 68, "transfer calldata length");
+        assert_eq!(eip1559.access_list.len(), 1);
+        let (address, keys) = &eip1559.access_list[0];
+        assert_eq!(
+            *address,
+            "0x5fbdb2315678afecb367f032d93f642f64180aa3"
+                .parse::<Address>()
+                .unwrap()
+        );
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0], B256::with_last_byte(3));
+
+        // Legacy transaction alongside it
+        let legacy = &block.transactions[1];
+        assert_eq!(legacy.tx_type, 0);
+        assert_eq!(legacy.gas_price, Some(1_000_000_000));
+        assert_eq!(legacy.value, U256::from(1_000_000_000_000_000_000u64));
+        assert!(legacy.access_list.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_malformed_block_response_is_a_clean_error() {
+        // gasUsed is not valid hex: parsing must fail with context, not
+        // panic or silently zero the field
+        let url = rpc_stub(json!({
+            "jsonrpc": "2.0",
+            "id": "echo",
+            "result": {
+                "number": "0x2a",
+                "hash": format!("{:?}", B256::repeat_byte(0xab)),
+                "gasUsed": "not-hex",
+                "gasLimit": "0x1c9c380",
+                "timestamp": "0x68b0f000",
+                "transactions": []
+            }
+        }))
+        .await;
+        let client = MegaEthClient::new(&url).await.unwrap();
+
+        let err = client.get_block(42).await.unwrap_err();
+        assert!(err.to_string().contains("gasUsed"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_response_id_is_an_error() {
+        let url = rpc_stub(json!({"jsonrpc": "2.0", "id": 999_999, "result": "0x10"})).await;
+        let client = MegaEthClient::new(&url).await.unwrap();
+
+        let err = client.get_block_number().await.unwrap_err();
+        assert!(err.to_string().contains("id mismatch"), "{}", err);
+    }
+
+    fn sample_tx(tx_type: u8) -> RawTransaction {
+        RawTransaction {
+            hash: B256::ZERO,
+            from: Address::ZERO,
+            to: Some(Address::repeat_byte(0x42)),
+            input: Bytes::from(vec![0xa9, 0x05, 0x9c, 0xbb, 0x00, 0x11, 0x22]),
+            gas: 53_000,
+            tx_type,
+            nonce: 7,
+            value: U256::from(1_000_000_000u64),
+            gas_price: Some(2_000_000_000),
+            max_fee_per_gas: Some(3_000_000_000),
+            max_priority_fee_per_gas: Some(1_000_000_000),
+            chain_id: Some(6342),
+            v: if tx_type == 0 { 12_719 } else { 1 },
+            r: U256::from(0x1234u64),
+            s: U256::from(0x5678u64),
+            access_list: vec![(Address::repeat_byte(0x11), vec![B256::repeat_byte(0x22)])],
+            blob_versioned_hashes: if tx_type == 3 {
+                vec![B256::repeat_byte(0x33), B256::repeat_byte(0x44)]
+            } else {
+                Vec::new()
+            },
+            max_fee_per_blob_gas: if tx_type == 3 { Some(1_000_000) } else { None },
+        }
+    }
+
+    #[test]
+    fn test_encoded_size_matches_wire_bytes() {
+        for tx_type in [0u8, 1, 2, 3] {
+            let tx = sample_tx(tx_type);
+            let bytes = tx.to_bytes_for_da();
+            assert_eq!(
+                tx.encoded_size(),
+                bytes.len() as u64,
+                "type {} size mismatch",
+                tx_type
+            );
+            if tx_type > 0 {
+                assert_eq!(bytes[0], tx_type, "typed tx must start with its type byte");
+            }
+        }
+    }
+
+    #[test]
+    fn test_wire_bytes_decode_as_envelopes() {
+        use alloy::consensus::TxEnvelope;
+        use alloy::eips::eip2718::Decodable2718;
+
+        for tx_type in [0u8, 1, 2, 3] {
+            let tx = sample_tx(tx_type);
+            let bytes = tx.to_bytes_for_da();
+            let envelope = TxEnvelope::decode_2718(&mut bytes.as_slice())
+                .unwrap_or_else(|e| panic!("type {} did not round-trip: {}", tx_type, e));
+            assert_eq!(envelope.tx_type() as u8, tx_type);
+        }
+    }
+
+    #[test]
+    fn test_deposit_tx_falls_back_to_estimate() {
+        let tx = sample_tx(126);
+        assert!(tx.encode_2718().is_none());
+        assert!(tx.encoded_size() > 0);
+        assert!(!tx.to_bytes_for_da().is_empty());
+    }
+
+    #[test]
+    fn test_parse_helpers_accept_numeric_and_hex_encodings() {
+        // Some providers return bare JSON numbers where the spec says hex
+        // strings; both encodings of the same value must agree
+        assert_eq!(parse_hex_u64(Some(&json!("0x2a"))).unwrap(), 42);
+        assert_eq!(parse_hex_u64(Some(&json!(42))).unwrap(), 42);
+        assert_eq!(
+            parse_hex_u128(Some(&json!("0x3b9aca00"))),
+            Some(1_000_000_000)
+        );
+        assert_eq!(
+            parse_hex_u128(Some(&json!(1_000_000_000u64))),
+            Some(1_000_000_000)
+        );
+        assert_eq!(
+            parse_u256(Some(&json!("0xde0b6b3a7640000"))).unwrap(),
+            U256::from(1_000_000_000_000_000_000u64)
+        );
+        assert_eq!(
+            parse_u256(Some(&json!(1_000u64))).unwrap(),
+            U256::from(1_000u64)
+        );
+    }
+
+    #[test]
+    fn test_malformed_values_error_instead_of_defaulting() {
+        // Legitimately absent signature fields are zero, but a present
+        // malformed value must surface as an error
+        assert_eq!(parse_u256(None).unwrap(), U256::ZERO);
+        assert_eq!(parse_u256(Some(&Value::Null)).unwrap(), U256::ZERO);
+        assert!(parse_u256(Some(&json!("0xzz"))).is_err());
+        assert!(parse_u256(Some(&json!([1, 2]))).is_err());
+        assert!(parse_hex_u64(Some(&json!(-1))).is_err());
+        assert!(
+            parse_b256(None).is_err(),
+            "a missing hash must not become the zero hash"
+        );
+    }
+
+    #[test]
+    fn test_parse_fee_history_sample_response() {
+        let sample = json!({
+            "oldestBlock": "0x10",
+            "baseFeePerGas": ["0x3b9aca00", "0x3f5476a0", "0x42c1d80"],
+            "gasUsedRatio": [0.5, 0.75],
+            "reward": [
+                ["0xf4240", "0x1e8480"],
+                ["0x1e8480", "0x2dc6c0"]
+            ]
+        });
+
+        let history = parse_fee_history(&sample).unwrap();
+        assert_eq!(history.oldest_block, 0x10);
+        assert_eq!(
+            history.base_fee_per_gas,
+            vec![1_000_000_000, 1_062_500_000, 70_000_000]
+        );
+        assert_eq!(history.gas_used_ratio, vec![0.5, 0.75]);
+        assert_eq!(
+            history.reward,
+            vec![vec![1_000_000, 2_000_000], vec![2_000_000, 3_000_000]]
+        );
+    }
+
+    #[test]
+    fn test_parse_fee_history_without_rewards() {
+        let sample = json!({
+            "oldestBlock": "0x1",
+            "baseFeePerGas": ["0x1"],
+            "gasUsedRatio": [0.1]
+        });
+
+        let history = parse_fee_history(&sample).unwrap();
+        assert!(history.reward.is_empty());
+    }
+}
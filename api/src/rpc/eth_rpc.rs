@@ -0,0 +1,125 @@
+//! RPC abstraction so consumers can run against a mock in tests
+//!
+//! `BlockPoller` and the replay cache are generic over this trait rather
+//! than holding a concrete [`MegaEthClient`], letting unit tests serve
+//! canned blocks with no live endpoint.
+
+use std::future::Future;
+
+use alloy_primitives::{Address, Bytes, U256};
+use anyhow::Result;
+
+use super::client::{MegaEthClient, RawBlock, RawReceipt};
+
+/// The subset of the MegaETH JSON-RPC surface consumers depend on
+///
+/// Methods are declared as `impl Future + Send` (rather than `async fn`)
+/// so generic consumers can hold the returned futures across awaits and
+/// box them for coalescing. Implementations still write plain `async fn`.
+pub trait EthRpc: Send + Sync + 'static {
+    /// Latest block number on the chain
+    fn get_latest_block_number(&self) -> impl Future<Output = Result<u64>> + Send;
+
+    /// Alias of [`get_latest_block_number`](Self::get_latest_block_number)
+    fn get_block_number(&self) -> impl Future<Output = Result<u64>> + Send;
+
+    /// Fetch a block with full transactions; None when it doesn't exist yet
+    fn get_block(
+        &self,
+        block_number: u64,
+    ) -> impl Future<Output = Result<Option<RawBlock>>> + Send;
+
+    /// Fetch all receipts for a block
+    fn get_block_receipts(
+        &self,
+        block_number: u64,
+    ) -> impl Future<Output = Result<Vec<RawReceipt>>> + Send;
+
+    /// Fetch a block and its receipts together (batched on the wire)
+    fn get_block_with_receipts(
+        &self,
+        block_number: u64,
+    ) -> impl Future<Output = Result<Option<(RawBlock, Vec<RawReceipt>)>>> + Send;
+
+    /// Contract bytecode at the latest block
+    fn get_code(&self, address: Address) -> impl Future<Output = Result<Bytes>> + Send;
+
+    /// Contract bytecode at a specific block tag
+    fn get_code_at(
+        &self,
+        address: Address,
+        block_tag: &str,
+    ) -> impl Future<Output = Result<Bytes>> + Send;
+
+    /// Account balance at a specific block tag
+    fn get_balance_at(
+        &self,
+        address: Address,
+        block_tag: &str,
+    ) -> impl Future<Output = Result<U256>> + Send;
+
+    /// Account nonce at a specific block tag
+    fn get_nonce_at(
+        &self,
+        address: Address,
+        block_tag: &str,
+    ) -> impl Future<Output = Result<u64>> + Send;
+
+    /// Storage slot value at a specific block tag
+    fn get_storage_at_block(
+        &self,
+        address: Address,
+        index: U256,
+        block_tag: &str,
+    ) -> impl Future<Output = Result<U256>> + Send;
+}
+
+impl EthRpc for MegaEthClient {
+    async fn get_latest_block_number(&self) -> Result<u64> {
+        MegaEthClient::get_latest_block_number(self).await
+    }
+
+    async fn get_block_number(&self) -> Result<u64> {
+        MegaEthClient::get_block_number(self).await
+    }
+
+    async fn get_block(&self, block_number: u64) -> Result<Option<RawBlock>> {
+        MegaEthClient::get_block(self, block_number).await
+    }
+
+    async fn get_block_receipts(&self, block_number: u64) -> Result<Vec<RawReceipt>> {
+        MegaEthClient::get_block_receipts(self, block_number).await
+    }
+
+    async fn get_block_with_receipts(
+        &self,
+        block_number: u64,
+    ) -> Result<Option<(RawBlock, Vec<RawReceipt>)>> {
+        MegaEthClient::get_block_with_receipts(self, block_number).await
+    }
+
+    async fn get_code(&self, address: Address) -> Result<Bytes> {
+        MegaEthClient::get_code(self, address).await
+    }
+
+    async fn get_code_at(&self, address: Address, block_tag: &str) -> Result<Bytes> {
+        MegaEthClient::get_code_at(self, address, block_tag).await
+    }
+
+    async fn get_balance_at(&self, address: Address, block_tag: &str) -> Result<U256> {
+        MegaEthClient::get_balance_at(self, address, block_tag).await
+    }
+
+    async fn get_nonce_at(&self, address: Address, block_tag: &str) -> Result<u64> {
+        MegaEthClient::get_nonce_at(self, address, block_tag).await
+    }
+
+    async fn get_storage_at_block(
+        &self,
+        address: Address,
+        index: U256,
+        block_tag: &str,
+    ) -> Result<U256> {
+        MegaEthClient::get_storage_at_block(self, address, index, block_tag).await
+    }
+}
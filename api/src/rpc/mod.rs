@@ -1,5 +1,8 @@
 mod client;
+mod eth_rpc;
 mod poller;
+pub mod rlp;
 
-pub use client::{MegaEthClient, RawBlock, RawReceipt, RawTransaction};
-pub use poller::{BlockEvent, BlockPoller};
+pub use client::{CircuitOpen, FeeHistory, MegaEthClient, RawBlock, RawLog, RawReceipt, RawTransaction};
+pub use eth_rpc::EthRpc;
+pub use poller::{BlockEvent, BlockPoller, TentativeBlockEvent};
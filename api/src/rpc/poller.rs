@@ -1,24 +1,86 @@
 use std::sync::Arc;
 use std::time::Duration;
+use alloy_primitives::Address;
 use serde::Serialize;
 use tokio::sync::broadcast;
 use tokio::time::interval;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
 use crate::metrics::{BlockMetrics, MetricsStore};
 use crate::processor::MetricsCalculator;
+use crate::questdb::QuestDBWriter;
 
-use super::client::MegaEthClient;
+use super::client::{CircuitOpen, MegaEthClient, CIRCUIT_COOLDOWN};
+use super::eth_rpc::EthRpc;
+
+/// How many deployed-code fetches run concurrently per block
+const CODE_SIZE_CONCURRENCY: usize = 8;
+
+/// Default number of blocks fetched and computed concurrently per poll
+const DEFAULT_PROCESS_CONCURRENCY: usize = 8;
+
+/// Lag (in blocks) past which the poller switches to batch catch-up mode
+const CATCHUP_ENTER_LAG: u64 = 500;
+
+/// Lag at or below which catch-up mode ends; well under the enter
+/// threshold so the mode doesn't flap around the boundary
+const CATCHUP_EXIT_LAG: u64 = 10;
+
+/// Span and concurrency multiplier applied while catching up
+const CATCHUP_MULTIPLIER: usize = 4;
 
 /// Block event for broadcasting
 #[derive(Debug, Clone, Serialize)]
 pub struct BlockEvent {
-    pub block: BlockMetrics,
+    /// Shared rather than owned: broadcasting clones the event once per
+    /// subscriber, and the metrics payload shouldn't be deep-copied each time
+    pub block: Arc<BlockMetrics>,
+    /// True when this event supersedes a previously-broadcast block with the
+    /// same number (reorg or poller retry); clients should update, not append
+    pub replaced: bool,
+    /// Distinct `to` addresses seen in the block, for subscriber filtering
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub to_addresses: Vec<Address>,
+    /// True when any aggregate metric scored far above the rolling baseline
+    /// or ran close to a protocol limit
+    pub anomalous: bool,
+    /// Largest normalized score across the block's metrics (-100 to +100)
+    pub anomaly_score: f64,
+}
+
+/// Head-of-chain block event, emitted ahead of the confirmation depth
+///
+/// Tentative blocks have not waited out `confirmation_blocks` and may be
+/// reorged away; clients opting into the head stream accept that a later
+/// event can revise an earlier one. They are broadcast-only and never enter
+/// the metrics store.
+#[derive(Debug, Clone, Serialize)]
+pub struct TentativeBlockEvent {
+    pub block: Arc<BlockMetrics>,
+    /// True when a previously-emitted tentative block with this number was
+    /// replaced by a different hash (reorg revision)
+    pub revised: bool,
+}
+
+/// A fully computed block awaiting its ordered commit into the store
+///
+/// Produced by the concurrent fetch/compute stage; committing is sequential
+/// so `last_block` stays monotonic and subscribers see blocks in order.
+struct PreparedBlock {
+    block_number: u64,
+    block_metrics: BlockMetrics,
+    tx_metrics: Vec<crate::metrics::TransactionMetrics>,
+    deployments: Vec<crate::metrics::DeploymentEvent>,
+    to_addresses: Vec<Address>,
 }
 
 /// Polls MegaETH for new blocks and processes them
-pub struct BlockPoller {
-    client: MegaEthClient,
+///
+/// Generic over the RPC so tests can drive it with a mock; production
+/// code uses the default [`MegaEthClient`].
+pub struct BlockPoller<R: EthRpc = MegaEthClient> {
+    client: R,
     store: Arc<MetricsStore>,
     calculator: MetricsCalculator,
     /// How far behind the head to stay (for reorg safety)
@@ -27,11 +89,35 @@ pub struct BlockPoller {
     poll_interval: Duration,
     /// Broadcast sender for new blocks
     block_tx: broadcast::Sender<BlockEvent>,
+    /// Optional QuestDB persistence for every processed block
+    writer: Option<QuestDBWriter>,
+    /// First block to process on a fresh start (default: 100 behind head)
+    start_block: Option<u64>,
+    /// Stop after processing this block (one-shot backfill mode)
+    end_block: Option<u64>,
+    /// Signals the poll loop to exit cleanly
+    shutdown: CancellationToken,
+    /// Whether to fetch deployed runtime code sizes (one RPC per deployment)
+    fetch_code_sizes: bool,
+    /// How many blocks are fetched and computed concurrently per poll
+    process_concurrency: usize,
+    /// Process every Nth block (1 = every block)
+    sample_interval: u64,
+    /// Optional head-of-chain stream, ahead of the confirmation depth
+    tentative_tx: Option<broadcast::Sender<TentativeBlockEvent>>,
+    /// Optional live stream of contract deployments, alongside the QuestDB
+    /// write; identification stays out of the poll loop (it's on-demand
+    /// behind /contracts/{address}/identify)
+    deployment_tx: Option<broadcast::Sender<crate::metrics::DeploymentEvent>>,
+    /// Last tentative block emitted, for change/revision detection
+    last_tentative: std::sync::Mutex<Option<(u64, alloy_primitives::B256)>>,
+    /// Whether the poller is in batch catch-up mode (see `update_catchup`)
+    catching_up: std::sync::atomic::AtomicBool,
 }
 
-impl BlockPoller {
+impl<R: EthRpc> BlockPoller<R> {
     pub fn new(
-        client: MegaEthClient,
+        client: R,
         store: Arc<MetricsStore>,
         confirmation_blocks: u64,
         poll_interval: Duration,
@@ -44,10 +130,93 @@ impl BlockPoller {
             confirmation_blocks,
             poll_interval,
             block_tx,
+            writer: None,
+            start_block: None,
+            end_block: None,
+            shutdown: CancellationToken::new(),
+            fetch_code_sizes: true,
+            process_concurrency: DEFAULT_PROCESS_CONCURRENCY,
+            sample_interval: 1,
+            tentative_tx: None,
+            deployment_tx: None,
+            last_tentative: std::sync::Mutex::new(None),
+            catching_up: std::sync::atomic::AtomicBool::new(false),
         }
     }
 
-    /// Start polling for new blocks (runs forever)
+    /// Broadcast contract deployments as they are committed
+    pub fn with_deployment_channel(
+        mut self,
+        deployment_tx: broadcast::Sender<crate::metrics::DeploymentEvent>,
+    ) -> Self {
+        self.deployment_tx = Some(deployment_tx);
+        self
+    }
+
+    /// Fetch and compute this many blocks concurrently per poll
+    ///
+    /// Store insertion and broadcasting stay strictly in block order; only
+    /// the network-bound fetch/compute stage is parallelized.
+    pub fn with_process_concurrency(mut self, concurrency: usize) -> Self {
+        self.process_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Process every Nth block instead of all of them
+    ///
+    /// Trades completeness for staying near the head on endpoints that
+    /// can't sustain the full block rate: skipped blocks are never
+    /// fetched, and committed blocks carry `sampled: true` so consumers
+    /// know window aggregates undercount the chain. 1 (the default)
+    /// disables sampling.
+    pub fn with_sampling(mut self, every_nth: u64) -> Self {
+        self.sample_interval = every_nth.max(1);
+        self
+    }
+
+    /// Broadcast the unconfirmed head block on a separate channel
+    ///
+    /// The main store keeps its confirmation depth; this adds a liveness
+    /// stream for clients that want the absolute head and tolerate reorgs.
+    pub fn with_tentative_channel(mut self, tx: broadcast::Sender<TentativeBlockEvent>) -> Self {
+        self.tentative_tx = Some(tx);
+        self
+    }
+
+    /// Enable or disable per-deployment code-size fetches
+    ///
+    /// Each deployment costs one extra RPC call; disable on constrained
+    /// endpoints at the price of zero code sizes in deployment stats.
+    pub fn with_code_size_fetching(mut self, enabled: bool) -> Self {
+        self.fetch_code_sizes = enabled;
+        self
+    }
+
+    /// Attach a QuestDB writer so every processed block is persisted
+    pub fn with_questdb_writer(mut self, writer: QuestDBWriter) -> Self {
+        self.writer = Some(writer);
+        self
+    }
+
+    /// Constrain the poller to an explicit block range
+    ///
+    /// `start` overrides the fresh-start default of 100 blocks behind head.
+    /// When `end` is set the poller runs as a one-shot backfill: it stops
+    /// cleanly after processing that block instead of following the head.
+    pub fn with_block_range(mut self, start: Option<u64>, end: Option<u64>) -> Self {
+        self.start_block = start;
+        self.end_block = end;
+        self
+    }
+
+    /// Use an externally-owned cancellation token, so the caller can stop
+    /// the poll loop on shutdown
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Start polling for new blocks (runs until the shutdown token fires)
     pub async fn run(&self) {
         info!(
             "Starting block poller with {}ms interval, {} confirmation blocks",
@@ -58,10 +227,32 @@ impl BlockPoller {
         let mut poll_timer = interval(self.poll_interval);
 
         loop {
-            poll_timer.tick().await;
+            tokio::select! {
+                _ = poll_timer.tick() => {}
+                _ = self.shutdown.cancelled() => {
+                    info!("Block poller shutting down");
+                    break;
+                }
+            }
 
             if let Err(e) = self.poll_once().await {
-                error!("Error polling blocks: {}", e);
+                // An open circuit means the endpoint is down; sleep out the
+                // cooldown instead of burning poll ticks on fail-fast errors
+                if e.downcast_ref::<CircuitOpen>().is_some() {
+                    warn!(
+                        "RPC circuit open, pausing polling for {:?}",
+                        CIRCUIT_COOLDOWN
+                    );
+                    tokio::select! {
+                        _ = tokio::time::sleep(CIRCUIT_COOLDOWN) => {}
+                        _ = self.shutdown.cancelled() => {
+                            info!("Block poller shutting down");
+                            break;
+                        }
+                    }
+                } else {
+                    error!("Error polling blocks: {}", e);
+                }
             }
         }
     }
@@ -70,54 +261,242 @@ impl BlockPoller {
     async fn poll_once(&self) -> anyhow::Result<()> {
         // Get the latest block number (minus confirmation blocks)
         let latest = self.client.get_latest_block_number().await?;
-        let target = latest.saturating_sub(self.confirmation_blocks);
+        self.store.set_latest_head(latest).await;
+        let mut target = latest.saturating_sub(self.confirmation_blocks);
+        if let Some(end) = self.end_block {
+            target = target.min(end);
+        }
+
+        let lag = latest.saturating_sub(self.store.last_block_number().await);
+        crate::telemetry::telemetry().set_poller_lag(lag);
+        self.update_catchup(lag);
 
         // Get our last processed block
-        let last_processed = self.store.last_block_number().await;
+        let mut last_processed = self.store.last_block_number().await;
+
+        // Verify the tip we stored is still canonical before advancing
+        let mut reorged = false;
+        if last_processed > 0 {
+            if let Some(fork_point) = self.detect_reorg(last_processed).await? {
+                warn!(
+                    "Reorg detected: rolling back blocks {} to {}",
+                    fork_point, last_processed
+                );
+                self.store.remove_blocks_from(fork_point).await;
+                last_processed = self.store.last_block_number().await;
+                reorged = true;
+            }
+        }
 
-        // If we're starting fresh, start from a recent block
+        // If we're starting fresh, start from the configured block or a
+        // recent one
         let start_block = if last_processed == 0 {
-            // Start from 100 blocks ago
-            target.saturating_sub(100)
+            self.start_block
+                .unwrap_or_else(|| target.saturating_sub(100))
         } else {
             last_processed + 1
         };
 
         // Process any missing blocks
         if start_block <= target {
-            let blocks_to_process = (target - start_block + 1).min(100); // Cap at 100 blocks per poll
+            // Cap the span covered per poll; under sampling the span scales
+            // with N so the per-poll RPC budget stays the same while the
+            // poller covers more of the chain, and catch-up mode widens
+            // both the span and the fetch concurrency
+            let (span_cap, concurrency) =
+                if self.catching_up.load(std::sync::atomic::Ordering::Relaxed) {
+                    (
+                        100 * self.sample_interval * CATCHUP_MULTIPLIER as u64,
+                        self.process_concurrency * CATCHUP_MULTIPLIER,
+                    )
+                } else {
+                    (100 * self.sample_interval, self.process_concurrency)
+                };
+            let span = (target - start_block + 1).min(span_cap);
+            let end = start_block + span;
+
+            // Sample by absolute block number so the picks stay evenly
+            // spaced across polls regardless of where each batch starts
+            let candidates: Vec<u64> = (start_block..end)
+                .filter(|n| n % self.sample_interval == 0)
+                .collect();
             debug!(
-                "Processing blocks {} to {} ({} blocks)",
+                "Processing {} of blocks {} to {}",
+                candidates.len(),
                 start_block,
-                start_block + blocks_to_process - 1,
-                blocks_to_process
+                end - 1
             );
 
-            for block_num in start_block..start_block + blocks_to_process {
-                self.process_block(block_num).await?;
+            // Fetch and compute concurrently, then commit strictly in block
+            // order so the store and broadcast stream stay monotonic. An
+            // error stops the batch at that block; committed blocks stand,
+            // and the rest are re-polled next tick.
+            let mut committed: u64 = 0;
+            'batch: for chunk in candidates.chunks(concurrency) {
+                let prepared = futures::future::join_all(
+                    chunk.iter().map(|&n| self.prepare_block(n)),
+                )
+                .await;
+
+                for (&block_num, result) in chunk.iter().zip(prepared) {
+                    match result {
+                        Ok(Some(block)) => {
+                            self.commit_block(block, reorged).await;
+                            committed += 1;
+                        }
+                        Ok(None) => {
+                            warn!("Block {} not found", block_num);
+                        }
+                        Err(e) => {
+                            error!("Error processing block {}: {}", block_num, e);
+                            break 'batch;
+                        }
+                    }
+                }
+            }
+
+            if self.sample_interval > 1 && committed > 0 {
+                debug!(
+                    "Sampling 1/{}: committed {} blocks covering a span of {}",
+                    self.sample_interval, committed, span
+                );
+            }
+        }
+
+        // Head stream: emit the unconfirmed tip for liveness-focused clients
+        if self.end_block.is_none() {
+            if let Err(e) = self.emit_tentative(latest).await {
+                warn!("Failed to emit tentative head block {}: {}", latest, e);
+            }
+        }
+
+        // In backfill mode, report progress and stop once the range is done
+        if let Some(end) = self.end_block {
+            let current = self.store.last_block_number().await;
+            // Under sampling the last candidate at or below `end` counts as
+            // done; the blocks past it would never be picked
+            let done = current >= end
+                || (self.sample_interval > 1
+                    && current > 0
+                    && current.saturating_add(self.sample_interval) > end);
+            if done {
+                info!("Backfill complete at block {}, stopping poller", end);
+                self.shutdown.cancel();
+            } else {
+                info!("Backfill progress: block {} of {}", current, end);
             }
         }
 
         Ok(())
     }
 
-    /// Process a single block
-    async fn process_block(&self, block_number: u64) -> anyhow::Result<()> {
-        // Fetch block and receipts in parallel
-        let (block_result, receipts_result) = tokio::join!(
-            self.client.get_block(block_number),
-            self.client.get_block_receipts(block_number)
-        );
+    /// Track the poller's lag and flip batch catch-up mode with hysteresis
+    ///
+    /// Past [`CATCHUP_ENTER_LAG`] the poller widens its per-poll span and
+    /// fetch concurrency until the lag falls back to [`CATCHUP_EXIT_LAG`];
+    /// the spread between the thresholds keeps the mode from flapping.
+    fn update_catchup(&self, lag: u64) {
+        use std::sync::atomic::Ordering;
 
-        let block = match block_result? {
-            Some(b) => b,
-            None => {
-                warn!("Block {} not found", block_number);
-                return Ok(());
+        let catching_up = self.catching_up.load(Ordering::Relaxed);
+        if !catching_up && lag > CATCHUP_ENTER_LAG {
+            warn!("Catching up, {} blocks behind", lag);
+            self.catching_up.store(true, Ordering::Relaxed);
+            crate::telemetry::telemetry().set_catching_up(true);
+        } else if catching_up && lag <= CATCHUP_EXIT_LAG {
+            info!("Caught up to within {} blocks of the head", lag);
+            self.catching_up.store(false, Ordering::Relaxed);
+            crate::telemetry::telemetry().set_catching_up(false);
+        } else if catching_up {
+            debug!("Catching up, {} blocks behind", lag);
+        }
+    }
+
+    /// Emit the current head block on the tentative channel, if one is
+    /// attached and the head moved or was replaced since the last emit
+    async fn emit_tentative(&self, head: u64) -> anyhow::Result<()> {
+        let Some(tx) = &self.tentative_tx else {
+            return Ok(());
+        };
+        let Some((block, receipts)) = self.client.get_block_with_receipts(head).await? else {
+            return Ok(());
+        };
+
+        // Head receipts may still be settling; never reject the block
+        let (metrics, _) = self.calculator.process_block_lenient(&block, &receipts)?;
+
+        let revised = {
+            let mut last = self.last_tentative.lock().unwrap();
+            match *last {
+                // Unchanged head: nothing new to tell subscribers
+                Some((number, hash)) if number == block.number && hash == block.hash => {
+                    return Ok(());
+                }
+                Some((number, hash)) => {
+                    let revised = number == block.number && hash != block.hash;
+                    *last = Some((block.number, block.hash));
+                    revised
+                }
+                None => {
+                    *last = Some((block.number, block.hash));
+                    false
+                }
             }
         };
 
-        let receipts = receipts_result?;
+        let _ = tx.send(TentativeBlockEvent {
+            block: Arc::new(metrics),
+            revised,
+        });
+        Ok(())
+    }
+
+    /// Check whether the stored chain tip still matches the canonical chain
+    ///
+    /// Returns the first block number that needs re-processing when the tip
+    /// has been reorged out, walking back until stored and chain hashes agree
+    async fn detect_reorg(&self, last_processed: u64) -> anyhow::Result<Option<u64>> {
+        let Some(stored) = self.store.get_block(last_processed).await else {
+            return Ok(None);
+        };
+        let Some(chain) = self.client.get_block(last_processed).await? else {
+            return Ok(None);
+        };
+        if chain.hash == stored.block_hash {
+            return Ok(None);
+        }
+
+        // Walk back to the most recent block where we agree with the chain
+        let mut number = last_processed;
+        while number > 0 {
+            number -= 1;
+            let Some(stored) = self.store.get_block(number).await else {
+                break;
+            };
+            let Some(chain) = self.client.get_block(number).await? else {
+                continue;
+            };
+            if chain.hash == stored.block_hash {
+                break;
+            }
+        }
+
+        Ok(Some(number + 1))
+    }
+
+    /// Fetch and compute a single block (the parallelizable stage)
+    ///
+    /// Returns None when the block doesn't exist yet. No shared state is
+    /// touched here, so several blocks can be prepared concurrently; the
+    /// ordered side effects happen in [`commit_block`](Self::commit_block).
+    async fn prepare_block(&self, block_number: u64) -> anyhow::Result<Option<PreparedBlock>> {
+        // Fetch block and receipts in a single batched request
+        let (mut block, mut receipts) = match self.client.get_block_with_receipts(block_number).await? {
+            Some(pair) => pair,
+            None => {
+                return Ok(None);
+            }
+        };
 
         // Verify receipt count matches transaction count
         let tx_count = block.transactions.len();
@@ -130,20 +509,432 @@ impl BlockPoller {
             );
         }
 
-        // Process the block
-        let (block_metrics, tx_metrics) = self.calculator.process_block(&block, &receipts)?;
+        // Process the block; incomplete receipts get one refetch before the
+        // estimated metrics are accepted (flagged via receipts_complete)
+        let (mut block_metrics, tx_metrics) = match self.calculator.process_block(&block, &receipts) {
+            Ok(processed) => processed,
+            Err(e) => {
+                warn!("Block {}: {}; refetching once", block_number, e);
+                if let Some((b, r)) = self.client.get_block_with_receipts(block_number).await? {
+                    block = b;
+                    receipts = r;
+                }
+                match self.calculator.process_block(&block, &receipts) {
+                    Ok(processed) => processed,
+                    Err(e) => {
+                        warn!(
+                            "Block {} still incomplete after retry ({}), storing estimated metrics",
+                            block_number, e
+                        );
+                        self.calculator.process_block_lenient(&block, &receipts)?
+                    }
+                }
+            }
+        };
+
+        // Flag blocks committed while skipping others, so window consumers
+        // know aggregates over this range are sampled
+        block_metrics.sampled = self.sample_interval > 1;
 
         debug!(
             "Block {} processed: {} txs, {} total gas, {} DA bytes",
             block_number, tx_metrics.len(), block_metrics.total_gas, block_metrics.da_size
         );
 
-        // Store the metrics
-        self.store.add_block(block_metrics.clone(), tx_metrics).await;
+        // Record contract deployments, filling in deployed code sizes with
+        // a bounded number of concurrent fetches
+        let mut deployments = self.calculator.extract_deployments(&block, &receipts);
+        if self.fetch_code_sizes && !deployments.is_empty() {
+            let addresses: Vec<Address> =
+                deployments.iter().filter_map(|e| e.contract_address).collect();
+            let mut sizes: std::collections::HashMap<Address, u64> =
+                std::collections::HashMap::with_capacity(addresses.len());
+            for chunk in addresses.chunks(CODE_SIZE_CONCURRENCY) {
+                let fetched = futures::future::join_all(chunk.iter().map(|&address| {
+                    let fut = self.client.get_code(address);
+                    async move { (address, fut.await) }
+                }))
+                .await;
+                for (address, result) in fetched {
+                    match result {
+                        Ok(code) => {
+                            sizes.insert(address, code.len() as u64);
+                        }
+                        Err(e) => {
+                            warn!("Failed to fetch code for deployment {:?}: {}", address, e);
+                        }
+                    }
+                }
+            }
+
+            for event in &mut deployments {
+                if let Some(size) = event.contract_address.and_then(|a| sizes.get(&a)) {
+                    event.code_size = *size;
+                }
+            }
+        }
+        // Distinct target addresses, so subscribers can filter by contract
+        let mut to_addresses: Vec<Address> = tx_metrics.iter().filter_map(|t| t.to).collect();
+        to_addresses.sort();
+        to_addresses.dedup();
+
+        Ok(Some(PreparedBlock {
+            block_number,
+            block_metrics,
+            tx_metrics,
+            deployments,
+            to_addresses,
+        }))
+    }
+
+    /// Commit a prepared block: persist, store and broadcast (in block order)
+    ///
+    /// `reorged` marks blocks re-processed after a rollback so the broadcast
+    /// event tells subscribers to replace, not append
+    async fn commit_block(&self, prepared: PreparedBlock, reorged: bool) {
+        let PreparedBlock {
+            block_number,
+            block_metrics,
+            tx_metrics,
+            deployments,
+            to_addresses,
+        } = prepared;
+
+        // Persist to QuestDB before the metrics are handed to the store
+        if let Some(writer) = &self.writer {
+            if let Err(e) = writer.write_block(&block_metrics, &tx_metrics).await {
+                warn!("Failed to persist block {} to QuestDB: {}", block_number, e);
+            }
+            if let Err(e) = writer.write_deployments(&deployments).await {
+                warn!(
+                    "Failed to persist deployments for block {} to QuestDB: {}",
+                    block_number, e
+                );
+            }
+        }
+        // Live subscribers get deployments as they commit; a send with no
+        // receivers fails harmlessly
+        if let Some(tx) = &self.deployment_tx {
+            for event in &deployments {
+                let _ = tx.send(event.clone());
+            }
+        }
+        self.store.add_deployments(deployments).await;
+
+        // Store the metrics; a true return means we re-emitted a known block
+        let event_block = Arc::new(block_metrics.clone());
+        let replaced = self.store.add_block(block_metrics, tx_metrics).await;
+
+        // Score against the rolling baseline so subscribers get realtime
+        // anomaly alerts (near-limit blocks, DA or KV spikes)
+        let normalized = self.store.normalize_block(&event_block).await;
 
         // Broadcast to WebSocket subscribers
-        let _ = self.block_tx.send(BlockEvent { block: block_metrics });
+        let _ = self.block_tx.send(BlockEvent {
+            block: event_block,
+            replaced: replaced || reorged,
+            to_addresses,
+            anomalous: normalized.is_anomalous(),
+            anomaly_score: normalized.max_score(),
+        });
 
-        Ok(())
+        crate::telemetry::telemetry().record_block_processed();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use alloy_primitives::{Bytes, B256, U256};
+    use chrono::Utc;
+
+    use crate::rpc::{RawBlock, RawReceipt};
+
+    fn test_block(number: u64) -> BlockMetrics {
+        BlockMetrics {
+            block_number: number,
+            block_hash: B256::ZERO,
+            timestamp: Utc::now(),
+            tx_count: 0,
+            total_gas: 0,
+            compute_gas: 0,
+            storage_gas: 0,
+            tx_size: 0,
+            da_size: 0,
+            da_gas: 0,
+            data_size: 0,
+            kv_updates: 0,
+            state_growth: 0,
+            compression_ratio: 0.0,
+            base_fee_per_gas: None,
+            burned_fees: 0,
+            value_transferred: alloy_primitives::U256::ZERO,
+            tx_type_counts: Default::default(),
+            failed_tx_count: 0,
+            failed_gas: 0,
+            sampled: false,
+            receipts_complete: true,
+            mini_block_count: 1,
+            mini_block_gas: vec![0],
+            gas_limit: 30_000_000,
+        }
+    }
+
+    /// Serves canned blocks so the poller runs with no live endpoint
+    struct MockRpc {
+        blocks: HashMap<u64, RawBlock>,
+        receipts: HashMap<u64, Vec<RawReceipt>>,
+        latest: u64,
+    }
+
+    impl MockRpc {
+        fn with_blocks(numbers: std::ops::RangeInclusive<u64>) -> Self {
+            let latest = *numbers.end();
+            let blocks = numbers.map(|n| (n, raw_block(n))).collect();
+            Self {
+                blocks,
+                receipts: HashMap::new(),
+                latest,
+            }
+        }
+    }
+
+    fn raw_block(number: u64) -> RawBlock {
+        RawBlock {
+            number,
+            hash: B256::with_last_byte(number as u8),
+            parent_hash: B256::ZERO,
+            base_fee_per_gas: None,
+            gas_used: 21_000,
+            gas_limit: 30_000_000,
+            timestamp: 1_700_000_000 + number,
+            extra_data: Bytes::new(),
+            mini_block_count: 1,
+            mini_block_gas: vec![21_000],
+            transactions: vec![],
+        }
+    }
+
+    impl EthRpc for MockRpc {
+        async fn get_latest_block_number(&self) -> anyhow::Result<u64> {
+            Ok(self.latest)
+        }
+
+        async fn get_block_number(&self) -> anyhow::Result<u64> {
+            Ok(self.latest)
+        }
+
+        async fn get_block(&self, block_number: u64) -> anyhow::Result<Option<RawBlock>> {
+            Ok(self.blocks.get(&block_number).cloned())
+        }
+
+        async fn get_block_receipts(&self, block_number: u64) -> anyhow::Result<Vec<RawReceipt>> {
+            Ok(self.receipts.get(&block_number).cloned().unwrap_or_default())
+        }
+
+        async fn get_block_with_receipts(
+            &self,
+            block_number: u64,
+        ) -> anyhow::Result<Option<(RawBlock, Vec<RawReceipt>)>> {
+            let receipts = self.receipts.get(&block_number).cloned().unwrap_or_default();
+            Ok(self.blocks.get(&block_number).cloned().map(|b| (b, receipts)))
+        }
+
+        async fn get_code(&self, _address: Address) -> anyhow::Result<Bytes> {
+            Ok(Bytes::new())
+        }
+
+        async fn get_code_at(&self, _address: Address, _block_tag: &str) -> anyhow::Result<Bytes> {
+            Ok(Bytes::new())
+        }
+
+        async fn get_balance_at(
+            &self,
+            _address: Address,
+            _block_tag: &str,
+        ) -> anyhow::Result<U256> {
+            Ok(U256::ZERO)
+        }
+
+        async fn get_nonce_at(&self, _address: Address, _block_tag: &str) -> anyhow::Result<u64> {
+            Ok(0)
+        }
+
+        async fn get_storage_at_block(
+            &self,
+            _address: Address,
+            _index: U256,
+            _block_tag: &str,
+        ) -> anyhow::Result<U256> {
+            Ok(U256::ZERO)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_poller_processes_canned_blocks_offline() {
+        let store = MetricsStore::new();
+        let (block_tx, mut block_rx) = broadcast::channel::<BlockEvent>(16);
+
+        let poller = BlockPoller::new(
+            MockRpc::with_blocks(1..=3),
+            store.clone(),
+            0,
+            Duration::from_millis(10),
+            block_tx,
+        )
+        .with_block_range(Some(1), Some(3));
+
+        poller.poll_once().await.unwrap();
+
+        assert_eq!(store.last_block_number().await, 3);
+        let stored = store.get_block(2).await.unwrap();
+        assert_eq!(stored.block_hash, B256::with_last_byte(2));
+
+        // Each processed block was broadcast in order
+        for expected in 1..=3 {
+            assert_eq!(block_rx.recv().await.unwrap().block.block_number, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_catchup_mode_has_hysteresis() {
+        let store = MetricsStore::new();
+        let (block_tx, _rx) = broadcast::channel::<BlockEvent>(8);
+        let poller = BlockPoller::new(
+            MockRpc::with_blocks(1..=1),
+            store,
+            0,
+            Duration::from_millis(10),
+            block_tx,
+        );
+        let is_catching_up =
+            |p: &BlockPoller<MockRpc>| p.catching_up.load(std::sync::atomic::Ordering::Relaxed);
+
+        assert!(!is_catching_up(&poller));
+
+        // Below the enter threshold nothing changes
+        poller.update_catchup(CATCHUP_ENTER_LAG);
+        assert!(!is_catching_up(&poller));
+
+        poller.update_catchup(CATCHUP_ENTER_LAG + 1);
+        assert!(is_catching_up(&poller));
+
+        // Between the thresholds the mode holds rather than flapping
+        poller.update_catchup(CATCHUP_EXIT_LAG + 1);
+        assert!(is_catching_up(&poller));
+
+        poller.update_catchup(CATCHUP_EXIT_LAG);
+        assert!(!is_catching_up(&poller));
+    }
+
+    #[tokio::test]
+    async fn test_sampling_processes_every_nth_block() {
+        let store = MetricsStore::new();
+        let (block_tx, _block_rx) = broadcast::channel::<BlockEvent>(16);
+
+        let poller = BlockPoller::new(
+            MockRpc::with_blocks(1..=10),
+            store.clone(),
+            0,
+            Duration::from_millis(10),
+            block_tx,
+        )
+        .with_block_range(Some(1), Some(10))
+        .with_sampling(3);
+
+        poller.poll_once().await.unwrap();
+
+        // Every block number divisible by 3 is committed, the rest skipped
+        assert_eq!(store.last_block_number().await, 9);
+        for n in [3u64, 6, 9] {
+            let block = store.get_block(n).await.unwrap();
+            assert!(block.sampled, "sampled blocks carry the flag");
+        }
+        for n in [1u64, 2, 4, 10] {
+            assert!(store.get_block(n).await.is_none(), "block {n} skipped");
+        }
+
+        // Backfill completes even though the range end is not a sample point
+        assert!(poller.shutdown.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_deployments_are_broadcast_on_commit() {
+        let store = MetricsStore::new();
+        let (block_tx, _block_rx) = broadcast::channel::<BlockEvent>(16);
+        let (deployment_tx, mut deployment_rx) = broadcast::channel(16);
+
+        // Block 1 carries one contract creation with a successful receipt
+        let mut rpc = MockRpc::with_blocks(1..=1);
+        let creation = crate::rpc::RawTransaction {
+            hash: B256::with_last_byte(0xaa),
+            from: Address::with_last_byte(1),
+            to: None,
+            input: Bytes::new(),
+            gas: 500_000,
+            tx_type: 2,
+            nonce: 0,
+            value: U256::ZERO,
+            gas_price: None,
+            max_fee_per_gas: Some(1_000_000_000),
+            max_priority_fee_per_gas: Some(1_000_000),
+            chain_id: Some(6342),
+            v: 0,
+            r: U256::from(1),
+            s: U256::from(1),
+            access_list: vec![],
+            blob_versioned_hashes: vec![],
+            max_fee_per_blob_gas: None,
+        };
+        let receipt = RawReceipt {
+            transaction_hash: creation.hash,
+            gas_used: 400_000,
+            status: true,
+            contract_address: Some(Address::with_last_byte(0xcc)),
+            from: creation.from,
+            effective_gas_price: Some(1_000_000_000),
+        };
+        let block = rpc.blocks.get_mut(&1).unwrap();
+        block.transactions.push(creation);
+        rpc.receipts.insert(1, vec![receipt]);
+
+        let poller = BlockPoller::new(rpc, store, 0, Duration::from_millis(10), block_tx)
+            .with_block_range(Some(1), Some(1))
+            .with_deployment_channel(deployment_tx);
+
+        poller.poll_once().await.unwrap();
+
+        let event = deployment_rx.recv().await.unwrap();
+        assert_eq!(event.block_number, 1);
+        assert_eq!(event.contract_address, Some(Address::with_last_byte(0xcc)));
+        assert_eq!(event.gas_used, 400_000);
+    }
+
+    #[tokio::test]
+    async fn test_rebroadcast_block_is_flagged_replaced() {
+        let store = MetricsStore::new();
+        let (block_tx, mut block_rx) = broadcast::channel::<BlockEvent>(8);
+
+        // Same block processed twice, e.g. a reorg or poller retry
+        for _ in 0..2 {
+            let replaced = store.add_block(test_block(42), vec![]).await;
+            block_tx
+                .send(BlockEvent {
+                    block: Arc::new(test_block(42)),
+                    replaced,
+                    to_addresses: vec![],
+                    anomalous: false,
+                    anomaly_score: 0.0,
+                })
+                .unwrap();
+        }
+
+        let first = block_rx.recv().await.unwrap();
+        let second = block_rx.recv().await.unwrap();
+
+        assert!(!first.replaced, "first broadcast should not be flagged");
+        assert!(second.replaced, "second broadcast should be flagged");
     }
 }
@@ -0,0 +1,143 @@
+//! RLP size arithmetic used by `RawTransaction::encoded_size`
+//!
+//! These compute encoded lengths without materializing the encoding; the
+//! tx_size and DA metrics depend on them, so they're verified against
+//! alloy-rlp's reference encoder in the tests below.
+
+use alloy_primitives::U256;
+
+/// Encoded RLP size for a u64
+pub fn rlp_uint_size(val: u64) -> u64 {
+    if val < 128 {
+        1
+    } else {
+        1 + ((64 - val.leading_zeros()) as u64).div_ceil(8)
+    }
+}
+
+/// Encoded RLP size for a u128
+pub fn rlp_u128_size(val: u128) -> u64 {
+    if val < 128 {
+        1
+    } else {
+        1 + ((128 - val.leading_zeros()) as u64).div_ceil(8)
+    }
+}
+
+/// Encoded RLP size for a U256
+pub fn rlp_u256_size(val: U256) -> u64 {
+    if val.is_zero() {
+        1
+    } else {
+        let bytes = val.to_be_bytes::<32>();
+        let leading_zeros = bytes.iter().take_while(|&&b| b == 0).count();
+        let significant_bytes = 32 - leading_zeros;
+        if significant_bytes == 1 && bytes[31] < 128 {
+            1
+        } else {
+            1 + significant_bytes as u64
+        }
+    }
+}
+
+/// Size of the RLP length prefix for a payload of `len` bytes
+pub fn rlp_length_prefix_size(len: u64) -> u64 {
+    if len < 56 {
+        1
+    } else {
+        1 + ((64 - len.leading_zeros()) as u64).div_ceil(8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_rlp::Encodable;
+
+    /// Boundary values around each byte-width transition
+    const UINT_BOUNDARIES: &[u64] = &[
+        0,
+        1,
+        127,
+        128,
+        255,
+        256,
+        (1 << 16) - 1,
+        1 << 16,
+        (1 << 24) - 1,
+        1 << 24,
+        (1 << 32) - 1,
+        1 << 32,
+        u64::MAX,
+    ];
+
+    #[test]
+    fn test_uint_size_matches_reference_encoder() {
+        for &val in UINT_BOUNDARIES {
+            assert_eq!(
+                rlp_uint_size(val),
+                val.length() as u64,
+                "rlp_uint_size({val})"
+            );
+        }
+    }
+
+    #[test]
+    fn test_u128_size_matches_reference_encoder() {
+        let boundaries = UINT_BOUNDARIES
+            .iter()
+            .map(|&v| v as u128)
+            .chain([(1u128 << 64), (1 << 64) + 1, u128::MAX]);
+        for val in boundaries {
+            assert_eq!(
+                rlp_u128_size(val),
+                val.length() as u64,
+                "rlp_u128_size({val})"
+            );
+        }
+    }
+
+    #[test]
+    fn test_u256_size_matches_reference_encoder() {
+        let boundaries = UINT_BOUNDARIES
+            .iter()
+            .map(|&v| U256::from(v))
+            .chain([
+                U256::from(u128::MAX),
+                U256::from(u128::MAX) + U256::from(1),
+                U256::from(1) << 255,
+                U256::MAX,
+            ]);
+        for val in boundaries {
+            assert_eq!(
+                rlp_u256_size(val),
+                val.length() as u64,
+                "rlp_u256_size({val})"
+            );
+        }
+    }
+
+    /// The single-significant-byte branch: values below 128 encode as one
+    /// byte, 128 itself needs a length prefix
+    #[test]
+    fn test_u256_single_byte_branch() {
+        assert_eq!(rlp_u256_size(U256::from(127)), 1);
+        assert_eq!(rlp_u256_size(U256::from(128)), 2);
+    }
+
+    #[test]
+    fn test_length_prefix_size_matches_reference_header() {
+        let boundaries = [0, 1, 55, 56, 255, 256, (1 << 16) - 1, 1 << 16, 1 << 32];
+        for len in boundaries {
+            let header = alloy_rlp::Header {
+                list: false,
+                payload_length: len as usize,
+            };
+            assert_eq!(
+                rlp_length_prefix_size(len),
+                header.length() as u64,
+                "rlp_length_prefix_size({len})"
+            );
+        }
+    }
+}
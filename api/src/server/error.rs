@@ -0,0 +1,80 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+
+/// Machine-readable error returned by every handler
+///
+/// Serializes as `{ "error": { "code": "...", "message": "..." } }` so
+/// clients can branch on `code` without parsing free-form text.
+#[derive(Debug)]
+pub enum ApiError {
+    /// The requested resource isn't in the store (404)
+    NotFound(String),
+    /// The request parameters don't make sense (400)
+    BadRequest(String),
+    /// The resource existed but has been evicted from retention (410)
+    Gone(String),
+    /// The upstream RPC failed while serving the request (502)
+    Upstream(String),
+    /// A backing service this route needs isn't configured (503)
+    Unavailable(String),
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: ErrorDetail<'a>,
+}
+
+#[derive(Serialize)]
+struct ErrorDetail<'a> {
+    code: &'a str,
+    message: &'a str,
+}
+
+impl ApiError {
+    fn status(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Gone(_) => StatusCode::GONE,
+            ApiError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            ApiError::NotFound(_) => "not_found",
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Gone(_) => "gone",
+            ApiError::Upstream(_) => "upstream_error",
+            ApiError::Unavailable(_) => "unavailable",
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            ApiError::NotFound(m)
+            | ApiError::BadRequest(m)
+            | ApiError::Gone(m)
+            | ApiError::Upstream(m)
+            | ApiError::Unavailable(m) => m,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.status(),
+            Json(ErrorBody {
+                error: ErrorDetail {
+                    code: self.code(),
+                    message: self.message(),
+                },
+            }),
+        )
+            .into_response()
+    }
+}
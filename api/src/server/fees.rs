@@ -0,0 +1,146 @@
+// Fee oracle: wraps eth_feeHistory behind a short-lived cache so the
+// dashboard's gas-price dial doesn't cost one RPC round-trip per viewer.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::rpc::{FeeHistory, MegaEthClient};
+
+/// Blocks sampled per `eth_feeHistory` call
+const FEE_HISTORY_BLOCKS: u64 = 20;
+
+/// Percentile of per-block priority fees to request; the median ignores both
+/// bribe-level tips and zero-tip system transactions
+const PRIORITY_FEE_PERCENTILE: f64 = 50.0;
+
+/// How long a fee snapshot stays fresh
+///
+/// Fees drift over seconds, not per block, so even a short TTL collapses
+/// per-viewer polling into one upstream call.
+const FEE_CACHE_TTL: Duration = Duration::from_secs(2);
+
+/// Fee snapshot served at `/viz/fees`
+#[derive(Debug, Clone, Serialize)]
+pub struct FeesData {
+    /// Projected base fee for the next block, in wei
+    pub base_fee_per_gas: u128,
+    /// Median of recent per-block median priority fees, in wei
+    pub suggested_priority_fee_per_gas: u128,
+    /// Mean gas used / gas limit across the sampled blocks (0.0-1.0)
+    pub mean_gas_used_ratio: f64,
+    /// First block in the sample
+    pub oldest_block: u64,
+}
+
+/// Serves current fee levels, refetching at most once per [`FEE_CACHE_TTL`]
+pub struct FeeOracle {
+    client: MegaEthClient,
+    cache: Mutex<Option<(Instant, FeesData)>>,
+}
+
+impl FeeOracle {
+    pub fn new(client: MegaEthClient) -> Self {
+        Self {
+            client,
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Circuit-breaker state of the underlying RPC client, for /health
+    pub fn circuit_state(&self) -> &'static str {
+        self.client.circuit_state()
+    }
+
+    /// Current fee snapshot, served from cache when fresh
+    pub async fn fees(&self) -> Result<FeesData> {
+        {
+            let cache = self.cache.lock().await;
+            if let Some((fetched, data)) = cache.as_ref() {
+                if fetched.elapsed() < FEE_CACHE_TTL {
+                    return Ok(data.clone());
+                }
+            }
+        }
+
+        let history = self
+            .client
+            .get_fee_history(FEE_HISTORY_BLOCKS, None, &[PRIORITY_FEE_PERCENTILE])
+            .await?;
+        let data = derive_fees(&history);
+        *self.cache.lock().await = Some((Instant::now(), data.clone()));
+        Ok(data)
+    }
+}
+
+/// Reduce a fee history to the dial's numbers
+///
+/// Split out from the oracle so the derivation is testable without RPC.
+fn derive_fees(history: &FeeHistory) -> FeesData {
+    // The last entry is the node's projection for the next block
+    let base_fee_per_gas = history.base_fee_per_gas.last().copied().unwrap_or(0);
+
+    // Median of the per-block medians resists single-block spikes
+    let mut rewards: Vec<u128> = history
+        .reward
+        .iter()
+        .filter_map(|per_block| per_block.first().copied())
+        .collect();
+    rewards.sort_unstable();
+    let suggested_priority_fee_per_gas = if rewards.is_empty() {
+        0
+    } else {
+        rewards[rewards.len() / 2]
+    };
+
+    let mean_gas_used_ratio = if history.gas_used_ratio.is_empty() {
+        0.0
+    } else {
+        history.gas_used_ratio.iter().sum::<f64>() / history.gas_used_ratio.len() as f64
+    };
+
+    FeesData {
+        base_fee_per_gas,
+        suggested_priority_fee_per_gas,
+        mean_gas_used_ratio,
+        oldest_block: history.oldest_block,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_fees_takes_projection_and_median() {
+        let history = FeeHistory {
+            oldest_block: 100,
+            base_fee_per_gas: vec![1_000, 1_100, 1_200],
+            gas_used_ratio: vec![0.25, 0.75],
+            reward: vec![vec![5_000], vec![50_000], vec![7_000]],
+        };
+
+        let fees = derive_fees(&history);
+        assert_eq!(fees.base_fee_per_gas, 1_200);
+        // Median of [5_000, 7_000, 50_000] ignores the spike
+        assert_eq!(fees.suggested_priority_fee_per_gas, 7_000);
+        assert_eq!(fees.mean_gas_used_ratio, 0.5);
+        assert_eq!(fees.oldest_block, 100);
+    }
+
+    #[test]
+    fn test_derive_fees_handles_empty_history() {
+        let history = FeeHistory {
+            oldest_block: 0,
+            base_fee_per_gas: vec![],
+            gas_used_ratio: vec![],
+            reward: vec![],
+        };
+
+        let fees = derive_fees(&history);
+        assert_eq!(fees.base_fee_per_gas, 0);
+        assert_eq!(fees.suggested_priority_fee_per_gas, 0);
+        assert_eq!(fees.mean_gas_used_ratio, 0.0);
+    }
+}
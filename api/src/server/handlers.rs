@@ -4,21 +4,50 @@ use axum::{
         Path, Query, State,
     },
     http::StatusCode,
-    response::IntoResponse,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
     Json,
 };
-use futures::{SinkExt, StreamExt};
+use futures::{SinkExt, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
+use tracing::debug;
+
+use crate::metrics::{
+    BlockMetrics, DeploymentStats, MetricHistogram, MetricsStore, MiniBlockGasStats, Sparkline,
+    SystemActivityStats, TopAddressesStats, TransactionMetrics, WindowReference, WindowStats,
+};
+use crate::questdb::{
+    BlockHistoryResponse, DeploymentDetail, DeploymentHeatmapCell, DeploymentHeatmapView,
+};
+use crate::rpc::{BlockEvent, TentativeBlockEvent};
 
-use crate::metrics::{BlockMetrics, MetricsStore, WindowStats};
-use crate::rpc::BlockEvent;
+use super::error::ApiError;
 
 /// Application state shared across handlers
 pub struct AppState {
     pub store: Arc<MetricsStore>,
     pub block_tx: broadcast::Sender<BlockEvent>,
+    /// Unconfirmed head-of-chain stream (see `/ws/blocks?mode=head`)
+    pub tentative_tx: broadcast::Sender<TentativeBlockEvent>,
+    /// Process-wide operational counters served at /metrics
+    pub telemetry: &'static crate::telemetry::Telemetry,
+    /// Fee oracle backing /viz/fees, when this process has an RPC client
+    pub fees: Option<super::fees::FeeOracle>,
+    /// Contract identification behind /contracts/{address}/identify; the
+    /// identifier carries its own TTL cache
+    pub contracts: Option<crate::contract_identifier::ContractIdentifier>,
+    /// Historical reader for `/stats/compare` baselines older than the
+    /// in-memory retention; None when QuestDB isn't configured
+    pub questdb: Option<Arc<crate::questdb::QuestDBReader>>,
+    /// Replay cache, when this process runs the replay engine
+    #[cfg(feature = "replay")]
+    pub cache_db: Option<crate::replay::SmartCacheDB>,
 }
 
 /// Query parameters for window stats
@@ -27,12 +56,431 @@ pub struct WindowQuery {
     /// Window size in seconds (default: 60)
     #[serde(default = "default_window")]
     pub seconds: u64,
+    /// Measure the window back from the latest block's timestamp ("head",
+    /// default) or the server wall clock ("wall")
+    #[serde(default)]
+    pub reference: WindowReference,
 }
 
 fn default_window() -> u64 {
     60
 }
 
+/// Query parameters for `/stats/compare`
+#[derive(Debug, Deserialize)]
+pub struct CompareQuery {
+    /// Length of the recent window in seconds (default: 300)
+    #[serde(default = "default_compare_window")]
+    pub recent_seconds: u64,
+    /// Length of the baseline window in seconds (default: 300)
+    #[serde(default = "default_compare_window")]
+    pub baseline_seconds: u64,
+    /// How far back the baseline window ends, in seconds (default: 3600)
+    #[serde(default = "default_baseline_offset")]
+    pub baseline_offset: u64,
+}
+
+fn default_compare_window() -> u64 {
+    300
+}
+
+fn default_baseline_offset() -> u64 {
+    3600
+}
+
+/// One side of a window comparison, tagged with where it was aggregated
+#[derive(Debug, Serialize)]
+pub struct ComparedWindow {
+    /// "memory" or "questdb"
+    pub source: &'static str,
+    pub stats: WindowStats,
+}
+
+/// Percentage changes from baseline to recent; None where the baseline is
+/// zero and a percentage would be meaningless
+#[derive(Debug, Serialize)]
+pub struct WindowDeltas {
+    pub block_count: Option<f64>,
+    pub tx_count: Option<f64>,
+    pub mean_total_gas: Option<f64>,
+    pub mean_compute_gas: Option<f64>,
+    pub mean_storage_gas: Option<f64>,
+    pub mean_tx_size: Option<f64>,
+    pub mean_da_size: Option<f64>,
+    pub mean_data_size: Option<f64>,
+    pub mean_kv_updates: Option<f64>,
+    pub mean_state_growth: Option<f64>,
+    pub mean_compression_ratio: Option<f64>,
+    pub mean_burned_fees: Option<f64>,
+}
+
+/// Response for `/stats/compare`
+#[derive(Debug, Serialize)]
+pub struct WindowComparison {
+    pub recent: ComparedWindow,
+    pub baseline: ComparedWindow,
+    pub deltas: WindowDeltas,
+}
+
+fn pct_delta(recent: f64, baseline: f64) -> Option<f64> {
+    if baseline == 0.0 {
+        None
+    } else {
+        Some((recent - baseline) / baseline * 100.0)
+    }
+}
+
+fn window_deltas(recent: &WindowStats, baseline: &WindowStats) -> WindowDeltas {
+    WindowDeltas {
+        block_count: pct_delta(recent.block_count as f64, baseline.block_count as f64),
+        tx_count: pct_delta(recent.tx_count as f64, baseline.tx_count as f64),
+        mean_total_gas: pct_delta(recent.mean_total_gas, baseline.mean_total_gas),
+        mean_compute_gas: pct_delta(recent.mean_compute_gas, baseline.mean_compute_gas),
+        mean_storage_gas: pct_delta(recent.mean_storage_gas, baseline.mean_storage_gas),
+        mean_tx_size: pct_delta(recent.mean_tx_size, baseline.mean_tx_size),
+        mean_da_size: pct_delta(recent.mean_da_size, baseline.mean_da_size),
+        mean_data_size: pct_delta(recent.mean_data_size, baseline.mean_data_size),
+        mean_kv_updates: pct_delta(recent.mean_kv_updates, baseline.mean_kv_updates),
+        mean_state_growth: pct_delta(recent.mean_state_growth, baseline.mean_state_growth),
+        mean_compression_ratio: pct_delta(
+            recent.mean_compression_ratio,
+            baseline.mean_compression_ratio,
+        ),
+        mean_burned_fees: pct_delta(recent.mean_burned_fees, baseline.mean_burned_fees),
+    }
+}
+
+/// One row of the `/stats/tx-types` breakdown
+#[derive(Debug, Serialize)]
+pub struct TxTypeCount {
+    /// EIP-2718 transaction type byte
+    pub tx_type: u8,
+    /// Human-readable name for the type
+    pub label: &'static str,
+    pub count: u64,
+    /// Fraction of the window's transactions (0.0-1.0)
+    pub share: f64,
+}
+
+/// Response for `/stats/tx-types`
+#[derive(Debug, Serialize)]
+pub struct TxTypeBreakdownResponse {
+    pub window_start: chrono::DateTime<chrono::Utc>,
+    pub window_end: chrono::DateTime<chrono::Utc>,
+    pub tx_count: u64,
+    pub types: Vec<TxTypeCount>,
+}
+
+fn tx_type_label(tx_type: u8) -> &'static str {
+    match tx_type {
+        0 => "legacy",
+        1 => "eip2930",
+        2 => "eip1559",
+        3 => "eip4844",
+        126 => "deposit",
+        _ => "unknown",
+    }
+}
+
+/// Get the transaction-type mix over a window
+///
+/// Deposit transactions (type 126) are DA-exempt, so their share directly
+/// affects how the window's DA costs should be read.
+pub async fn get_tx_type_breakdown(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<WindowQuery>,
+) -> Json<TxTypeBreakdownResponse> {
+    let stats = state
+        .store
+        .get_window_stats(query.seconds, query.reference)
+        .await;
+
+    let total: u64 = stats.tx_type_counts.values().sum();
+    let types = stats
+        .tx_type_counts
+        .iter()
+        .map(|(&tx_type, &count)| TxTypeCount {
+            tx_type,
+            label: tx_type_label(tx_type),
+            count,
+            share: if total > 0 {
+                count as f64 / total as f64
+            } else {
+                0.0
+            },
+        })
+        .collect();
+
+    Json(TxTypeBreakdownResponse {
+        window_start: stats.window_start,
+        window_end: stats.window_end,
+        tx_count: stats.tx_count,
+        types,
+    })
+}
+
+/// Query parameters for `/stats/percentile-audit`
+#[derive(Debug, Deserialize)]
+pub struct PercentileAuditQuery {
+    /// Per-transaction metric, named after the transaction_metrics column
+    /// (default: total_gas)
+    #[serde(default = "default_audit_metric")]
+    pub metric: String,
+    /// Window size in seconds (default: 60)
+    #[serde(default = "default_window")]
+    pub seconds: u64,
+    /// Percentile to audit, 1-99 (default: 95)
+    #[serde(default = "default_audit_percentile")]
+    pub percentile: usize,
+}
+
+fn default_audit_metric() -> String {
+    "total_gas".to_string()
+}
+
+fn default_audit_percentile() -> usize {
+    95
+}
+
+/// Response for `/stats/percentile-audit`
+#[derive(Debug, Serialize)]
+pub struct PercentileAuditResponse {
+    pub metric: String,
+    pub percentile: usize,
+    pub window_start: chrono::DateTime<chrono::Utc>,
+    pub window_end: chrono::DateTime<chrono::Utc>,
+    /// Exact percentile over the in-memory transactions
+    pub exact_value: u64,
+    /// How many in-memory samples the exact value covers
+    pub sample_count: usize,
+    /// QuestDB's approx_percentile over the same window; absent when
+    /// QuestDB isn't configured or the window holds no rows there
+    pub approx_value: Option<f64>,
+    /// (approx - exact) / exact; absent without an approx value or when
+    /// the exact value is zero
+    pub relative_error: Option<f64>,
+}
+
+/// Extractor for a per-transaction metric, keyed by its column name
+///
+/// Doubles as the whitelist for what may be interpolated into the QuestDB
+/// approx_percentile query.
+fn audit_metric_extractor(metric: &str) -> Option<fn(&TransactionMetrics) -> u64> {
+    Some(match metric {
+        "total_gas" => |t| t.total_gas,
+        "compute_gas" => |t| t.compute_gas,
+        "storage_gas" => |t| t.storage_gas,
+        "tx_size" => |t| t.tx_size,
+        "da_size" => |t| t.da_size,
+        "data_size" => |t| t.data_size,
+        "kv_updates" => |t| t.kv_updates,
+        "state_growth" => |t| t.state_growth,
+        _ => return None,
+    })
+}
+
+/// Compare the exact in-memory percentile against QuestDB's approximation
+///
+/// Diagnostics for operators: the long-range QuestDB numbers come from
+/// approx_percentile, and this shows how far they drift from the exact
+/// percentile over the same recent window.
+pub async fn get_percentile_audit(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PercentileAuditQuery>,
+) -> Result<Json<PercentileAuditResponse>, ApiError> {
+    if query.seconds == 0 {
+        return Err(ApiError::BadRequest(
+            "Window length must be positive".to_string(),
+        ));
+    }
+    if !(1..=99).contains(&query.percentile) {
+        return Err(ApiError::BadRequest(
+            "Percentile must be between 1 and 99".to_string(),
+        ));
+    }
+    let Some(extract) = audit_metric_extractor(&query.metric) else {
+        return Err(ApiError::BadRequest(format!(
+            "Unknown metric '{}'",
+            query.metric
+        )));
+    };
+
+    let window_end = state
+        .store
+        .latest_timestamp()
+        .await
+        .unwrap_or_else(chrono::Utc::now);
+    let window_start = window_end - chrono::Duration::seconds(query.seconds as i64);
+
+    let (exact_value, sample_count) = state
+        .store
+        .get_tx_percentile_between(window_start, window_end, query.percentile, extract)
+        .await;
+
+    let approx_value = match &state.questdb {
+        Some(questdb) => questdb
+            .approx_tx_percentile(
+                &query.metric,
+                window_start,
+                window_end,
+                query.percentile as f64 / 100.0,
+            )
+            .await
+            .map_err(|e| ApiError::Upstream(format!("QuestDB query failed: {}", e)))?,
+        None => None,
+    };
+
+    let relative_error = approx_value.and_then(|approx| {
+        if exact_value > 0 {
+            Some((approx - exact_value as f64) / exact_value as f64)
+        } else {
+            None
+        }
+    });
+
+    Ok(Json(PercentileAuditResponse {
+        metric: query.metric,
+        percentile: query.percentile,
+        window_start,
+        window_end,
+        exact_value,
+        sample_count,
+        approx_value,
+        relative_error,
+    }))
+}
+
+/// Windows accepted by `/history/blocks`, matching the QuestDB SAMPLE BY
+/// intervals in `get_block_history`
+const HISTORY_WINDOWS: [&str; 7] = ["1m", "5m", "15m", "1h", "6h", "24h", "7d"];
+
+/// Query parameters for `/history/blocks`
+#[derive(Debug, Deserialize)]
+pub struct BlockHistoryQuery {
+    /// Bucket size, which also picks the lookback period (default: 1h)
+    #[serde(default = "default_history_window")]
+    pub window: String,
+    /// Also scan the last 2 hours for holes in the block sequence
+    #[serde(default)]
+    pub gaps: bool,
+}
+
+fn default_history_window() -> String {
+    "1h".to_string()
+}
+
+/// Get long-range block production history from QuestDB
+///
+/// Reaches further back than the in-memory retention; the window picks
+/// both the bucket size and the lookback period.
+pub async fn get_block_history(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<BlockHistoryQuery>,
+) -> Result<Json<BlockHistoryResponse>, ApiError> {
+    if !HISTORY_WINDOWS.contains(&query.window.as_str()) {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid window '{}'; expected one of {}",
+            query.window,
+            HISTORY_WINDOWS.join(", ")
+        )));
+    }
+
+    let questdb = require_questdb(&state)?;
+    let history = questdb
+        .get_block_history(&query.window, query.gaps)
+        .await
+        .map_err(|e| ApiError::Upstream(format!("QuestDB query failed: {}", e)))?;
+
+    Ok(Json(history))
+}
+
+/// Query parameters for `/deployments/heatmap`
+#[derive(Debug, Deserialize)]
+pub struct DeploymentHeatmapQuery {
+    /// Bucket granularity: daily, weekly or monthly (default: daily)
+    #[serde(default = "default_heatmap_view")]
+    pub view: DeploymentHeatmapView,
+}
+
+fn default_heatmap_view() -> DeploymentHeatmapView {
+    DeploymentHeatmapView::Daily
+}
+
+/// Response for `/deployments/heatmap`
+#[derive(Debug, Serialize)]
+pub struct DeploymentHeatmapResponse {
+    pub view: DeploymentHeatmapView,
+    pub cells: Vec<DeploymentHeatmapCell>,
+}
+
+/// Query parameters for `/deployments/details`
+///
+/// `start`/`end` are RFC 3339 timestamps, typically a heatmap cell's
+/// bucket boundaries.
+#[derive(Debug, Deserialize)]
+pub struct DeploymentDetailsQuery {
+    pub start: chrono::DateTime<chrono::Utc>,
+    pub end: chrono::DateTime<chrono::Utc>,
+}
+
+/// Response for `/deployments/details`
+#[derive(Debug, Serialize)]
+pub struct DeploymentDetailsResponse {
+    pub start: chrono::DateTime<chrono::Utc>,
+    pub end: chrono::DateTime<chrono::Utc>,
+    pub count: usize,
+    pub deployments: Vec<DeploymentDetail>,
+}
+
+/// The QuestDB reader, or 503 when this process runs without one
+fn require_questdb(state: &AppState) -> Result<&crate::questdb::QuestDBReader, ApiError> {
+    state.questdb.as_deref().ok_or_else(|| {
+        ApiError::Unavailable("QuestDB is not configured on this instance".to_string())
+    })
+}
+
+/// Get the contract deployment heatmap from QuestDB
+pub async fn get_deployment_heatmap(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DeploymentHeatmapQuery>,
+) -> Result<Json<DeploymentHeatmapResponse>, ApiError> {
+    let questdb = require_questdb(&state)?;
+    let cells = questdb
+        .get_deployment_heatmap(query.view)
+        .await
+        .map_err(|e| ApiError::Upstream(format!("QuestDB query failed: {}", e)))?;
+
+    Ok(Json(DeploymentHeatmapResponse {
+        view: query.view,
+        cells,
+    }))
+}
+
+/// Get individual deployments within a heatmap cell's time bucket
+pub async fn get_deployment_details(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DeploymentDetailsQuery>,
+) -> Result<Json<DeploymentDetailsResponse>, ApiError> {
+    if query.start >= query.end {
+        return Err(ApiError::BadRequest(
+            "start must be before end".to_string(),
+        ));
+    }
+
+    let questdb = require_questdb(&state)?;
+    let deployments = questdb
+        .get_deployment_details(query.start, query.end)
+        .await
+        .map_err(|e| ApiError::Upstream(format!("QuestDB query failed: {}", e)))?;
+
+    Ok(Json(DeploymentDetailsResponse {
+        start: query.start,
+        end: query.end,
+        count: deployments.len(),
+        deployments,
+    }))
+}
+
 /// Query parameters for recent blocks
 #[derive(Debug, Deserialize)]
 pub struct RecentBlocksQuery {
@@ -45,19 +493,220 @@ fn default_count() -> usize {
     100
 }
 
+/// Maximum block span accepted by /blocks/range
+const MAX_RANGE_SPAN: u64 = 1000;
+
+/// Query parameters for a block range
+#[derive(Debug, Deserialize)]
+pub struct BlockRangeQuery {
+    /// First block number (inclusive)
+    pub start: u64,
+    /// Last block number (inclusive)
+    pub end: u64,
+    /// Maximum number of blocks to return per page (default: 100)
+    #[serde(default = "default_count")]
+    pub limit: usize,
+}
+
+/// A page of blocks from a range query
+#[derive(Serialize)]
+pub struct BlockRangeResponse {
+    pub blocks: Vec<BlockMetrics>,
+    /// Block number to use as `start` for the next page, if the range was truncated
+    pub next_cursor: Option<u64>,
+}
+
+/// How stale the last processed block may be before /health degrades
+const DEFAULT_HEALTH_MAX_BLOCK_AGE_SECS: u64 = 30;
+
 /// Health check response
 #[derive(Serialize)]
 pub struct HealthResponse {
+    /// "ok", or "degraded" when the poller has stalled
     pub status: String,
     pub last_block: u64,
+    /// Seconds since the last block was processed (absent before the first)
+    pub last_block_age_secs: Option<u64>,
+    /// How far the store trails the chain head the poller last saw
+    pub blocks_behind: u64,
+    /// In-memory retention cap, in blocks
+    pub retention_blocks: u64,
+    /// RPC circuit-breaker state ("closed", "open" or "half_open"); absent
+    /// when this process has no RPC client
+    pub rpc_circuit: Option<&'static str>,
+    /// True while the poller is in batch catch-up mode (far behind the head)
+    pub catching_up: bool,
+}
+
+/// Effective window configuration, for debugging
+#[derive(Serialize)]
+pub struct DebugConfigResponse {
+    /// Short window used for responsive aggregation stats (seconds)
+    pub stats_window_secs: u64,
+    /// Long window normalization baselines are computed against (seconds)
+    pub baseline_window_secs: u64,
 }
 
 /// Get health status
-pub async fn health(State(state): State<Arc<AppState>>) -> Json<HealthResponse> {
+///
+/// Returns 503 with status "degraded" when no block has been processed
+/// within HEALTH_MAX_BLOCK_AGE_SECS, so load balancers drop stuck instances.
+pub async fn health(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let last_block = state.store.last_block_number().await;
-    Json(HealthResponse {
-        status: "ok".to_string(),
-        last_block,
+    let age = state.store.last_block_age().await;
+    let blocks_behind = state.store.latest_head().await.saturating_sub(last_block);
+
+    let max_age_secs = std::env::var("HEALTH_MAX_BLOCK_AGE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_HEALTH_MAX_BLOCK_AGE_SECS);
+
+    let alive = age.map(|a| a.as_secs() < max_age_secs).unwrap_or(false);
+    let (status, code) = if alive {
+        ("ok", StatusCode::OK)
+    } else {
+        ("degraded", StatusCode::SERVICE_UNAVAILABLE)
+    };
+
+    (
+        code,
+        Json(HealthResponse {
+            status: status.to_string(),
+            last_block,
+            last_block_age_secs: age.map(|a| a.as_secs()),
+            blocks_behind,
+            retention_blocks: state.store.max_blocks() as u64,
+            rpc_circuit: state.fees.as_ref().map(|f| f.circuit_state()),
+            catching_up: state.telemetry.is_catching_up(),
+        }),
+    )
+}
+
+/// Query parameters for /stats/histogram
+#[derive(Debug, Deserialize)]
+pub struct HistogramQuery {
+    /// Window size in seconds (default: 60)
+    #[serde(default = "default_window")]
+    pub seconds: u64,
+    /// Metric to bin: gas, tx_size, da_size, kv_updates (default: gas)
+    #[serde(default = "default_histogram_metric")]
+    pub metric: String,
+    /// Number of buckets (default: 20, capped at 200)
+    #[serde(default = "default_histogram_buckets")]
+    pub buckets: usize,
+    /// Log-spaced bucket edges (default: false)
+    #[serde(default)]
+    pub log: bool,
+}
+
+fn default_histogram_metric() -> String {
+    "gas".to_string()
+}
+
+fn default_histogram_buckets() -> usize {
+    20
+}
+
+/// Get the distribution of a per-transaction metric over a window
+pub async fn get_gas_histogram(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HistogramQuery>,
+) -> Result<Json<MetricHistogram>, ApiError> {
+    state
+        .store
+        .get_metric_histogram(query.seconds, &query.metric, query.buckets, query.log)
+        .await
+        .map(Json)
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "Unknown metric '{}' (expected gas, tx_size, da_size, or kv_updates)",
+                query.metric
+            ))
+        })
+}
+
+/// Query parameters for /stats/top-addresses
+#[derive(Debug, Deserialize)]
+pub struct TopAddressesQuery {
+    /// Window size in seconds (default: 60)
+    #[serde(default = "default_window")]
+    pub seconds: u64,
+    /// Metric to rank by: gas, tx_size, da_size, kv_updates (default: gas)
+    #[serde(default = "default_histogram_metric")]
+    pub metric: String,
+    /// How many addresses to return (default: 20, capped at 200)
+    #[serde(default = "default_top_addresses_limit")]
+    pub limit: usize,
+}
+
+fn default_top_addresses_limit() -> usize {
+    20
+}
+
+/// Get the addresses consuming the most of a metric over a window
+pub async fn get_top_addresses(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TopAddressesQuery>,
+) -> Result<Json<TopAddressesStats>, ApiError> {
+    state
+        .store
+        .get_top_addresses(query.seconds, &query.metric, query.limit.min(200))
+        .await
+        .map(Json)
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "Unknown metric '{}' (expected gas, tx_size, da_size, or kv_updates)",
+                query.metric
+            ))
+        })
+}
+
+/// Get live contract deployment activity over a window
+pub async fn get_deployment_stats(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<WindowQuery>,
+) -> Json<DeploymentStats> {
+    Json(state.store.get_deployment_stats(query.seconds).await)
+}
+
+/// Get per-system-contract activity over a window
+pub async fn get_system_activity(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<WindowQuery>,
+) -> Json<SystemActivityStats> {
+    Json(state.store.get_system_activity(query.seconds).await)
+}
+
+/// Get replay cache hit-rate counters
+///
+/// 404 when this process isn't running the replay engine.
+#[cfg(feature = "replay")]
+pub async fn get_cache_stats(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<crate::replay::CacheStatsSnapshot>, ApiError> {
+    state
+        .cache_db
+        .as_ref()
+        .map(|db| Json(db.stats_snapshot()))
+        .ok_or_else(|| ApiError::NotFound("Replay cache not running in this process".to_string()))
+}
+
+/// Prometheus text-format metrics scrape
+pub async fn get_metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        state.telemetry.render(),
+    )
+}
+
+/// Get the effective window configuration
+pub async fn get_debug_config() -> Json<DebugConfigResponse> {
+    Json(DebugConfigResponse {
+        stats_window_secs: crate::metrics::stats_window_from_env().as_secs(),
+        baseline_window_secs: crate::metrics::baseline_window_from_env().as_secs(),
     })
 }
 
@@ -66,19 +715,188 @@ pub async fn get_window_stats(
     State(state): State<Arc<AppState>>,
     Query(query): Query<WindowQuery>,
 ) -> Json<WindowStats> {
-    let stats = state.store.get_window_stats(query.seconds).await;
+    let stats = state.store.get_window_stats(query.seconds, query.reference).await;
+    Json(stats)
+}
+
+/// Compare a recent stats window against a baseline window offset into
+/// the past
+///
+/// The baseline is aggregated in memory when retention still covers the
+/// whole window, and from QuestDB when it doesn't; each side of the
+/// response says which source it came from.
+pub async fn get_window_comparison(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CompareQuery>,
+) -> Result<Json<WindowComparison>, ApiError> {
+    if query.recent_seconds == 0 || query.baseline_seconds == 0 {
+        return Err(ApiError::BadRequest(
+            "Window lengths must be positive".to_string(),
+        ));
+    }
+
+    let anchor = state
+        .store
+        .latest_timestamp()
+        .await
+        .unwrap_or_else(chrono::Utc::now);
+    let recent_start = anchor - chrono::Duration::seconds(query.recent_seconds as i64);
+    let recent = state.store.get_window_stats_between(recent_start, anchor).await;
+
+    let baseline_end = anchor - chrono::Duration::seconds(query.baseline_offset as i64);
+    let baseline_start = baseline_end - chrono::Duration::seconds(query.baseline_seconds as i64);
+
+    // Serve the baseline from memory only when retention covers the whole
+    // window; a partially covered window would skew the comparison
+    let covered = state
+        .store
+        .earliest_timestamp()
+        .await
+        .is_some_and(|earliest| earliest <= baseline_start);
+
+    let (baseline, baseline_source) = if covered {
+        let stats = state
+            .store
+            .get_window_stats_between(baseline_start, baseline_end)
+            .await;
+        (stats, "memory")
+    } else if let Some(questdb) = &state.questdb {
+        let stats = questdb
+            .get_window_stats_between(baseline_start, baseline_end)
+            .await
+            .map_err(|e| ApiError::Upstream(format!("QuestDB query failed: {}", e)))?;
+        (stats, "questdb")
+    } else {
+        return Err(ApiError::NotFound(
+            "Baseline window is older than in-memory retention and QuestDB is not configured"
+                .to_string(),
+        ));
+    };
+
+    let deltas = window_deltas(&recent, &baseline);
+    Ok(Json(WindowComparison {
+        recent: ComparedWindow {
+            source: "memory",
+            stats: recent,
+        },
+        baseline: ComparedWindow {
+            source: baseline_source,
+            stats: baseline,
+        },
+        deltas,
+    }))
+}
+
+/// Get the gas-per-mini-block distribution for a window
+pub async fn get_mini_block_gas_stats(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<WindowQuery>,
+) -> Json<MiniBlockGasStats> {
+    let stats = state.store.get_mini_block_gas_stats(query.seconds).await;
     Json(stats)
 }
 
 /// Get a specific block's metrics
+///
+/// Finalized blocks never change, so responses carry a weak ETag derived
+/// from the block hash and a matching `If-None-Match` short-circuits to
+/// 304 Not Modified. Window/stats endpoints change every block and skip
+/// ETags entirely.
 pub async fn get_block(
     State(state): State<Arc<AppState>>,
     Path(block_number): Path<u64>,
-) -> Result<Json<BlockMetrics>, StatusCode> {
-    match state.store.get_block(block_number).await {
-        Some(block) => Ok(Json(block)),
-        None => Err(StatusCode::NOT_FOUND),
+    headers: axum::http::HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let block = match state.store.get_block(block_number).await {
+        Some(block) => block,
+        None => {
+            // Distinguish a block evicted from the retention window (gone
+            // for good) from a gap or not-yet-seen block
+            return Err(match state.store.retained_range().await {
+                Some((min, _)) if block_number < min => ApiError::Gone(format!(
+                    "Block {} has been evicted from retention (oldest retained: {})",
+                    block_number, min
+                )),
+                _ => ApiError::NotFound(format!("Block {} not in the store", block_number)),
+            });
+        }
+    };
+
+    // Weak because the JSON serialization isn't byte-for-byte guaranteed,
+    // only semantically identical
+    let etag = format!("W/\"{:?}\"", block.block_hash);
+
+    if let Some(if_none_match) = headers
+        .get(axum::http::header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+    {
+        if if_none_match
+            .split(',')
+            .any(|candidate| candidate.trim() == etag)
+        {
+            return Ok((StatusCode::NOT_MODIFIED, [(axum::http::header::ETAG, etag)]).into_response());
+        }
+    }
+
+    Ok((
+        StatusCode::OK,
+        [(axum::http::header::ETAG, etag)],
+        Json(block),
+    )
+        .into_response())
+}
+
+/// Get a single transaction's resource breakdown by hash
+///
+/// Served from the in-memory transaction window, so transactions in
+/// blocks evicted from retention are not found.
+pub async fn get_transaction(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+) -> Result<Json<TransactionMetrics>, ApiError> {
+    let hash: alloy_primitives::B256 = hash
+        .parse()
+        .map_err(|_| ApiError::BadRequest(format!("Invalid transaction hash: {}", hash)))?;
+
+    state
+        .store
+        .get_transaction(hash)
+        .await
+        .map(Json)
+        .ok_or_else(|| {
+            ApiError::NotFound(format!("Transaction {:?} not in the retained window", hash))
+        })
+}
+
+/// Get blocks in an explicit block-number range, paginated via `next_cursor`
+pub async fn get_block_range(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<BlockRangeQuery>,
+) -> Result<Json<BlockRangeResponse>, ApiError> {
+    if query.end < query.start {
+        return Err(ApiError::BadRequest(format!(
+            "Range end {} is before start {}",
+            query.end, query.start
+        )));
     }
+    if query.end - query.start + 1 > MAX_RANGE_SPAN {
+        return Err(ApiError::BadRequest(format!(
+            "Range spans more than {} blocks",
+            MAX_RANGE_SPAN
+        )));
+    }
+
+    let mut blocks = state.store.get_blocks_in_range(query.start, query.end).await;
+
+    // Truncate to the page limit and hand back a cursor for the rest
+    let next_cursor = if blocks.len() > query.limit {
+        blocks.truncate(query.limit);
+        blocks.last().map(|b| b.block_number + 1)
+    } else {
+        None
+    };
+
+    Ok(Json(BlockRangeResponse { blocks, next_cursor }))
 }
 
 /// Get recent blocks
@@ -90,6 +908,130 @@ pub async fn get_recent_blocks(
     Json(blocks)
 }
 
+/// Get current base fee and a suggested priority fee
+///
+/// Derived from `eth_feeHistory` and cached briefly upstream of this
+/// handler; see [`super::fees::FeeOracle`].
+pub async fn get_fees(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<super::fees::FeesData>, ApiError> {
+    let oracle = state
+        .fees
+        .as_ref()
+        .ok_or_else(|| ApiError::NotFound("Fee oracle not running in this process".to_string()))?;
+    oracle
+        .fees()
+        .await
+        .map(Json)
+        .map_err(|e| ApiError::Upstream(e.to_string()))
+}
+
+/// Identify a contract by address
+///
+/// Runs the full identification pipeline on the first request and serves
+/// the cached result while it is fresh; see
+/// [`ContractIdentifier::identify`](crate::contract_identifier::ContractIdentifier::identify).
+pub async fn identify_contract(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+) -> Result<Json<crate::contract_identifier::ContractInfo>, ApiError> {
+    let address: alloy_primitives::Address = address
+        .parse()
+        .map_err(|_| ApiError::BadRequest(format!("Invalid address: {}", address)))?;
+
+    let identifier = state.contracts.as_ref().ok_or_else(|| {
+        ApiError::NotFound("Contract identification not running in this process".to_string())
+    })?;
+    identifier
+        .identify(address)
+        .await
+        .map(Json)
+        .map_err(|e| ApiError::Upstream(e.to_string()))
+}
+
+/// Query parameters for the sparkline endpoint
+#[derive(Deserialize)]
+pub struct SparklineQuery {
+    /// Window size in seconds (default: 60)
+    #[serde(default = "default_window")]
+    pub seconds: u64,
+    /// Metric to bucket: gas, tx_count, tx_size, da_size, kv_updates,
+    /// state_growth (default: gas)
+    #[serde(default = "default_histogram_metric")]
+    pub metric: String,
+    /// Number of time slices (default: 60, capped at 600)
+    #[serde(default = "default_sparkline_buckets")]
+    pub buckets: usize,
+}
+
+fn default_sparkline_buckets() -> usize {
+    60
+}
+
+/// Get a time-bucketed metric series for sparkline rendering
+pub async fn get_sparkline(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SparklineQuery>,
+) -> Result<Json<Sparkline>, ApiError> {
+    state
+        .store
+        .get_sparkline(query.seconds, &query.metric, query.buckets)
+        .await
+        .map(Json)
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "Unknown metric '{}' (expected gas, tx_count, tx_size, da_size, kv_updates, or state_growth)",
+                query.metric
+            ))
+        })
+}
+
+/// Recent blocks as CSV, for pulling straight into a notebook
+///
+/// Same data as `/blocks/recent`, one row per block with a header row;
+/// timestamps are RFC 3339.
+pub async fn get_blocks_csv(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RecentBlocksQuery>,
+) -> impl IntoResponse {
+    let blocks = state.store.get_recent_blocks(query.count).await;
+
+    let mut csv = String::from(
+        "block_number,timestamp,tx_count,total_gas,compute_gas,storage_gas,\
+         tx_size,da_size,data_size,kv_updates,state_growth,gas_limit\n",
+    );
+    for b in &blocks {
+        use std::fmt::Write;
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            b.block_number,
+            b.timestamp.to_rfc3339(),
+            b.tx_count,
+            b.total_gas,
+            b.compute_gas,
+            b.storage_gas,
+            b.tx_size,
+            b.da_size,
+            b.data_size,
+            b.kv_updates,
+            b.state_growth,
+            b.gas_limit
+        );
+    }
+
+    (
+        [
+            (axum::http::header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                axum::http::header::CONTENT_DISPOSITION,
+                "attachment; filename=\"blocks.csv\"",
+            ),
+        ],
+        csv,
+    )
+}
+
 /// Ring visualization data (optimized for the activity ring)
 #[derive(Serialize)]
 pub struct RingData {
@@ -109,6 +1051,14 @@ pub struct RingData {
     pub da_size: u64,
     pub tx_count: u64,
     pub block_count: u64,
+    /// Mini-blocks in the window, for the EVM-blocks vs mini-blocks ratio
+    pub mini_block_count: u64,
+    /// Window-wide DA compression ratio (da_size / tx_size)
+    pub compression_ratio: f64,
+    /// Smoothed gas throughput (gas/s), stable across single large blocks
+    pub gas_per_second_ewma: f64,
+    /// Smoothed block throughput (blocks/s)
+    pub blocks_per_second_ewma: f64,
 }
 
 /// Typical max values for normalization
@@ -120,7 +1070,8 @@ pub async fn get_ring_data(
     State(state): State<Arc<AppState>>,
     Query(query): Query<WindowQuery>,
 ) -> Json<RingData> {
-    let stats = state.store.get_window_stats(query.seconds).await;
+    let stats = state.store.get_window_stats(query.seconds, query.reference).await;
+    let (gas_per_second_ewma, blocks_per_second_ewma) = state.store.ewma_rates().await;
 
     let gas_normalized = (stats.mean_total_gas / TYPICAL_MAX_GAS_PER_BLOCK).min(1.0);
     let kv_normalized = (stats.mean_kv_updates / TYPICAL_MAX_KV_PER_BLOCK).min(1.0);
@@ -146,6 +1097,10 @@ pub async fn get_ring_data(
         da_size: stats.sum_da_size,
         tx_count: stats.tx_count,
         block_count: stats.block_count,
+        mini_block_count: stats.sum_mini_blocks,
+        compression_ratio: stats.mean_compression_ratio,
+        gas_per_second_ewma,
+        blocks_per_second_ewma,
     })
 }
 
@@ -185,7 +1140,7 @@ pub async fn get_dial_data(
     State(state): State<Arc<AppState>>,
     Query(query): Query<WindowQuery>,
 ) -> Json<DialData> {
-    let stats = state.store.get_window_stats(query.seconds).await;
+    let stats = state.store.get_window_stats(query.seconds, query.reference).await;
 
     Json(DialData {
         compute: DialMetrics {
@@ -208,43 +1163,288 @@ pub async fn get_dial_data(
     })
 }
 
+/// Which block stream a WebSocket client subscribes to
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamMode {
+    /// Blocks behind the confirmation depth (the default)
+    #[default]
+    Confirmed,
+    /// The unconfirmed chain head: lower latency, but events carry
+    /// `revised: true` when a reorg replaces an already-sent block
+    Head,
+}
+
+/// Query parameters for the WebSocket block stream
+#[derive(Debug, Default, Deserialize)]
+pub struct WsBlocksQuery {
+    #[serde(default)]
+    pub mode: StreamMode,
+}
+
 /// WebSocket handler for real-time block streaming
 pub async fn ws_blocks(
     ws: WebSocketUpgrade,
     State(state): State<Arc<AppState>>,
+    Query(query): Query<WsBlocksQuery>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_ws_connection(socket, state))
+    match query.mode {
+        StreamMode::Confirmed => ws.on_upgrade(|socket| handle_ws_connection(socket, state)),
+        StreamMode::Head => ws.on_upgrade(|socket| handle_head_ws_connection(socket, state)),
+    }
 }
 
-/// Handle a WebSocket connection
-async fn handle_ws_connection(socket: WebSocket, state: Arc<AppState>) {
-    let (mut sender, mut receiver) = socket.split();
+/// Server-Sent Events stream of block events
+///
+/// Same feed as /ws/blocks for clients and proxies that handle SSE better
+/// than WebSockets. The block number is sent as the SSE event id so clients
+/// reconnecting with Last-Event-ID know where they left off.
+pub async fn sse_blocks(
+    State(state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let rx = state.block_tx.subscribe();
 
-    // Subscribe to block events
-    let mut block_rx = state.block_tx.subscribe();
+    let stream = futures::stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    let json = match serde_json::to_string(&event) {
+                        Ok(j) => j,
+                        Err(_) => continue,
+                    };
+                    let sse_event = Event::default()
+                        .id(event.block.block_number.to_string())
+                        .data(json);
+                    return Some((Ok(sse_event), rx));
+                }
+                // Slow consumer skipped some events; keep streaming from here
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Per-connection subscription filter for the block stream
+///
+/// Sent by the client as `{ "filter": { "min_gas": .., "addresses": [..] } }`;
+/// connections that never send one receive every block.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct BlockFilter {
+    /// Only forward blocks using at least this much gas
+    #[serde(default)]
+    pub min_gas: Option<u64>,
+    /// Only forward blocks containing a transaction to one of these addresses
+    #[serde(default)]
+    pub addresses: Option<Vec<alloy_primitives::Address>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClientMessage {
+    filter: BlockFilter,
+}
+
+impl BlockFilter {
+    fn matches(&self, event: &BlockEvent) -> bool {
+        if let Some(min_gas) = self.min_gas {
+            if event.block.total_gas < min_gas {
+                return false;
+            }
+        }
+        if let Some(addresses) = &self.addresses {
+            if !addresses.is_empty()
+                && !addresses.iter().any(|a| event.to_addresses.contains(a))
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Forward the tentative head stream to a WebSocket client
+///
+/// No filtering: head mode is for liveness displays that want every tip
+/// update, revisions included.
+async fn handle_head_ws_connection(socket: WebSocket, state: Arc<AppState>) {
+    state.telemetry.ws_connected();
+
+    let (mut sender, mut receiver) = socket.split();
+    let mut tentative_rx = state.tentative_tx.subscribe();
 
-    // Spawn task to send blocks to client
     let send_task = tokio::spawn(async move {
-        while let Ok(event) = block_rx.recv().await {
+        loop {
+            let event = match tentative_rx.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    let notice = format!("{{\"type\":\"lagged\",\"skipped\":{}}}", skipped);
+                    if sender.send(Message::Text(notice.into())).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+
             let json = match serde_json::to_string(&event) {
                 Ok(j) => j,
                 Err(_) => continue,
             };
-
             if sender.send(Message::Text(json.into())).await.is_err() {
                 break;
             }
         }
     });
 
-    // Handle incoming messages (for ping/pong and close)
     let recv_task = tokio::spawn(async move {
         while let Some(msg) = receiver.next().await {
             match msg {
                 Ok(Message::Close(_)) => break,
-                Ok(Message::Ping(data)) => {
-                    // Pong is handled automatically by axum
-                    let _ = data;
+                Err(_) => break,
+                _ => {}
+            }
+        }
+    });
+
+    tokio::select! {
+        _ = send_task => {},
+        _ = recv_task => {},
+    }
+
+    state.telemetry.ws_disconnected();
+}
+
+/// How often the server pings an idle connection
+const WS_PING_INTERVAL: Duration = Duration::from_secs(30);
+/// How long the client may stay silent before the connection is dropped
+const WS_PONG_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Keepalive state shared between a connection's send and receive tasks
+///
+/// The send task pings on an interval and checks the deadline; the receive
+/// task records pongs as liveness, pushing the deadline out.
+struct WsKeepalive {
+    last_seen: std::sync::Mutex<Instant>,
+}
+
+impl WsKeepalive {
+    fn new() -> Self {
+        Self {
+            last_seen: std::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Record traffic from the client, resetting the deadline
+    fn record_pong(&self) {
+        *self.last_seen.lock().unwrap() = Instant::now();
+    }
+
+    /// Whether the client has been silent longer than `timeout`
+    fn expired(&self, timeout: Duration) -> bool {
+        self.last_seen.lock().unwrap().elapsed() > timeout
+    }
+}
+
+/// Handle a WebSocket connection
+async fn handle_ws_connection(socket: WebSocket, state: Arc<AppState>) {
+    state.telemetry.ws_connected();
+
+    let (mut sender, mut receiver) = socket.split();
+
+    // Subscribe to block events
+    let mut block_rx = state.block_tx.subscribe();
+
+    // Filter is set by the client's first message, if it sends one
+    let filter = Arc::new(std::sync::Mutex::new(None::<BlockFilter>));
+    let filter_rx = filter.clone();
+
+    let keepalive = Arc::new(WsKeepalive::new());
+    let keepalive_recv = keepalive.clone();
+
+    // Spawn task to send blocks to client, pinging through quiet periods
+    // so proxies with short idle timeouts don't drop the connection
+    let send_task = tokio::spawn(async move {
+        let mut ping_interval = tokio::time::interval(WS_PING_INTERVAL);
+        ping_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // The first tick completes immediately; skip it
+        ping_interval.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = ping_interval.tick() => {
+                    // A client that stopped answering pings is gone even if
+                    // the TCP connection lingers
+                    if keepalive.expired(WS_PONG_TIMEOUT) {
+                        break;
+                    }
+                    if sender.send(Message::Ping(Vec::new().into())).await.is_err() {
+                        break;
+                    }
+                }
+                received = block_rx.recv() => {
+                    let event = match received {
+                        Ok(event) => event,
+                        // At MegaETH block rates a slow dashboard can outrun
+                        // the broadcast buffer; tell it what it missed and
+                        // keep going rather than silently dropping the
+                        // connection
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            let notice =
+                                format!("{{\"type\":\"lagged\",\"skipped\":{}}}", skipped);
+                            if sender.send(Message::Text(notice.into())).await.is_err() {
+                                break;
+                            }
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+
+                    let forward = filter
+                        .lock()
+                        .unwrap()
+                        .as_ref()
+                        .map(|f| f.matches(&event))
+                        .unwrap_or(true);
+                    if !forward {
+                        continue;
+                    }
+
+                    let json = match serde_json::to_string(&event) {
+                        Ok(j) => j,
+                        Err(_) => continue,
+                    };
+
+                    if sender.send(Message::Text(json.into())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    // Handle incoming messages (filter updates, ping/pong, close)
+    let recv_task = tokio::spawn(async move {
+        while let Some(msg) = receiver.next().await {
+            match msg {
+                Ok(Message::Text(text)) => {
+                    match serde_json::from_str::<ClientMessage>(&text) {
+                        Ok(message) => {
+                            *filter_rx.lock().unwrap() = Some(message.filter);
+                        }
+                        Err(e) => {
+                            debug!("Ignoring malformed WS client message: {}", e);
+                        }
+                    }
+                }
+                Ok(Message::Close(_)) => break,
+                Ok(Message::Ping(_)) => {
+                    // Pong is sent automatically by axum; the client is alive
+                    keepalive_recv.record_pong();
+                }
+                Ok(Message::Pong(_)) => {
+                    keepalive_recv.record_pong();
                 }
                 Err(_) => break,
                 _ => {}
@@ -257,4 +1457,23 @@ async fn handle_ws_connection(socket: WebSocket, state: Arc<AppState>) {
         _ = send_task => {},
         _ = recv_task => {},
     }
+
+    state.telemetry.ws_disconnected();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pong_resets_keepalive_deadline() {
+        let keepalive = WsKeepalive::new();
+
+        // Simulate a client that has been silent past the timeout
+        *keepalive.last_seen.lock().unwrap() = Instant::now() - Duration::from_secs(120);
+        assert!(keepalive.expired(WS_PONG_TIMEOUT));
+
+        keepalive.record_pong();
+        assert!(!keepalive.expired(WS_PONG_TIMEOUT));
+    }
 }
@@ -1,4 +1,8 @@
+mod error;
+mod fees;
 mod handlers;
 mod routes;
 
-pub use routes::create_router;
+pub use error::ApiError;
+pub use fees::FeeOracle;
+pub use routes::{create_router, create_router_with_services};
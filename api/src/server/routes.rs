@@ -1,43 +1,567 @@
 use axum::{
+    http::HeaderValue,
     routing::get,
     Router,
 };
 use std::sync::Arc;
 use tokio::sync::broadcast;
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 
+use super::fees::FeeOracle;
 use super::handlers::{self, AppState};
+use crate::contract_identifier::ContractIdentifier;
 use crate::metrics::MetricsStore;
-use crate::rpc::BlockEvent;
+use crate::questdb::QuestDBReader;
+use crate::rpc::{BlockEvent, TentativeBlockEvent};
 
 /// Create the API router with all routes
 pub fn create_router(
     store: Arc<MetricsStore>,
     block_tx: broadcast::Sender<BlockEvent>,
+    tentative_tx: broadcast::Sender<TentativeBlockEvent>,
 ) -> Router {
-    let state = Arc::new(AppState { store, block_tx });
+    let state = Arc::new(AppState {
+        store,
+        block_tx,
+        tentative_tx,
+        telemetry: crate::telemetry::telemetry(),
+        fees: None,
+        contracts: None,
+        questdb: None,
+        #[cfg(feature = "replay")]
+        cache_db: None,
+    });
 
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    build_router(state, None)
+}
+
+/// Create the API router with its RPC-backed services attached, so
+/// `/viz/fees` and `/contracts/{address}/identify` serve live data
+///
+/// `allowed_origins` restricts CORS to an explicit origin list; None allows
+/// any origin (development default).
+pub fn create_router_with_services(
+    store: Arc<MetricsStore>,
+    block_tx: broadcast::Sender<BlockEvent>,
+    tentative_tx: broadcast::Sender<TentativeBlockEvent>,
+    fees: FeeOracle,
+    contracts: ContractIdentifier,
+    questdb: Option<Arc<QuestDBReader>>,
+    allowed_origins: Option<Vec<String>>,
+) -> Router {
+    let state = Arc::new(AppState {
+        store,
+        block_tx,
+        tentative_tx,
+        telemetry: crate::telemetry::telemetry(),
+        fees: Some(fees),
+        contracts: Some(contracts),
+        questdb,
+        #[cfg(feature = "replay")]
+        cache_db: None,
+    });
+
+    build_router(state, allowed_origins)
+}
+
+/// Create the API router with the replay cache attached, so `/cache/stats`
+/// reports live hit rates
+#[cfg(feature = "replay")]
+pub fn create_router_with_cache(
+    store: Arc<MetricsStore>,
+    block_tx: broadcast::Sender<BlockEvent>,
+    tentative_tx: broadcast::Sender<TentativeBlockEvent>,
+    cache_db: crate::replay::SmartCacheDB,
+) -> Router {
+    let state = Arc::new(AppState {
+        store,
+        block_tx,
+        tentative_tx,
+        telemetry: crate::telemetry::telemetry(),
+        fees: None,
+        contracts: None,
+        questdb: None,
+        cache_db: Some(cache_db),
+    });
+
+    build_router(state, None)
+}
 
-    Router::new()
+fn build_router(state: Arc<AppState>, allowed_origins: Option<Vec<String>>) -> Router {
+    // Restrict origins when a deployment provides an allow-list; anything
+    // unparseable is dropped rather than silently widened to Any. Methods
+    // and headers stay permissive either way.
+    let cors = CorsLayer::new().allow_methods(Any).allow_headers(Any);
+    let cors = match &allowed_origins {
+        Some(origins) if !origins.is_empty() => cors.allow_origin(
+            origins
+                .iter()
+                .filter_map(|origin| origin.parse::<HeaderValue>().ok())
+                .collect::<Vec<_>>(),
+        ),
+        _ => cors.allow_origin(Any),
+    };
+
+    let router = Router::new()
         // Health check
         .route("/health", get(handlers::health))
         // Window statistics
         .route("/stats/window", get(handlers::get_window_stats))
+        .route("/stats/mini-block-gas", get(handlers::get_mini_block_gas_stats))
+        .route("/stats/system-activity", get(handlers::get_system_activity))
+        // Recent window vs an offset baseline window
+        .route("/stats/compare", get(handlers::get_window_comparison))
+        .route("/stats/histogram", get(handlers::get_gas_histogram))
+        .route("/stats/deployments", get(handlers::get_deployment_stats))
+        .route("/stats/top-addresses", get(handlers::get_top_addresses))
+        .route("/stats/percentile-audit", get(handlers::get_percentile_audit))
+        .route("/stats/tx-types", get(handlers::get_tx_type_breakdown))
+        // Long-range history (QuestDB-backed; 503 without a reader)
+        .route("/history/blocks", get(handlers::get_block_history))
+        // Deployment history (QuestDB-backed; 503 without a reader)
+        .route("/deployments/heatmap", get(handlers::get_deployment_heatmap))
+        .route("/deployments/details", get(handlers::get_deployment_details))
+        // Contract identification
+        .route("/contracts/{address}/identify", get(handlers::identify_contract))
         // Block endpoints
         .route("/blocks/{block_number}", get(handlers::get_block))
         .route("/blocks/recent", get(handlers::get_recent_blocks))
+        // CSV export of the same data, for notebooks
+        .route("/blocks/recent.csv", get(handlers::get_blocks_csv))
+        .route("/blocks/range", get(handlers::get_block_range))
+        // Per-transaction resource breakdown by hash
+        .route("/transactions/{hash}", get(handlers::get_transaction))
         // Visualization endpoints (optimized for frontend)
         .route("/viz/ring", get(handlers::get_ring_data))
         .route("/viz/dials", get(handlers::get_dial_data))
-        // WebSocket for real-time block streaming
+        .route("/viz/sparkline", get(handlers::get_sparkline))
+        // Gas-price dial: base fee + suggested priority fee
+        .route("/viz/fees", get(handlers::get_fees))
+        // WebSocket for real-time block streaming (mode=head streams the
+        // unconfirmed tip, subject to revision on reorg)
         .route("/ws/blocks", get(handlers::ws_blocks))
+        // Server-Sent Events alternative to the WebSocket stream
+        .route("/sse/blocks", get(handlers::sse_blocks))
+        // Operational metrics (Prometheus text format)
+        .route("/metrics", get(handlers::get_metrics))
+        // Debug
+        .route("/debug/config", get(handlers::get_debug_config));
+
+    // Replay cache monitoring
+    #[cfg(feature = "replay")]
+    let router = router.route("/cache/stats", get(handlers::get_cache_stats));
+
+    router
         // Add middleware
         .layer(cors)
+        // Gzip/brotli-encode JSON bodies when the client asks; upgrade
+        // requests (WebSocket) are passed through untouched
+        .layer(CompressionLayer::new())
         .layer(TraceLayer::new_for_http())
         .with_state(state)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use chrono::Utc;
+    use tower::ServiceExt;
+
+    use crate::metrics::BlockMetrics;
+    use alloy_primitives::B256;
+
+    fn test_block(number: u64) -> BlockMetrics {
+        BlockMetrics {
+            block_number: number,
+            block_hash: B256::ZERO,
+            timestamp: Utc::now(),
+            tx_count: 0,
+            total_gas: 0,
+            compute_gas: 0,
+            storage_gas: 0,
+            tx_size: 0,
+            da_size: 0,
+            da_gas: 0,
+            data_size: 0,
+            kv_updates: 0,
+            state_growth: 0,
+            compression_ratio: 0.0,
+            base_fee_per_gas: None,
+            burned_fees: 0,
+            value_transferred: alloy_primitives::U256::ZERO,
+            tx_type_counts: Default::default(),
+            failed_tx_count: 0,
+            failed_gas: 0,
+            sampled: false,
+            receipts_complete: true,
+            mini_block_count: 1,
+            mini_block_gas: vec![0],
+            gas_limit: 30_000_000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deployment_routes_are_503_without_questdb() {
+        let store = MetricsStore::new();
+        let (block_tx, _) = broadcast::channel(8);
+        let (tentative_tx, _) = broadcast::channel(8);
+        let router = create_router(store, block_tx, tentative_tx);
+
+        for uri in [
+            "/history/blocks?window=1h",
+            "/deployments/heatmap?view=weekly",
+            "/deployments/details?start=2026-08-01T00:00:00Z&end=2026-08-02T00:00:00Z",
+        ] {
+            let response = router
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(
+                response.status(),
+                StatusCode::SERVICE_UNAVAILABLE,
+                "{}",
+                uri
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_percentile_audit_without_questdb_omits_approx() {
+        let store = MetricsStore::new();
+        let (block_tx, _) = broadcast::channel(8);
+        let (tentative_tx, _) = broadcast::channel(8);
+        let router = create_router(store, block_tx, tentative_tx);
+
+        // Unknown metrics are a client error before any query runs
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/stats/percentile-audit?metric=gas_price")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // The exact side works without QuestDB; the approx side is null
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/stats/percentile-audit?metric=total_gas&seconds=60")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(json["approx_value"].is_null());
+        assert!(json["relative_error"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_block_history_rejects_unknown_window() {
+        let store = MetricsStore::new();
+        let (block_tx, _) = broadcast::channel(8);
+        let (tentative_tx, _) = broadcast::channel(8);
+        let router = create_router(store, block_tx, tentative_tx);
+
+        // Window validation runs before the QuestDB check, so a bad window
+        // is a client error even on instances without a reader
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/history/blocks?window=3h")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_recent_blocks_response_is_gzipped_when_requested() {
+        let store = MetricsStore::new();
+        for n in 0..100 {
+            store.add_block(test_block(n), vec![]).await;
+        }
+        let (block_tx, _) = broadcast::channel(8);
+        let (tentative_tx, _) = broadcast::channel(8);
+        let router = create_router(store, block_tx, tentative_tx);
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/blocks/recent?count=100")
+                    .header(header::ACCEPT_ENCODING, "gzip")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(header::CONTENT_ENCODING)
+                .map(|v| v.to_str().unwrap()),
+            Some("gzip")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_block_honors_if_none_match() {
+        let store = MetricsStore::new();
+        store.add_block(test_block(42), vec![]).await;
+        let (block_tx, _) = broadcast::channel(8);
+        let (tentative_tx, _) = broadcast::channel(8);
+        let router = create_router(store, block_tx, tentative_tx);
+
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/blocks/42")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .expect("block response carries an ETag")
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(etag.starts_with("W/\""));
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/blocks/42")
+                    .header(header::IF_NONE_MATCH, &etag)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[tokio::test]
+    async fn test_recent_blocks_csv_has_header_and_rows() {
+        let store = MetricsStore::new();
+        for n in 0..10 {
+            store.add_block(test_block(n), vec![]).await;
+        }
+        let (block_tx, _) = broadcast::channel(8);
+        let (tentative_tx, _) = broadcast::channel(8);
+        let router = create_router(store, block_tx, tentative_tx);
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/blocks/recent.csv?count=10")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .map(|v| v.to_str().unwrap()),
+            Some("text/csv; charset=utf-8")
+        );
+        let bytes = axum::body::to_bytes(response.into_body(), 64 * 1024)
+            .await
+            .unwrap();
+        let body = String::from_utf8(bytes.to_vec()).unwrap();
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines.len(), 11, "header plus one row per block");
+        assert!(lines[0].starts_with("block_number,timestamp,tx_count,total_gas"));
+    }
+
+    async fn error_body(response: axum::response::Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), 64 * 1024)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_missing_block_returns_structured_404() {
+        let store = MetricsStore::new();
+        let (block_tx, _) = broadcast::channel(8);
+        let (tentative_tx, _) = broadcast::channel(8);
+        let router = create_router(store, block_tx, tentative_tx);
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/blocks/999")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = error_body(response).await;
+        assert_eq!(body["error"]["code"], "not_found");
+        assert!(body["error"]["message"].as_str().unwrap().contains("999"));
+    }
+
+    #[tokio::test]
+    async fn test_evicted_block_returns_410() {
+        let store = MetricsStore::new();
+        // Retention starts at block 100; block 5 is below it
+        for n in 100..110 {
+            store.add_block(test_block(n), vec![]).await;
+        }
+        let (block_tx, _) = broadcast::channel(8);
+        let (tentative_tx, _) = broadcast::channel(8);
+        let router = create_router(store, block_tx, tentative_tx);
+
+        let response = router
+            .clone()
+            .oneshot(Request::builder().uri("/blocks/5").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::GONE);
+        let body = error_body(response).await;
+        assert_eq!(body["error"]["code"], "gone");
+
+        // Above the retained maximum is a plain 404, not gone
+        let response = router
+            .oneshot(Request::builder().uri("/blocks/999").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_lookup_validates_hash_and_404s() {
+        let store = MetricsStore::new();
+        let (block_tx, _) = broadcast::channel(8);
+        let (tentative_tx, _) = broadcast::channel(8);
+        let router = create_router(store, block_tx, tentative_tx);
+
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/transactions/not-a-hash")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = error_body(response).await;
+        assert_eq!(body["error"]["code"], "bad_request");
+
+        let missing = format!("/transactions/{:?}", B256::with_last_byte(7));
+        let response = router
+            .oneshot(Request::builder().uri(missing).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_origin_gets_no_allow_origin_header() {
+        let store = MetricsStore::new();
+        let (block_tx, _) = broadcast::channel(8);
+        let (tentative_tx, _) = broadcast::channel(8);
+        let fees = FeeOracle::new(
+            crate::rpc::MegaEthClient::new("http://localhost:1").await.unwrap(),
+        );
+        let contracts = ContractIdentifier::new(
+            crate::rpc::MegaEthClient::new("http://localhost:1").await.unwrap(),
+            None,
+        );
+        let router = create_router_with_services(
+            store,
+            block_tx,
+            tentative_tx,
+            fees,
+            contracts,
+            None,
+            Some(vec!["https://dashboard.example".to_string()]),
+        );
+
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/health")
+                    .header(header::ORIGIN, "https://dashboard.example")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            response
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .map(|v| v.to_str().unwrap()),
+            Some("https://dashboard.example")
+        );
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/health")
+                    .header(header::ORIGIN, "https://evil.example")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_inverted_range_returns_structured_400() {
+        let store = MetricsStore::new();
+        let (block_tx, _) = broadcast::channel(8);
+        let (tentative_tx, _) = broadcast::channel(8);
+        let router = create_router(store, block_tx, tentative_tx);
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/blocks/range?start=10&end=5")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = error_body(response).await;
+        assert_eq!(body["error"]["code"], "bad_request");
+    }
+}
@@ -0,0 +1,61 @@
+//! Catalog of known MegaETH / OP-stack system contracts
+//!
+//! Covers the OP-stack predeploys plus the MegaETH oracles. The catalog is
+//! shared by the contract monitor and the system-activity endpoint so the
+//! two views stay in sync.
+
+use alloy_primitives::{address, Address};
+
+/// A known system contract
+#[derive(Debug, Clone, Copy)]
+pub struct SystemContract {
+    pub address: Address,
+    pub name: &'static str,
+    pub symbol: &'static str,
+    pub category: &'static str,
+}
+
+/// All known system contracts
+pub const SYSTEM_CONTRACTS: &[SystemContract] = &[
+    SystemContract {
+        address: address!("0x6342000000000000000000000000000000000001"),
+        name: "Oracle",
+        symbol: "ORA",
+        category: "infrastructure",
+    },
+    SystemContract {
+        address: address!("0x6342000000000000000000000000000000000002"),
+        name: "Timestamp Oracle",
+        symbol: "TSO",
+        category: "infrastructure",
+    },
+    SystemContract {
+        address: address!("0x4200000000000000000000000000000000000015"),
+        name: "L1 Block",
+        symbol: "L1B",
+        category: "infrastructure",
+    },
+    SystemContract {
+        address: address!("0x4200000000000000000000000000000000000007"),
+        name: "L2 Cross Domain Messenger",
+        symbol: "CDM",
+        category: "bridge",
+    },
+    SystemContract {
+        address: address!("0x4200000000000000000000000000000000000010"),
+        name: "L2 Standard Bridge",
+        symbol: "BRG",
+        category: "bridge",
+    },
+    SystemContract {
+        address: address!("0x4200000000000000000000000000000000000006"),
+        name: "WETH",
+        symbol: "WETH",
+        category: "defi",
+    },
+];
+
+/// Look up a known system contract by address
+pub fn lookup(address: Address) -> Option<&'static SystemContract> {
+    SYSTEM_CONTRACTS.iter().find(|c| c.address == address)
+}
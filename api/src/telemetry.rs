@@ -0,0 +1,261 @@
+//! Lightweight Prometheus-style telemetry registry
+//!
+//! Hand-rolled rather than pulling in a metrics crate: the surface we need is
+//! a handful of counters, two gauges, and one latency histogram, all rendered
+//! in the Prometheus text exposition format by `/metrics`.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// Latency histogram bucket upper bounds, in seconds
+const LATENCY_BUCKETS: [f64; 9] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+/// Per-RPC-method call counters and latency histogram
+#[derive(Default)]
+struct MethodStats {
+    calls: AtomicU64,
+    errors: AtomicU64,
+    /// Cumulative bucket counts matching LATENCY_BUCKETS, plus +Inf at the end
+    buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    /// Total observed latency in microseconds (for the histogram _sum)
+    latency_micros: AtomicU64,
+}
+
+impl MethodStats {
+    fn observe(&self, latency: Duration, ok: bool) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        if !ok {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        self.latency_micros
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+
+        let secs = latency.as_secs_f64();
+        for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+            if secs <= *bound {
+                self.buckets[i].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        // +Inf bucket counts everything
+        self.buckets[LATENCY_BUCKETS.len()].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Process-wide operational counters, rendered by `/metrics`
+#[derive(Default)]
+pub struct Telemetry {
+    /// Per-method RPC stats, keyed by JSON-RPC method name
+    rpc: Mutex<HashMap<String, Arc<MethodStats>>>,
+    /// Blocks successfully processed by the poller
+    blocks_processed: AtomicU64,
+    /// How far the store trails the chain head (gauge)
+    poller_lag_blocks: AtomicU64,
+    /// Whether the poller is in batch catch-up mode (gauge, 0/1)
+    poller_catching_up: AtomicBool,
+    /// Currently-connected WebSocket clients (gauge)
+    ws_connections: AtomicI64,
+}
+
+impl Telemetry {
+    /// Record one RPC round trip with its latency and outcome
+    pub fn record_rpc_call(&self, method: &str, latency: Duration, ok: bool) {
+        let stats = {
+            let mut rpc = self.rpc.lock().unwrap();
+            Arc::clone(rpc.entry(method.to_string()).or_default())
+        };
+        stats.observe(latency, ok);
+    }
+
+    /// Record one successfully processed block
+    pub fn record_block_processed(&self) {
+        self.blocks_processed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Set the current poller lag in blocks
+    pub fn set_poller_lag(&self, blocks: u64) {
+        self.poller_lag_blocks.store(blocks, Ordering::Relaxed);
+    }
+
+    /// Flag whether the poller is in catch-up mode
+    pub fn set_catching_up(&self, catching_up: bool) {
+        self.poller_catching_up.store(catching_up, Ordering::Relaxed);
+    }
+
+    /// Whether the poller is currently catching up
+    pub fn is_catching_up(&self) -> bool {
+        self.poller_catching_up.load(Ordering::Relaxed)
+    }
+
+    /// Track a WebSocket client connecting or disconnecting
+    pub fn ws_connected(&self) {
+        self.ws_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn ws_disconnected(&self) {
+        self.ws_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Render everything in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+
+        out.push_str("# HELP megaviz_rpc_requests_total JSON-RPC requests sent, by method\n");
+        out.push_str("# TYPE megaviz_rpc_requests_total counter\n");
+        let rpc: Vec<(String, Arc<MethodStats>)> = {
+            let map = self.rpc.lock().unwrap();
+            let mut entries: Vec<_> = map
+                .iter()
+                .map(|(k, v)| (k.clone(), Arc::clone(v)))
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            entries
+        };
+        for (method, stats) in &rpc {
+            let _ = writeln!(
+                out,
+                "megaviz_rpc_requests_total{{method=\"{}\"}} {}",
+                method,
+                stats.calls.load(Ordering::Relaxed)
+            );
+        }
+
+        out.push_str("# HELP megaviz_rpc_errors_total Failed JSON-RPC requests, by method\n");
+        out.push_str("# TYPE megaviz_rpc_errors_total counter\n");
+        for (method, stats) in &rpc {
+            let _ = writeln!(
+                out,
+                "megaviz_rpc_errors_total{{method=\"{}\"}} {}",
+                method,
+                stats.errors.load(Ordering::Relaxed)
+            );
+        }
+
+        out.push_str(
+            "# HELP megaviz_rpc_request_duration_seconds JSON-RPC round-trip latency\n",
+        );
+        out.push_str("# TYPE megaviz_rpc_request_duration_seconds histogram\n");
+        for (method, stats) in &rpc {
+            for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "megaviz_rpc_request_duration_seconds_bucket{{method=\"{}\",le=\"{}\"}} {}",
+                    method,
+                    bound,
+                    stats.buckets[i].load(Ordering::Relaxed)
+                );
+            }
+            let _ = writeln!(
+                out,
+                "megaviz_rpc_request_duration_seconds_bucket{{method=\"{}\",le=\"+Inf\"}} {}",
+                method,
+                stats.buckets[LATENCY_BUCKETS.len()].load(Ordering::Relaxed)
+            );
+            let _ = writeln!(
+                out,
+                "megaviz_rpc_request_duration_seconds_sum{{method=\"{}\"}} {}",
+                method,
+                stats.latency_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+            );
+            let _ = writeln!(
+                out,
+                "megaviz_rpc_request_duration_seconds_count{{method=\"{}\"}} {}",
+                method,
+                stats.calls.load(Ordering::Relaxed)
+            );
+        }
+
+        out.push_str("# HELP megaviz_blocks_processed_total Blocks processed by the poller\n");
+        out.push_str("# TYPE megaviz_blocks_processed_total counter\n");
+        let _ = writeln!(
+            out,
+            "megaviz_blocks_processed_total {}",
+            self.blocks_processed.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP megaviz_poller_lag_blocks Blocks the store trails the chain head\n");
+        out.push_str("# TYPE megaviz_poller_lag_blocks gauge\n");
+        let _ = writeln!(
+            out,
+            "megaviz_poller_lag_blocks {}",
+            self.poller_lag_blocks.load(Ordering::Relaxed)
+        );
+
+        out.push_str(
+            "# HELP megaviz_poller_catching_up Whether the poller is in batch catch-up mode\n",
+        );
+        out.push_str("# TYPE megaviz_poller_catching_up gauge\n");
+        let _ = writeln!(
+            out,
+            "megaviz_poller_catching_up {}",
+            u8::from(self.poller_catching_up.load(Ordering::Relaxed))
+        );
+
+        out.push_str("# HELP megaviz_ws_connections Currently connected WebSocket clients\n");
+        out.push_str("# TYPE megaviz_ws_connections gauge\n");
+        let _ = writeln!(
+            out,
+            "megaviz_ws_connections {}",
+            self.ws_connections.load(Ordering::Relaxed).max(0)
+        );
+
+        #[cfg(feature = "replay")]
+        {
+            let stats = crate::replay::replay_stats();
+            out.push_str("# HELP megaviz_replay_attempts_total Transactions submitted to replay\n");
+            out.push_str("# TYPE megaviz_replay_attempts_total counter\n");
+            let _ = writeln!(out, "megaviz_replay_attempts_total {}", stats.attempted());
+            out.push_str(
+                "# HELP megaviz_replay_failures_total Replays that fell back to estimation\n",
+            );
+            out.push_str("# TYPE megaviz_replay_failures_total counter\n");
+            let _ = writeln!(out, "megaviz_replay_failures_total {}", stats.failed());
+            out.push_str(
+                "# HELP megaviz_replay_failure_rate Fraction of replay attempts that failed\n",
+            );
+            out.push_str("# TYPE megaviz_replay_failure_rate gauge\n");
+            let _ = writeln!(out, "megaviz_replay_failure_rate {}", stats.failure_rate());
+        }
+
+        out
+    }
+}
+
+/// The process-wide telemetry registry
+///
+/// Global rather than threaded through constructors because the RPC client
+/// is created in several binaries that don't carry an AppState.
+pub fn telemetry() -> &'static Telemetry {
+    static REGISTRY: OnceLock<Telemetry> = OnceLock::new();
+    REGISTRY.get_or_init(Telemetry::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_contains_recorded_rpc_method() {
+        let t = Telemetry::default();
+        t.record_rpc_call("eth_getBlockByNumber", Duration::from_millis(30), true);
+        t.record_rpc_call("eth_getBlockByNumber", Duration::from_millis(300), false);
+        t.record_block_processed();
+        t.set_poller_lag(5);
+
+        let text = t.render();
+        assert!(text.contains("megaviz_rpc_requests_total{method=\"eth_getBlockByNumber\"} 2"));
+        assert!(text.contains("megaviz_rpc_errors_total{method=\"eth_getBlockByNumber\"} 1"));
+        // 30ms lands in the 0.05 bucket but not 0.025
+        assert!(text.contains("le=\"0.025\"} 0"));
+        assert!(text.contains("le=\"0.05\"} 1"));
+        assert!(text.contains("le=\"+Inf\"} 2"));
+        assert!(text.contains("megaviz_blocks_processed_total 1"));
+        assert!(text.contains("megaviz_poller_lag_blocks 5"));
+        assert!(text.contains("megaviz_poller_catching_up 0"));
+        t.set_catching_up(true);
+        assert!(t.render().contains("megaviz_poller_catching_up 1"));
+    }
+}